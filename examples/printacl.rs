@@ -2,35 +2,17 @@
 //!
 //! Run with command: `cargo run --example printacl /some/path`
 
-use posix_acl::Qualifier::*;
-use posix_acl::{ACLEntry, ACLError, PosixACL, ACL_EXECUTE, ACL_READ, ACL_WRITE};
+use posix_acl::{ACLError, PosixACL};
 use std::env::args_os;
 use std::path::Path;
 use std::process::exit;
 
-fn print_acl(acl: PosixACL) {
-    let entries = acl.entries();
-    if entries.is_empty() {
+fn print_acl(acl: &PosixACL) {
+    if acl.is_empty() {
         // If no "default" ACL has been set on directories, they have 0 entries.
-        println!("    (no entries)")
-    }
-    for entry in entries {
-        let ACLEntry { qual, perm } = entry;
-        println!(
-            "    {}:{}{}{}",
-            match qual {
-                Undefined => "invalid:".into(),
-                UserObj => "user:".into(),
-                GroupObj => "group:".into(),
-                Other => "other:".into(),
-                User(uid) => format!("user:{}", uid),
-                Group(gid) => format!("group:{}", gid),
-                Mask => "mask:".into(),
-            },
-            if perm & ACL_READ != 0 { "r" } else { "-" },
-            if perm & ACL_WRITE != 0 { "w" } else { "-" },
-            if perm & ACL_EXECUTE != 0 { "x" } else { "-" },
-        )
+        println!("(no entries)");
+    } else {
+        print!("{acl}");
     }
 }
 
@@ -39,7 +21,7 @@ fn handle_acl_result(result: Result<PosixACL, ACLError>, path: &Path, type_hint:
     match result {
         Ok(acl) => {
             println!("{} {}:", path.display(), type_hint);
-            print_acl(acl);
+            print_acl(&acl);
             0
         }
         Err(e) => {
@@ -0,0 +1,320 @@
+//! Evaluating ACLs like the kernel does, for access checks in userspace.
+use crate::text::{numeric_entry_text, perm_text};
+use crate::Qualifier::{Group, GroupObj, Mask, Other, User, UserObj};
+use crate::{
+    ACLEntry, PosixACL, Qualifier, ACL_EXECUTE, ACL_READ, ACL_WRITE, CAP_DAC_OVERRIDE,
+    CAP_DAC_READ_SEARCH,
+};
+use std::fmt;
+
+/// Principal whose access is listed by [`PosixACL::access_report()`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Principal {
+    /// Owner of the file, with UID if known.
+    Owner(Option<u32>),
+    /// Members of the owning group of the file, with GID if known.
+    OwningGroup(Option<u32>),
+    /// User with UID `u32` value
+    User(u32),
+    /// Members of group with GID `u32` value
+    Group(u32),
+    /// Everyone else not covered by the ACL
+    Other,
+}
+
+/// Step of the access check algorithm that decided an access check, see
+/// [`PosixACL::allows()`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AccessClass {
+    /// The process is the file owner, the `UserObj` entry applies.
+    Owner,
+    /// A `User` entry matches the process.
+    User,
+    /// The `GroupObj` or `Group` entries of the groups of the process apply.
+    Group,
+    /// No other entry matches the process, the `Other` entry applies.
+    Other,
+}
+
+/// Why an access check granted or denied access, returned by
+/// [`PosixACL::explain_access()`].
+///
+/// The `Display` implementation describes the decision in a sentence, e.g.
+/// `access denied by user:1234:rw- limited by mask::r--`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct AccessExplanation {
+    /// Whether access is granted, same as the result of [`PosixACL::allows()`].
+    pub allowed: bool,
+    /// The requested permissions.
+    pub want: u32,
+    /// The step of the access check algorithm that applied.
+    pub class: AccessClass,
+    /// Entries matching the process, with permissions before applying the mask. For
+    /// [`AccessClass::Group`], these are all matching group entries. Empty if the ACL lacks the
+    /// entry that applies, which denies access.
+    pub matched: Vec<ACLEntry>,
+    /// Permissions of the `Mask` entry, if it limits the matched entries. `None` for
+    /// [`AccessClass::Owner`] and [`AccessClass::Other`], which are not limited by the mask.
+    pub mask: Option<u32>,
+}
+
+impl AccessExplanation {
+    /// Return `true` if access was denied only because of the mask: one of the matched entries
+    /// grants all requested permissions, but not after applying the mask.
+    #[must_use]
+    pub fn masked(&self) -> bool {
+        let want = self.want;
+        match self.mask {
+            Some(mask) if !self.allowed => self
+                .matched
+                .iter()
+                .any(|entry| entry.perm & want == want && entry.perm & mask & want != want),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for AccessExplanation {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verdict = if self.allowed { "granted" } else { "denied" };
+        if self.matched.is_empty() {
+            let missing = match self.class {
+                AccessClass::Owner => "user::",
+                AccessClass::User => "user",
+                AccessClass::Group => "group",
+                AccessClass::Other => "other::",
+            };
+            return write!(fmt, "access {verdict} by missing {missing} entry");
+        }
+        let entries: Vec<String> = self
+            .matched
+            .iter()
+            .map(|&e| numeric_entry_text(e))
+            .collect();
+        write!(fmt, "access {verdict} by {}", entries.join(", "))?;
+        if let Some(mask) = self.mask {
+            write!(fmt, " limited by mask::{}", perm_text(mask, 0))?;
+        }
+        if self.class == AccessClass::Other {
+            fmt.write_str(" (no user or group entry matches)")?;
+        }
+        Ok(())
+    }
+}
+
+impl PosixACL {
+    /// Check whether a process with user `uid` and groups `gids` is granted all of the `want`
+    /// permissions on a file owned by `file_owner` and `file_group`, using the POSIX ACL access
+    /// check algorithm (see `acl(5)`):
+    ///
+    /// 1. If `uid` is the file owner, the `UserObj` entry applies.
+    /// 2. Otherwise, if there is a `User` entry for `uid`, it applies, limited by the `Mask`.
+    /// 3. Otherwise, if any of `gids` matches the `GroupObj` (via `file_group`) or a `Group`
+    ///    entry, access is granted if any of the matching entries, limited by the `Mask`, grants
+    ///    all of `want`. If none does, access is denied.
+    /// 4. Otherwise, the `Other` entry applies.
+    ///
+    /// `gids` should contain both the primary and supplementary groups of the process. Missing
+    /// entries are treated as having no permissions. Privileges such as root's `CAP_DAC_OVERRIDE`
+    /// are not taken into account, see [`allows_privileged()`](Self::allows_privileged).
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ, ACL_WRITE};
+    /// let mut acl = PosixACL::new(0o640);
+    /// acl.set(Qualifier::User(1234), ACL_READ | ACL_WRITE);
+    /// acl.fix_mask();
+    ///
+    /// assert!(acl.allows(1234, &[1234], ACL_READ | ACL_WRITE, 0, 0));
+    /// assert!(!acl.allows(5678, &[5678], ACL_READ, 0, 0));
+    /// ```
+    ///
+    /// Use [`explain_access()`](Self::explain_access) to find out which entry decided.
+    #[must_use]
+    pub fn allows(
+        &self,
+        uid: u32,
+        gids: &[u32],
+        want: u32,
+        file_owner: u32,
+        file_group: u32,
+    ) -> bool {
+        self.explain_access(uid, gids, want, file_owner, file_group)
+            .allowed
+    }
+
+    /// Like [`allows()`](Self::allows), but also take capabilities of the process into account
+    /// that override permission checks, like the Linux kernel does. `caps` is a combination of
+    /// [`CAP_DAC_OVERRIDE`](crate::CAP_DAC_OVERRIDE) and
+    /// [`CAP_DAC_READ_SEARCH`](crate::CAP_DAC_READ_SEARCH), and `is_dir` tells whether the file is
+    /// a directory. If the ACL denies access:
+    ///
+    /// * `CAP_DAC_READ_SEARCH` grants read permission, and execute (search) permission on
+    ///   directories.
+    /// * `CAP_DAC_OVERRIDE` grants all permissions on directories. On other files, it grants read
+    ///   and write permission, and execute permission only if the file mode has at least one
+    ///   execute bit set, see [`reported_mode()`](Self::reported_mode).
+    ///
+    /// Root usually has both capabilities. `CAP_FOWNER` is not needed here: it only bypasses
+    /// ownership checks, e.g. for changing the ACL, and never grants access. The kernel
+    /// additionally requires that the owner and group of the file are mapped in the user
+    /// namespace of the process, which is not checked.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, ACL_EXECUTE, ACL_READ, ACL_WRITE, CAP_DAC_OVERRIDE};
+    /// let acl = PosixACL::new(0o600);
+    /// let root = |want| acl.allows_privileged(0, &[0], want, 1000, 1000, false, CAP_DAC_OVERRIDE);
+    ///
+    /// assert!(root(ACL_READ | ACL_WRITE));
+    /// // No execute bit is set in the mode
+    /// assert!(!root(ACL_EXECUTE));
+    /// ```
+    #[must_use]
+    #[allow(clippy::too_many_arguments)]
+    pub fn allows_privileged(
+        &self,
+        uid: u32,
+        gids: &[u32],
+        want: u32,
+        file_owner: u32,
+        file_group: u32,
+        is_dir: bool,
+        caps: u32,
+    ) -> bool {
+        if self.allows(uid, gids, want, file_owner, file_group) {
+            return true;
+        }
+        let want = want & (ACL_READ | ACL_WRITE | ACL_EXECUTE);
+        if is_dir {
+            return (want & ACL_WRITE == 0 && caps & CAP_DAC_READ_SEARCH != 0)
+                || caps & CAP_DAC_OVERRIDE != 0;
+        }
+        if want == ACL_READ && caps & CAP_DAC_READ_SEARCH != 0 {
+            return true;
+        }
+        (want & ACL_EXECUTE == 0 || self.reported_mode() & 0o111 != 0)
+            && caps & CAP_DAC_OVERRIDE != 0
+    }
+
+    /// Like [`allows()`](Self::allows), but explain the decision: which step of the algorithm
+    /// applied, which entries matched, and whether the mask limited them. Useful for debugging
+    /// "permission denied" errors.
+    ///
+    /// ```
+    /// use posix_acl::{AccessClass, PosixACL, Qualifier, ACL_READ, ACL_WRITE};
+    /// let mut acl = PosixACL::new(0o640);
+    /// acl.set(Qualifier::User(1234), ACL_READ | ACL_WRITE);
+    /// acl.set(Qualifier::Mask, ACL_READ);
+    ///
+    /// let explanation = acl.explain_access(1234, &[1234], ACL_WRITE, 0, 0);
+    /// assert!(!explanation.allowed);
+    /// assert_eq!(explanation.class, AccessClass::User);
+    /// assert!(explanation.masked());
+    /// assert_eq!(
+    ///     explanation.to_string(),
+    ///     "access denied by user:1234:rw- limited by mask::r--"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn explain_access(
+        &self,
+        uid: u32,
+        gids: &[u32],
+        want: u32,
+        file_owner: u32,
+        file_group: u32,
+    ) -> AccessExplanation {
+        let entries = self.entries();
+        let mask = entries
+            .iter()
+            .find(|entry| entry.qual == Mask)
+            .map(|entry| entry.perm);
+        let explain = |class, matched: Vec<ACLEntry>, mask: Option<u32>| {
+            let limit = mask.unwrap_or(!0);
+            let allowed = matched
+                .iter()
+                .any(|entry| entry.perm & limit & want == want);
+            AccessExplanation {
+                allowed,
+                want,
+                class,
+                matched,
+                mask,
+            }
+        };
+        let find = |qual: Qualifier| -> Vec<ACLEntry> {
+            entries.iter().copied().filter(|e| e.qual == qual).collect()
+        };
+
+        if uid == file_owner {
+            return explain(AccessClass::Owner, find(UserObj), None);
+        }
+        let user = find(User(uid));
+        if !user.is_empty() {
+            return explain(AccessClass::User, user, mask);
+        }
+        let groups: Vec<ACLEntry> = entries
+            .iter()
+            .copied()
+            .filter(|entry| match entry.qual {
+                GroupObj => gids.contains(&file_group),
+                Group(gid) => gids.contains(&gid),
+                _ => false,
+            })
+            .collect();
+        if !groups.is_empty() {
+            return explain(AccessClass::Group, groups, mask);
+        }
+        explain(AccessClass::Other, find(Other), None)
+    }
+
+    /// List every principal granted access by the ACL with their effective permissions, i.e.
+    /// limited by the `Mask` as described in [`effective()`](Self::effective). This answers
+    /// questions like "who can write this file".
+    ///
+    /// If `file_owner` is given, a `User` entry for the owner is omitted, since the `UserObj`
+    /// entry takes precedence over it. Entries with no effective permissions are included,
+    /// `Mask` and unrecognized entries are not.
+    ///
+    /// Note that a process belonging to multiple groups is granted access by any matching group
+    /// entry, see [`allows()`](Self::allows).
+    ///
+    /// ```
+    /// use posix_acl::{Principal, PosixACL, Qualifier, ACL_READ, ACL_WRITE};
+    /// let mut acl = PosixACL::new(0o640);
+    /// acl.set(Qualifier::User(1234), ACL_READ | ACL_WRITE);
+    /// acl.set(Qualifier::Mask, ACL_READ);
+    ///
+    /// let writers: Vec<Principal> = acl
+    ///     .access_report(Some(1000), None)
+    ///     .into_iter()
+    ///     .filter(|&(_, perm)| perm & ACL_WRITE != 0)
+    ///     .map(|(principal, _)| principal)
+    ///     .collect();
+    /// assert_eq!(writers, vec![Principal::Owner(Some(1000))]);
+    /// ```
+    #[must_use]
+    pub fn access_report(
+        &self,
+        file_owner: Option<u32>,
+        file_group: Option<u32>,
+    ) -> Vec<(Principal, u32)> {
+        self.effective_entries()
+            .into_iter()
+            .filter_map(|entry| {
+                let principal = match entry.qual {
+                    UserObj => Principal::Owner(file_owner),
+                    GroupObj => Principal::OwningGroup(file_group),
+                    User(uid) if Some(uid) == file_owner => return None,
+                    User(uid) => Principal::User(uid),
+                    Group(gid) => Principal::Group(gid),
+                    Other => Principal::Other,
+                    Qualifier::Mask | Qualifier::Undefined | Qualifier::Unknown(_) => return None,
+                };
+                Some((principal, entry.perm))
+            })
+            .collect()
+    }
+}
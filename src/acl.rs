@@ -1,22 +1,38 @@
-use crate::error::{ACLError, FLAG_WRITE};
-use crate::iter::RawACLIterator;
-use crate::util::{check_pointer, check_return, path_to_cstring, AutoPtr};
-use crate::Qualifier::{GroupObj, Other, UserObj};
-use crate::{ACLEntry, Qualifier, ACL_RWX};
+use crate::capabilities;
+use crate::error::{ACLError, FLAG_DELETE, FLAG_WRITE};
+use crate::instrument::{self, Timer};
+use crate::iter::{ACLIter, ACLIterMut, RawACLIterator};
+use crate::util::{or_panic, path_to_cstring, try_pointer, try_return, AutoPtr};
+use crate::Qualifier::{Group, GroupObj, Mask, Other, User, UserObj};
+use crate::{xattr, ACLEntry, Qualifier, ValidationReason, ACL_RWX};
 use acl_sys::{
-    acl_add_perm, acl_calc_mask, acl_clear_perms, acl_create_entry, acl_delete_entry, acl_entry_t,
-    acl_get_file, acl_get_permset, acl_init, acl_permset_t, acl_set_file, acl_set_permset,
-    acl_set_qualifier, acl_set_tag_type, acl_t, acl_to_text, acl_type_t, acl_valid,
+    acl_add_perm, acl_calc_mask, acl_clear_perms, acl_copy_entry, acl_create_entry,
+    acl_delete_def_file, acl_delete_entry, acl_dup, acl_entry_t, acl_from_text, acl_get_fd,
+    acl_get_file, acl_get_permset, acl_init, acl_permset_t, acl_set_fd, acl_set_file,
+    acl_set_permset, acl_set_qualifier, acl_set_tag_type, acl_t, acl_to_text, acl_type_t,
+    acl_valid, ACL_DUPLICATE_ERROR, ACL_ENTRY_ERROR, ACL_MISS_ERROR, ACL_MULTI_ERROR,
     ACL_TYPE_ACCESS, ACL_TYPE_DEFAULT,
 };
 use libc::ssize_t;
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
-use std::os::raw::c_void;
+use std::ffi::CString;
+use std::fs::{self, Permissions};
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::iter::FromIterator;
+use std::ops::Index;
+use std::os::raw::{c_char, c_int, c_void};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::io::AsRawFd;
 use std::path::Path;
 use std::ptr::{addr_of, null_mut};
 use std::slice::from_raw_parts;
-use std::str::from_utf8;
-use std::{fmt, mem};
+use std::str::{from_utf8, FromStr};
+use std::{fmt, io, mem};
+
+/// Flags for errors from modifying an ACL in memory.
+const FLAGS_MODIFY: u32 = FLAG_WRITE | ACL_TYPE_ACCESS;
 
 /// The ACL of a file.
 ///
@@ -26,6 +42,8 @@ use std::{fmt, mem};
 #[allow(clippy::upper_case_acronyms)]
 pub struct PosixACL {
     pub(crate) acl: acl_t,
+    /// Generation counter of libacl's internal entry cursor, see [`RawACLIterator`].
+    pub(crate) cursor: Cell<u64>,
 }
 
 /// Custom debug formatting, since output `PosixACL { acl: 0x7fd74c000ca8 }` is not very helpful.
@@ -38,18 +56,271 @@ impl fmt::Debug for PosixACL {
     }
 }
 
+/// Formats the ACL in the long text form used by `getfacl`, one entry per line, same as
+/// [`PosixACL::as_text()`].
+///
+/// ```
+/// use posix_acl::PosixACL;
+/// let acl = PosixACL::new(0o640);
+/// assert_eq!(acl.to_string(), "user::rw-\ngroup::r--\nother::---\n");
+/// ```
+impl fmt::Display for PosixACL {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str(&self.as_text())
+    }
+}
+
+/// Duplicates the ACL using `acl_dup()`.
+///
+/// # Panics
+///
+/// When memory allocation fails, use [`PosixACL::try_clone()`] to handle errors.
+impl Clone for PosixACL {
+    fn clone(&self) -> Self {
+        or_panic(self.try_clone())
+    }
+}
+
 impl Drop for PosixACL {
     fn drop(&mut self) {
         AutoPtr(self.acl);
     }
 }
 
+/// ACLs can be moved to other threads: `acl_t` points to plain heap memory owned exclusively by
+/// this `PosixACL`, and libacl keeps no thread-local or global state about it.
+///
+/// `PosixACL` is NOT `Sync`: even reading entries through `&self` moves libacl's internal entry
+/// cursor stored in the ACL, which is not synchronized.
+///
+/// ```compile_fail
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<posix_acl::PosixACL>();
+/// ```
+unsafe impl Send for PosixACL {}
+
+/// Compares ACLs using `acl_cmp()` where available, without allocating.
 impl PartialEq for PosixACL {
     fn eq(&self, other: &Self) -> bool {
-        self.entries() == other.entries()
+        match unsafe { capabilities::acl_cmp(self.acl, other.acl) } {
+            Some(0) => true,
+            Some(1) => false,
+            // acl_cmp() refuses malformed ACLs or is not available, compare entry by entry instead.
+            _ => self.iter().eq(other.iter()),
+        }
+    }
+}
+
+impl Eq for PosixACL {}
+
+/// Hashes the entries in canonical order, see [`PosixACL::entries_sorted()`], so that equal ACLs
+/// have equal hashes. This allows grouping files with identical ACLs:
+///
+/// ```
+/// use posix_acl::PosixACL;
+/// use std::collections::HashMap;
+/// let mut files: HashMap<PosixACL, Vec<&str>> = HashMap::new();
+/// for path in ["/etc/hosts", "/etc/shells", "/etc/passwd"] {
+///     files.entry(PosixACL::read_acl(path).unwrap()).or_default().push(path);
+/// }
+/// assert!(files.len() <= 3);
+/// ```
+///
+/// The hash does not depend on libacl's internal entry cursor, but Clippy's `mutable_key_type`
+/// lint cannot know that and warns about using `PosixACL` as a key. The `Vec<ACLEntry>` returned
+/// by `entries_sorted()` can be used as the key instead.
+impl Hash for PosixACL {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.entries_sorted().hash(state);
+    }
+}
+
+/// Convert file permissions into a minimal ACL, see [`PosixACL::new()`].
+///
+/// ```
+/// use posix_acl::PosixACL;
+/// let perms = std::fs::metadata("/etc/shells").unwrap().permissions();
+/// let acl = PosixACL::from(perms);
+/// ```
+impl From<Permissions> for PosixACL {
+    fn from(perms: Permissions) -> Self {
+        PosixACL::new(perms.mode())
+    }
+}
+
+/// The minimal ACL for mode `0o644` (owner can read and write, everyone else can read), the
+/// usual permissions of newly created files with the common umask `022`. Note that this is a
+/// valid ACL, unlike [`PosixACL::empty()`].
+///
+/// ```
+/// use posix_acl::PosixACL;
+/// assert_eq!(PosixACL::default(), PosixACL::new(0o644));
+/// ```
+impl Default for PosixACL {
+    fn default() -> Self {
+        PosixACL::new(0o644)
+    }
+}
+
+/// Parses the long text form (one entry per line), as well as the short form accepted by
+/// `setfacl`, such as `u::rwx,g::r-x,o::---,u:alice:rw`. See [`PosixACL::from_text()`].
+///
+/// ```
+/// use posix_acl::{PosixACL, Qualifier, ACL_READ, ACL_WRITE};
+/// let acl: PosixACL = "u::rwx,g::r-x,o::---,u:0:rw".parse().unwrap();
+/// assert_eq!(acl.get(Qualifier::User(0)), Some(ACL_READ | ACL_WRITE));
+/// ```
+impl FromStr for PosixACL {
+    type Err = ACLError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PosixACL::from_text(s)
+    }
+}
+
+/// Build an ACL from entries, starting from an empty ACL. Later entries for the same qualifier
+/// replace earlier ones, like [`PosixACL::set()`].
+///
+/// ```
+/// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+/// let template = PosixACL::new(0o640);
+/// let acl: PosixACL = template
+///     .iter()
+///     .map(|entry| (entry.qual, entry.perm & ACL_READ))
+///     .collect();
+/// assert_eq!(acl, PosixACL::new(0o440));
+/// ```
+///
+/// # Panics
+///
+/// When adding a `Qualifier::Unknown` entry, libacl does not allow creating those.
+impl FromIterator<(Qualifier, u32)> for PosixACL {
+    fn from_iter<I: IntoIterator<Item = (Qualifier, u32)>>(iter: I) -> Self {
+        let mut acl = PosixACL::empty();
+        acl.extend(iter);
+        acl
+    }
+}
+
+/// See `FromIterator<(Qualifier, u32)>`.
+impl FromIterator<ACLEntry> for PosixACL {
+    fn from_iter<I: IntoIterator<Item = ACLEntry>>(iter: I) -> Self {
+        let mut acl = PosixACL::empty();
+        acl.extend(iter);
+        acl
+    }
+}
+
+/// Set permissions of each entry, like [`PosixACL::set()`].
+///
+/// ```
+/// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+/// let mut acl = PosixACL::new(0o640);
+/// acl.extend([1000, 1001].iter().map(|&uid| (Qualifier::User(uid), ACL_READ)));
+/// assert_eq!(acl.get(Qualifier::User(1001)), Some(ACL_READ));
+/// ```
+///
+/// # Panics
+///
+/// When adding a `Qualifier::Unknown` entry, libacl does not allow creating those.
+impl Extend<(Qualifier, u32)> for PosixACL {
+    fn extend<I: IntoIterator<Item = (Qualifier, u32)>>(&mut self, iter: I) {
+        for (qual, perm) in iter {
+            self.set(qual, perm);
+        }
+    }
+}
+
+/// See `Extend<(Qualifier, u32)>`.
+impl Extend<ACLEntry> for PosixACL {
+    fn extend<I: IntoIterator<Item = ACLEntry>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().map(|entry| (entry.qual, entry.perm)));
+    }
+}
+
+/// Convert the ACL into a map of qualifiers to permissions.
+///
+/// ```
+/// use posix_acl::{PosixACL, Qualifier, ACL_READ, ACL_WRITE};
+/// use std::collections::BTreeMap;
+/// let map = BTreeMap::from(PosixACL::new(0o640));
+/// assert_eq!(map[&Qualifier::UserObj], ACL_READ | ACL_WRITE);
+/// ```
+impl From<PosixACL> for BTreeMap<Qualifier, u32> {
+    fn from(acl: PosixACL) -> Self {
+        acl.iter().map(|entry| (entry.qual, entry.perm)).collect()
+    }
+}
+
+/// Convert the ACL into a map of qualifiers to permissions.
+#[allow(clippy::implicit_hasher)]
+impl From<PosixACL> for HashMap<Qualifier, u32> {
+    fn from(acl: PosixACL) -> Self {
+        acl.iter().map(|entry| (entry.qual, entry.perm)).collect()
     }
 }
 
+/// Build an ACL from a map of qualifiers to permissions. The result must pass
+/// [`PosixACL::validate()`], use `FromIterator` to build ACLs without validation.
+///
+/// ```
+/// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+/// use std::collections::BTreeMap;
+/// use std::convert::TryFrom;
+/// let mut map = BTreeMap::from(PosixACL::new(0o640));
+/// map.insert(Qualifier::User(1234), ACL_READ);
+/// map.insert(Qualifier::Mask, ACL_READ);
+/// let acl = PosixACL::try_from(map).unwrap();
+/// assert_eq!(acl.get(Qualifier::User(1234)), Some(ACL_READ));
+/// ```
+///
+/// # Errors
+/// * `ACLError::ValidationError`: The ACL failed validation, or the map contains
+///   `Qualifier::Undefined` or `Qualifier::Unknown` entries, which cannot be created.
+impl TryFrom<BTreeMap<Qualifier, u32>> for PosixACL {
+    type Error = ACLError;
+
+    fn try_from(map: BTreeMap<Qualifier, u32>) -> Result<Self, Self::Error> {
+        PosixACL::try_from_entries(map)
+    }
+}
+
+/// See `TryFrom<BTreeMap<Qualifier, u32>>`.
+impl<S: BuildHasher> TryFrom<HashMap<Qualifier, u32, S>> for PosixACL {
+    type Error = ACLError;
+
+    fn try_from(map: HashMap<Qualifier, u32, S>) -> Result<Self, Self::Error> {
+        PosixACL::try_from_entries(map)
+    }
+}
+
+/// Get the permissions of `qual`, like [`PosixACL::get()`]. Convenient for the required
+/// entries `UserObj`, `GroupObj` and `Other`, which are present in all valid ACLs.
+///
+/// ```
+/// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+/// let acl = PosixACL::new(0o640);
+/// assert_eq!(acl[Qualifier::GroupObj], ACL_READ);
+/// ```
+///
+/// # Panics
+///
+/// When the ACL has no entry for `qual`, like indexing a `HashMap`. Also when reading the UID/GID
+/// of an entry fails, see [`PosixACL::try_get()`].
+impl Index<Qualifier> for PosixACL {
+    type Output = u32;
+
+    fn index(&self, qual: Qualifier) -> &u32 {
+        match or_panic(self.try_get(qual)) {
+            Some(perm) => &PERMS[(perm & ACL_RWX) as usize],
+            None => panic!("ACL has no {:?} entry", qual),
+        }
+    }
+}
+
+/// All possible permission values, for returning references from [`Index`].
+static PERMS: [u32; 8] = [0, 1, 2, 3, 4, 5, 6, 7];
+
 impl PosixACL {
     /// Convert a file mode ("chmod" number) into a "minimal" ACL. This is the primary constructor.
     /// Note that modes are usually expressed in octal, e.g. `PosixACL::new(0o644)`
@@ -60,6 +331,10 @@ impl PosixACL {
     ///
     /// Input bits higher than 9 (e.g. SUID flag, etc) are ignored.
     ///
+    /// Uses the platform's `acl_from_mode()` where available, so the result matches what the
+    /// platform considers the minimal ACL for the mode. A `std::fs::Permissions` value can be
+    /// converted with `From`.
+    ///
     /// ```
     /// use posix_acl::PosixACL;
     /// assert_eq!(
@@ -67,13 +342,30 @@ impl PosixACL {
     ///     "user::rwx\ngroup::r-x\nother::--x\n"
     /// );
     /// ```
+    ///
+    /// # Panics
+    ///
+    /// When the platform fails to allocate memory, use [`try_new()`](Self::try_new) to handle
+    /// errors.
     #[must_use]
     pub fn new(file_mode: u32) -> PosixACL {
-        let mut acl = PosixACL::empty();
-        acl.set(UserObj, (file_mode >> 6) & ACL_RWX);
-        acl.set(GroupObj, (file_mode >> 3) & ACL_RWX);
-        acl.set(Other, file_mode & ACL_RWX);
-        acl
+        or_panic(PosixACL::try_new(file_mode))
+    }
+
+    /// Like [`new()`](Self::new), but return an error instead of panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to allocate memory.
+    pub fn try_new(file_mode: u32) -> Result<PosixACL, ACLError> {
+        if let Some(acl) = unsafe { capabilities::acl_from_mode(file_mode) } {
+            try_pointer(acl, FLAGS_MODIFY)?;
+            return Ok(unsafe { PosixACL::from_raw(acl) });
+        }
+        let mut acl = PosixACL::try_with_capacity(3)?;
+        acl.try_set(UserObj, (file_mode >> 6) & ACL_RWX)?;
+        acl.try_set(GroupObj, (file_mode >> 3) & ACL_RWX)?;
+        acl.try_set(Other, file_mode & ACL_RWX)?;
+        Ok(acl)
     }
 
     /// Create an empty ACL. NB! Empty ACLs are NOT considered valid.
@@ -83,12 +375,72 @@ impl PosixACL {
     }
 
     /// Create an empty ACL with capacity. NB! Empty ACLs are NOT considered valid.
+    ///
+    /// The capacity is only a hint passed to `acl_init()`. libacl on Linux allocates each entry
+    /// separately and ignores it, so there is no need to reserve capacity before adding many
+    /// entries, and no way to query it.
+    ///
+    /// # Panics
+    ///
+    /// When the platform fails to allocate memory, use
+    /// [`try_with_capacity()`](Self::try_with_capacity) to handle errors.
     #[must_use]
     pub fn with_capacity(capacity: usize) -> PosixACL {
+        or_panic(PosixACL::try_with_capacity(capacity))
+    }
+
+    /// Like [`with_capacity()`](Self::with_capacity), but return an error instead of panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to allocate memory.
+    pub fn try_with_capacity(capacity: usize) -> Result<PosixACL, ACLError> {
         let capacity = i32::try_from(capacity).unwrap_or(i32::MAX);
         let acl = unsafe { acl_init(capacity) };
-        check_pointer(acl, "acl_init");
-        PosixACL { acl }
+        try_pointer(acl, FLAGS_MODIFY)?;
+        Ok(unsafe { PosixACL::from_raw(acl) })
+    }
+
+    /// Like [`clone()`](Clone::clone), but return an error instead of panicking.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+    /// let mut acl = PosixACL::new(0o640);
+    /// let snapshot = acl.try_clone().unwrap();
+    /// acl.set(Qualifier::Other, ACL_READ);
+    /// assert_eq!(snapshot, PosixACL::new(0o640));
+    /// ```
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to allocate memory.
+    pub fn try_clone(&self) -> Result<PosixACL, ACLError> {
+        let acl = unsafe { acl_dup(self.acl) };
+        try_pointer(acl, FLAGS_MODIFY)?;
+        Ok(unsafe { PosixACL::from_raw(acl) })
+    }
+
+    /// Parse an ACL from its textual representation, as produced by [`as_text()`](Self::as_text)
+    /// and `getfacl`. Entries are separated by newlines or commas.
+    ///
+    /// User and group names are resolved to UID/GID by the platform.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_RWX};
+    /// let acl = PosixACL::from_text("user::rw-\ngroup::r--\nother::---\nuser:1000:rwx").unwrap();
+    /// assert_eq!(acl.get(Qualifier::User(1000)), Some(ACL_RWX));
+    /// ```
+    ///
+    /// # Errors
+    /// * `ACLError::ParseError`: The text is not a valid ACL, or refers to unknown user/group
+    ///   names.
+    pub fn from_text(text: &str) -> Result<PosixACL, ACLError> {
+        let c_text = CString::new(text)
+            .map_err(|_| ACLError::parse_error("Invalid ACL text: contains NUL byte"))?;
+        let acl = unsafe { acl_from_text(c_text.as_ptr()) };
+        if acl.is_null() {
+            Err(ACLError::parse_error(format!("Invalid ACL text {text:?}")))
+        } else {
+            Ok(unsafe { PosixACL::from_raw(acl) })
+        }
     }
 
     /// Read a path's access ACL and return as `PosixACL` object.
@@ -128,13 +480,106 @@ impl PosixACL {
         Self::read_acl_flags(path.as_ref(), ACL_TYPE_DEFAULT)
     }
 
-    fn read_acl_flags(path: &Path, flags: acl_type_t) -> Result<PosixACL, ACLError> {
+    pub(crate) fn read_acl_flags(path: &Path, flags: acl_type_t) -> Result<PosixACL, ACLError> {
+        let timer = Timer::start();
         let c_path = path_to_cstring(path);
         let acl: acl_t = unsafe { acl_get_file(c_path.as_ptr(), flags) };
-        if acl.is_null() {
-            Err(ACLError::last_os_error(flags))
+        let result = if acl.is_null() {
+            Err(ACLError::last_os_error(flags).with_path(path))
+        } else {
+            Ok(unsafe { PosixACL::from_raw(acl) })
+        };
+        timer.finish("read", &result);
+        result
+    }
+
+    /// Read the access ACL of an open file and return as `PosixACL` object.
+    ///
+    /// Unlike [`read_acl()`](Self::read_acl), this does not re-resolve the path, avoiding races
+    /// with renames and the cost of path lookup.
+    /// ```
+    /// use posix_acl::PosixACL;
+    /// let file = std::fs::File::open("/etc/shells").unwrap();
+    /// let acl = PosixACL::read_acl_fd(&file).unwrap();
+    /// ```
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (permission denied, etc).
+    pub fn read_acl_fd<F: AsRawFd + ?Sized>(file: &F) -> Result<PosixACL, ACLError> {
+        let timer = Timer::start();
+        let acl: acl_t = unsafe { acl_get_fd(file.as_raw_fd()) };
+        let result = if acl.is_null() {
+            Err(ACLError::last_os_error(ACL_TYPE_ACCESS))
         } else {
-            Ok(PosixACL { acl })
+            Ok(unsafe { PosixACL::from_raw(acl) })
+        };
+        timer.finish("read", &result);
+        result
+    }
+
+    /// Check whether `path` has an extended ACL, i.e. an access ACL with entries beyond those
+    /// derived from the file mode, or a default ACL. Cheaper than reading the ACL.
+    /// ```
+    /// use posix_acl::PosixACL;
+    /// let extended = PosixACL::has_extended_acl("/etc/shells").unwrap();
+    /// ```
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    pub fn has_extended_acl<P: AsRef<Path>>(path: P) -> Result<bool, ACLError> {
+        let path = path.as_ref();
+        let c_path = path_to_cstring(path);
+        let ret =
+            unsafe { capabilities::acl_extended_file(c_path.as_ptr()) }.unwrap_or_else(|| {
+                Self::extended_from_xattrs(|name, size| unsafe {
+                    libc::getxattr(c_path.as_ptr(), name, null_mut(), size)
+                })
+            });
+        Self::extended_result(ret).map_err(|err| err.with_path(path))
+    }
+
+    /// Like [`has_extended_acl()`](Self::has_extended_acl), for an open file.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (permission denied, etc).
+    pub fn has_extended_acl_fd<F: AsRawFd + ?Sized>(file: &F) -> Result<bool, ACLError> {
+        let fd = file.as_raw_fd();
+        let ret = unsafe { capabilities::acl_extended_fd(fd) }.unwrap_or_else(|| {
+            Self::extended_from_xattrs(|name, size| unsafe {
+                libc::fgetxattr(fd, name, null_mut(), size)
+            })
+        });
+        Self::extended_result(ret)
+    }
+
+    /// Fallback for `acl_extended_file()` and `acl_extended_fd()`, with the same return value.
+    /// Like libacl, only compares the sizes of the ACL extended attributes, read with
+    /// `getxattr(name, size)`, to the size of a minimal ACL.
+    fn extended_from_xattrs(getxattr: impl Fn(*const c_char, usize) -> ssize_t) -> c_int {
+        // Header and the UserObj, GroupObj and Other entries in the xattr format.
+        const MINIMAL_SIZE: ssize_t = 4 + 3 * 8;
+        let attrs = [
+            (xattr::ACCESS, MINIMAL_SIZE + 1),
+            (xattr::DEFAULT, MINIMAL_SIZE),
+        ];
+        for &(name, extended_size) in &attrs {
+            let c_name = CString::new(name).unwrap();
+            let size = getxattr(c_name.as_ptr(), 0);
+            if size >= extended_size {
+                return 1;
+            }
+            if size < 0 && io::Error::last_os_error().raw_os_error() != Some(libc::ENODATA) {
+                return -1;
+            }
+        }
+        0
+    }
+
+    fn extended_result(ret: i32) -> Result<bool, ACLError> {
+        match ret {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(ACLError::last_os_error(ACL_TYPE_ACCESS)),
         }
     }
 
@@ -151,6 +596,138 @@ impl PosixACL {
         self.write_acl_flags(path.as_ref(), ACL_TYPE_ACCESS)
     }
 
+    /// Like [`write_acl()`](Self::write_acl), but first read the current access ACL of `path`
+    /// and only write if it differs. Returns `true` if the ACL was written.
+    ///
+    /// This avoids needless writes and ctime updates when applying the same ACL to many files.
+    /// ACLs without named `User` or `Group` entries are compared by their
+    /// [mode bits](Self::reported_mode), as the kernel does not store them as ACLs.
+    ///
+    /// ```no_run
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+    /// let mut acl = PosixACL::new(0o640);
+    /// acl.set(Qualifier::User(1234), ACL_READ);
+    /// acl.write_acl_if_changed("/srv/report.txt").unwrap();
+    /// // Second time is a no-op
+    /// assert!(!acl.write_acl_if_changed("/srv/report.txt").unwrap());
+    /// ```
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    /// * `ACLError::ValidationError`: The ACL failed validation. See [`PosixACL::validate()`] for
+    ///    more information.
+    pub fn write_acl_if_changed<P: AsRef<Path>>(&mut self, path: P) -> Result<bool, ACLError> {
+        let path = path.as_ref();
+        self.try_fix_mask()?;
+        self.validate()?;
+        let current = PosixACL::read_acl(path)?;
+        let unchanged = if self.has_named_entries() {
+            current == *self
+        } else {
+            !current.has_named_entries()
+                && current.try_reported_mode()? == self.try_reported_mode()?
+        };
+        if unchanged {
+            return Ok(false);
+        }
+        self.write_acl_exact(path, ACL_TYPE_ACCESS)?;
+        Ok(true)
+    }
+
+    /// Like [`write_acl()`](Self::write_acl), but only write if the current access ACL of `path`
+    /// still equals `expected`, usually the ACL as it was originally read. This prevents
+    /// overwriting changes made by other processes in the meantime.
+    ///
+    /// NB! The check and the write are separate system calls, so this narrows the window for
+    /// lost updates but does not close it entirely.
+    ///
+    /// ```no_run
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+    /// let original = PosixACL::read_acl("/srv/report.txt").unwrap();
+    /// let mut acl = original.clone();
+    /// acl.set(Qualifier::User(1234), ACL_READ);
+    /// acl.write_acl_if_unchanged("/srv/report.txt", &original).unwrap();
+    /// ```
+    ///
+    /// # Errors
+    /// * `ACLError::Conflict`: The ACL of `path` differs from `expected`, nothing was written.
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    /// * `ACLError::ValidationError`: The ACL failed validation. See [`PosixACL::validate()`] for
+    ///    more information.
+    pub fn write_acl_if_unchanged<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        expected: &PosixACL,
+    ) -> Result<(), ACLError> {
+        let path = path.as_ref();
+        self.try_fix_mask()?;
+        self.validate()?;
+        if PosixACL::read_acl(path)? != *expected {
+            return Err(ACLError::conflict(FLAG_WRITE | ACL_TYPE_ACCESS, path));
+        }
+        self.write_acl_exact(path, ACL_TYPE_ACCESS)
+    }
+
+    /// Like [`write_acl()`](Self::write_acl), but if the filesystem does not support ACLs,
+    /// change the file mode bits instead. Returns `true` if this fallback was used, meaning
+    /// that named `User` and `Group` entries were not applied.
+    ///
+    /// The permission bits are set to [`reported_mode()`](Self::reported_mode), like the kernel
+    /// would report for the ACL. Setuid, setgid and sticky bits of the file are kept.
+    ///
+    /// ```no_run
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+    /// let mut acl = PosixACL::new(0o640);
+    /// acl.set(Qualifier::User(1234), ACL_READ);
+    /// if acl.write_acl_or_chmod("/mnt/usb/report.txt").unwrap() {
+    ///     eprintln!("Warning: ACLs not supported, only file mode was changed");
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    /// * `ACLError::ValidationError`: The ACL failed validation. See [`PosixACL::validate()`] for
+    ///    more information.
+    pub fn write_acl_or_chmod<P: AsRef<Path>>(&mut self, path: P) -> Result<bool, ACLError> {
+        let path = path.as_ref();
+        match self.write_acl(path) {
+            Ok(()) => Ok(false),
+            Err(ACLError::Unsupported(_)) => {
+                let to_error = |err| ACLError::io_error(err, FLAG_WRITE).with_path(path);
+                let special = fs::metadata(path).map_err(to_error)?.permissions().mode() & 0o7000;
+                fs::set_permissions(
+                    path,
+                    Permissions::from_mode(special | self.try_reported_mode()?),
+                )
+                .map_err(to_error)?;
+                instrument::chmod_fallback();
+                Ok(true)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Validate and write this ACL to the access ACL of an open file. Overwrites any existing
+    /// access ACL. See [`write_acl()`](Self::write_acl).
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (permission denied, etc).
+    /// * `ACLError::ValidationError`: The ACL failed validation. See [`PosixACL::validate()`] for
+    ///    more information.
+    pub fn write_acl_fd<F: AsRawFd + ?Sized>(&mut self, file: &F) -> Result<(), ACLError> {
+        self.try_fix_mask()?;
+        self.validate()?;
+        let timer = Timer::start();
+        let ret = unsafe { acl_set_fd(file.as_raw_fd(), self.acl) };
+        let result = if ret == 0 {
+            Ok(())
+        } else {
+            Err(ACLError::last_os_error(FLAG_WRITE | ACL_TYPE_ACCESS))
+        };
+        timer.finish("write", &result);
+        result
+    }
+
     /// Validate and write this ACL to a directory's default ACL. Overwrites existing default ACL.
     /// This will fail if `path` is not a directory.
     ///
@@ -168,110 +745,1017 @@ impl PosixACL {
         self.write_acl_flags(path.as_ref(), ACL_TYPE_DEFAULT)
     }
 
-    fn write_acl_flags(&mut self, path: &Path, flags: acl_type_t) -> Result<(), ACLError> {
+    /// Delete the default ACL of a directory, like `setfacl -k`. It is NOT an error if the
+    /// directory has no default ACL.
+    ///
+    /// An empty default ACL cannot be written with [`write_default_acl()`](Self::write_default_acl)
+    /// because it fails validation.
+    /// ```no_run
+    /// use posix_acl::PosixACL;
+    /// PosixACL::delete_default_acl("/srv/shared").unwrap();
+    /// ```
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    /// * Passing a non-directory path will fail with 'permission denied' error on Linux.
+    pub fn delete_default_acl<P: AsRef<Path>>(path: P) -> Result<(), ACLError> {
+        let timer = Timer::start();
+        let path = path.as_ref();
+        let c_path = path_to_cstring(path);
+        let ret = unsafe { acl_delete_def_file(c_path.as_ptr()) };
+        let result = if ret == 0 {
+            Ok(())
+        } else {
+            Err(ACLError::last_os_error(FLAG_DELETE | ACL_TYPE_DEFAULT).with_path(path))
+        };
+        timer.finish("delete", &result);
+        result
+    }
+
+    pub(crate) fn write_acl_flags(
+        &mut self,
+        path: &Path,
+        flags: acl_type_t,
+    ) -> Result<(), ACLError> {
+        self.try_fix_mask()?;
+        self.write_acl_exact(path, flags)
+    }
+
+    /// Validate and write the ACL as-is, without re-calculating the `Mask` entry.
+    pub(crate) fn write_acl_exact(&self, path: &Path, flags: acl_type_t) -> Result<(), ACLError> {
         let c_path = path_to_cstring(path);
-        self.fix_mask();
         self.validate()?;
+        let timer = Timer::start();
         let ret = unsafe { acl_set_file(c_path.as_ptr(), flags, self.acl) };
-        if ret == 0 {
+        let result = if ret == 0 {
             Ok(())
         } else {
-            Err(ACLError::last_os_error(FLAG_WRITE | flags))
+            Err(ACLError::last_os_error(FLAG_WRITE | flags).with_path(path))
+        };
+        timer.finish("write", &result);
+        result
+    }
+
+    /// Build a validated ACL, refusing entries that libacl cannot create.
+    fn try_from_entries<I>(entries: I) -> Result<PosixACL, ACLError>
+    where
+        I: IntoIterator<Item = (Qualifier, u32)>,
+    {
+        let mut acl = PosixACL::empty();
+        for (qual, perm) in entries {
+            if matches!(qual, Qualifier::Undefined | Qualifier::Unknown(_)) {
+                return Err(ACLError::validation_error(ValidationReason::InvalidEntry));
+            }
+            acl.try_set(qual, perm)?;
         }
+        acl.validate()?;
+        Ok(acl)
     }
 
-    /// Iterator of `acl_entry_t`, unsafe
-    pub(crate) unsafe fn raw_iter(&self) -> RawACLIterator {
+    /// Iterator of `acl_entry_t`
+    pub(crate) fn raw_iter(&self) -> RawACLIterator<'_> {
         RawACLIterator::new(self)
     }
 
-    /// Get all `ACLEntry` items. The POSIX ACL C API does not allow multiple parallel iterators so we
-    /// return a materialized vector just to be safe.
+    /// Get all `ACLEntry` items.
+    ///
+    /// # Panics
+    ///
+    /// When the platform fails to allocate memory, use [`try_entries()`](Self::try_entries) to
+    /// handle errors.
     #[must_use]
     pub fn entries(&self) -> Vec<ACLEntry> {
-        unsafe { self.raw_iter() }
-            .map(ACLEntry::from_entry)
-            .collect()
+        self.iter().collect()
     }
 
-    /// Get the current `perm` value of `qual`, if any.
-    #[must_use]
-    pub fn get(&self, qual: Qualifier) -> Option<u32> {
-        let entry = self.raw_get_entry(&qual)?;
-
-        // XXX inefficient, no need to construct ACLEntry.
-        Some(ACLEntry::from_entry(entry).perm)
+    /// Like [`entries()`](Self::entries), but return an error instead of panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to allocate memory.
+    pub fn try_entries(&self) -> Result<Vec<ACLEntry>, ACLError> {
+        self.raw_iter()
+            .map(|entry| ACLEntry::try_from_entry(entry?))
+            .collect()
     }
 
-    /// Set the permission of `qual` to `perm`. If this `qual` already exists, it is updated,
-    /// otherwise a new one is added.
+    /// Iterate over `ACLEntry` items without collecting them into a `Vec` like
+    /// [`entries()`](Self::entries). `&PosixACL` also implements `IntoIterator`.
     ///
-    /// `perm` must be a combination of the `ACL_` constants, combined by binary OR.
-    pub fn set(&mut self, qual: Qualifier, perm: u32) {
-        let entry = match self.raw_get_entry(&qual) {
-            Some(v) => v,
-            None => self.raw_add_entry(&qual),
-        };
-
-        Self::raw_set_permset(entry, perm);
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+    /// let mut acl = PosixACL::new(0o640);
+    /// acl.set(Qualifier::User(1234), ACL_READ);
+    ///
+    /// let readers = acl.iter().filter(|entry| entry.perm & ACL_READ != 0).count();
+    /// assert_eq!(readers, 3);
+    /// for entry in &acl {
+    ///     assert_eq!(acl.get(entry.qual), Some(entry.perm));
+    /// }
+    /// ```
+    pub fn iter(&self) -> ACLIter<'_> {
+        ACLIter::new(self)
     }
 
-    /// Remove entry with matching `qual`. If found, returns the matching `perm`, otherwise `None`
-    #[allow(clippy::must_use_candidate)]
-    pub fn remove(&self, qual: Qualifier) -> Option<u32> {
-        let entry = self.raw_get_entry(&qual)?;
-        let wrapped = ACLEntry::from_entry(entry);
-
-        unsafe {
-            acl_delete_entry(self.acl, entry);
-        }
+    /// Iterate over entries, allowing their permissions to be changed in place with
+    /// [`EntryRef::set_perm()`]. This avoids looking up each entry again, as
+    /// [`set()`](Self::set) does.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ, ACL_WRITE};
+    /// let mut acl = PosixACL::new(0o664);
+    /// acl.set(Qualifier::User(1234), ACL_READ | ACL_WRITE);
+    ///
+    /// // Revoke write access from everyone but the owner
+    /// for mut entry in acl.entries_mut() {
+    ///     if entry.qualifier() != Qualifier::UserObj {
+    ///         entry.set_perm(entry.perm() & !ACL_WRITE);
+    ///     }
+    /// }
+    /// assert_eq!(acl.get(Qualifier::User(1234)), Some(ACL_READ));
+    /// assert_eq!(acl.get(Qualifier::UserObj), Some(ACL_READ | ACL_WRITE));
+    /// ```
+    pub fn entries_mut(&mut self) -> ACLIterMut<'_> {
+        ACLIterMut::new(self)
+    }
 
-        // XXX inefficient, no need to construct ACLEntry.
-        Some(wrapped.perm)
+    /// Get all `ACLEntry` items in the POSIX canonical order, regardless of the order the
+    /// platform stores them in: `UserObj`, `User` entries by UID, `GroupObj`, `Group` entries by
+    /// GID, `Mask`, `Other`. Unrecognized entries come last. See also
+    /// [`canonicalize()`](Self::canonicalize).
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+    /// let mut acl = PosixACL::new(0o640);
+    /// acl.set(Qualifier::User(2000), ACL_READ);
+    /// acl.set(Qualifier::User(1000), ACL_READ);
+    ///
+    /// let order: Vec<Qualifier> = acl.entries_sorted().iter().map(|entry| entry.qual).collect();
+    /// assert_eq!(order[1..3], [Qualifier::User(1000), Qualifier::User(2000)]);
+    /// ```
+    #[must_use]
+    pub fn entries_sorted(&self) -> Vec<ACLEntry> {
+        let mut entries = self.entries();
+        entries.sort();
+        entries
     }
 
-    fn raw_set_permset(entry: acl_entry_t, perm: u32) {
-        unsafe {
+    /// Return the number of entries, without allocating like [`entries()`](Self::entries).
+    ///
+    /// # Panics
+    ///
+    /// When the platform's `acl_entries()` function fails, use [`try_len()`](Self::try_len) to
+    /// handle errors.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        or_panic(self.try_len())
+    }
+
+    /// Like [`len()`](Self::len), but return an error instead of panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform's `acl_entries()` function failed.
+    pub fn try_len(&self) -> Result<usize, ACLError> {
+        match unsafe { capabilities::acl_entries(self.acl) } {
+            Some(len) => usize::try_from(len).map_err(|_| ACLError::last_os_error(ACL_TYPE_ACCESS)),
+            None => self
+                .raw_iter()
+                .try_fold(0, |count, entry| entry.map(|_| count + 1)),
+        }
+    }
+
+    /// Return `true` if the ACL has no entries. Empty ACLs are returned when reading the default
+    /// ACL of a directory that has none.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the current `perm` value of `qual`, if any.
+    ///
+    /// # Panics
+    ///
+    /// When reading the UID/GID of an entry fails, use [`try_get()`](Self::try_get) to handle
+    /// errors.
+    #[must_use]
+    pub fn get(&self, qual: Qualifier) -> Option<u32> {
+        or_panic(self.try_get(qual))
+    }
+
+    /// Like [`get()`](Self::get), but return an error instead of panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Reading the UID/GID of an entry failed, e.g. the platform failed to
+    ///   allocate memory.
+    pub fn try_get(&self, qual: Qualifier) -> Result<Option<u32>, ACLError> {
+        self.raw_get_entry(&qual)?
+            .map(ACLEntry::perm_from_entry)
+            .transpose()
+    }
+
+    /// Return `true` if the ACL has an entry for `qual`. Cheaper than `get(qual).is_some()`, as
+    /// qualifiers and permissions of non-matching entries are not read.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier};
+    /// let acl = PosixACL::new(0o640);
+    /// assert!(acl.contains(Qualifier::Other));
+    /// assert!(!acl.contains(Qualifier::Mask));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// When reading the UID/GID of an entry fails, use [`try_contains()`](Self::try_contains) to
+    /// handle errors.
+    #[must_use]
+    pub fn contains(&self, qual: Qualifier) -> bool {
+        or_panic(self.try_contains(qual))
+    }
+
+    /// Like [`contains()`](Self::contains), but return an error instead of panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Reading the UID/GID of an entry failed, e.g. the platform failed to
+    ///   allocate memory.
+    pub fn try_contains(&self, qual: Qualifier) -> Result<bool, ACLError> {
+        Ok(self.raw_get_entry(&qual)?.is_some())
+    }
+
+    /// Set the permission of `qual` to `perm`. If this `qual` already exists, it is updated,
+    /// otherwise a new one is added.
+    ///
+    /// `perm` must be a combination of the `ACL_` constants, combined by binary OR.
+    ///
+    /// Like `HashMap::insert()`, returns the previous `perm` of `qual` if it existed.
+    ///
+    /// # Panics
+    ///
+    /// When adding a `Qualifier::Unknown` entry, libacl does not allow creating those. Also when
+    /// the platform fails to allocate memory, use [`try_set()`](Self::try_set) to handle errors.
+    pub fn set(&mut self, qual: Qualifier, perm: u32) -> Option<u32> {
+        or_panic(self.try_set(qual, perm))
+    }
+
+    /// Like [`set()`](Self::set), but return an error instead of panicking. The ACL is left
+    /// unchanged on error.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+    /// let mut acl = PosixACL::new(0o640);
+    /// assert_eq!(acl.try_set(Qualifier::User(1234), ACL_READ).unwrap(), None);
+    /// assert!(acl.try_set(Qualifier::Unknown(0x100), ACL_READ).is_err());
+    /// ```
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: `qual` is `Qualifier::Unknown` or `Qualifier::Undefined`, or the
+    ///   platform failed to allocate memory.
+    pub fn try_set(&mut self, qual: Qualifier, perm: u32) -> Result<Option<u32>, ACLError> {
+        let (entry, prev) = match self.raw_get_entry(&qual)? {
+            Some(v) => (v, Some(ACLEntry::perm_from_entry(v)?)),
+            None => (self.raw_add_entry(&qual)?, None),
+        };
+
+        Self::raw_set_permset(entry, perm)?;
+        Ok(prev)
+    }
+
+    /// Add `perm` bits to the permissions of `qual`, keeping the existing ones. If this `qual`
+    /// does not exist, a new entry is added with just `perm`.
+    ///
+    /// Returns the previous `perm` of `qual` if it existed.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ, ACL_WRITE};
+    /// let mut acl = PosixACL::new(0o640);
+    /// acl.add_perm(Qualifier::Other, ACL_READ);
+    /// acl.add_perm(Qualifier::User(1234), ACL_WRITE);
+    /// assert_eq!(acl.get(Qualifier::Other), Some(ACL_READ));
+    /// assert_eq!(acl.get(Qualifier::User(1234)), Some(ACL_WRITE));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// When adding a `Qualifier::Unknown` entry, libacl does not allow creating those. Also when
+    /// the platform fails to allocate memory, use [`try_add_perm()`](Self::try_add_perm) to
+    /// handle errors.
+    pub fn add_perm(&mut self, qual: Qualifier, perm: u32) -> Option<u32> {
+        or_panic(self.try_add_perm(qual, perm))
+    }
+
+    /// Like [`add_perm()`](Self::add_perm), but return an error instead of panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: `qual` is `Qualifier::Unknown` or `Qualifier::Undefined`, or the
+    ///   platform failed to allocate memory.
+    pub fn try_add_perm(&mut self, qual: Qualifier, perm: u32) -> Result<Option<u32>, ACLError> {
+        let prev = self.try_get(qual)?;
+        self.try_set(qual, prev.unwrap_or(0) | perm)?;
+        Ok(prev)
+    }
+
+    /// Make sure that `qual` is granted at least `perm`, adding any missing bits but never
+    /// removing existing ones. Returns `true` if the ACL was changed.
+    ///
+    /// Unlike [`add_perm()`](Self::add_perm), the ACL is not touched if nothing is missing; in
+    /// particular, no entry is added if `perm` is `0`.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ, ACL_WRITE};
+    /// let mut acl = PosixACL::new(0o640);
+    /// assert!(acl.ensure(Qualifier::User(1234), ACL_READ | ACL_WRITE));
+    /// assert!(!acl.ensure(Qualifier::User(1234), ACL_READ));
+    /// assert!(!acl.ensure(Qualifier::UserObj, ACL_READ));
+    /// assert_eq!(acl.get(Qualifier::User(1234)), Some(ACL_READ | ACL_WRITE));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// When adding a `Qualifier::Unknown` entry, libacl does not allow creating those. Also when
+    /// the platform fails to allocate memory, use [`try_ensure()`](Self::try_ensure) to handle
+    /// errors.
+    pub fn ensure(&mut self, qual: Qualifier, perm: u32) -> bool {
+        or_panic(self.try_ensure(qual, perm))
+    }
+
+    /// Like [`ensure()`](Self::ensure), but return an error instead of panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: `qual` is `Qualifier::Unknown` or `Qualifier::Undefined`, or the
+    ///   platform failed to allocate memory.
+    pub fn try_ensure(&mut self, qual: Qualifier, perm: u32) -> Result<bool, ACLError> {
+        let prev = self.try_get(qual)?.unwrap_or(0);
+        if prev & perm == perm {
+            return Ok(false);
+        }
+        self.try_set(qual, prev | perm)?;
+        Ok(true)
+    }
+
+    /// Remove `perm` bits from the permissions of `qual`, keeping the others. If this `qual`
+    /// does not exist, nothing is changed. The entry is kept even if no permissions remain.
+    ///
+    /// Returns the previous `perm` of `qual` if it existed.
+    ///
+    /// # Panics
+    ///
+    /// When the platform fails to read or update the entry, use
+    /// [`try_remove_perm()`](Self::try_remove_perm) to handle errors.
+    pub fn remove_perm(&mut self, qual: Qualifier, perm: u32) -> Option<u32> {
+        or_panic(self.try_remove_perm(qual, perm))
+    }
+
+    /// Like [`remove_perm()`](Self::remove_perm), but return an error instead of panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to read or update an entry, e.g. failed to
+    ///   allocate memory.
+    pub fn try_remove_perm(&mut self, qual: Qualifier, perm: u32) -> Result<Option<u32>, ACLError> {
+        let prev = match self.try_get(qual)? {
+            Some(prev) => prev,
+            None => return Ok(None),
+        };
+        self.try_set(qual, prev & !perm)?;
+        Ok(Some(prev))
+    }
+
+    /// Remove entry with matching `qual`. If found, returns the matching `perm`, otherwise `None`
+    ///
+    /// # Panics
+    ///
+    /// When reading the UID/GID of an entry fails, use [`try_remove()`](Self::try_remove) to
+    /// handle errors.
+    pub fn remove(&mut self, qual: Qualifier) -> Option<u32> {
+        or_panic(self.try_remove(qual))
+    }
+
+    /// Like [`remove()`](Self::remove), but return an error instead of panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Reading the UID/GID of an entry or deleting it failed, e.g. the
+    ///   platform failed to allocate memory.
+    pub fn try_remove(&mut self, qual: Qualifier) -> Result<Option<u32>, ACLError> {
+        let entry = match self.raw_get_entry(&qual)? {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        let perm = ACLEntry::perm_from_entry(entry)?;
+        try_return(unsafe { acl_delete_entry(self.acl, entry) }, FLAGS_MODIFY)?;
+        Ok(Some(perm))
+    }
+
+    /// Like [`try_remove()`](Self::try_remove), but refuse to remove the required entries
+    /// `UserObj`, `GroupObj` and `Other`, without which the ACL can never pass validation.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier};
+    /// let mut acl = PosixACL::new(0o640);
+    /// assert!(acl.remove_checked(Qualifier::GroupObj).is_err());
+    /// assert_eq!(acl.remove_checked(Qualifier::User(1234)).unwrap(), None);
+    /// ```
+    ///
+    /// # Errors
+    /// * `ACLError::ValidationError`: `qual` is a required entry. The ACL is left unchanged.
+    /// * `ACLError::IoError`: Reading the UID/GID of an entry or deleting it failed, e.g. the
+    ///   platform failed to allocate memory.
+    pub fn remove_checked(&mut self, qual: Qualifier) -> Result<Option<u32>, ACLError> {
+        if matches!(qual, UserObj | GroupObj | Other) {
+            return Err(ACLError::validation_error(ValidationReason::MissingEntry));
+        }
+        self.try_remove(qual)
+    }
+
+    /// Remove all named `User` and `Group` entries and the `Mask` entry, leaving only the base
+    /// entries `UserObj`, `GroupObj` and `Other`, like `setfacl -b`. Returns the removed entries.
+    ///
+    /// Note that if the ACL had a `Mask` entry, the group permissions reported by `stat()`
+    /// change from the mask to the `GroupObj` permissions.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+    /// let mut acl = PosixACL::new(0o640);
+    /// acl.set(Qualifier::User(1234), ACL_READ);
+    /// acl.fix_mask();
+    ///
+    /// let removed = acl.strip_extended();
+    /// assert_eq!(removed.len(), 2);
+    /// assert_eq!(acl, PosixACL::new(0o640));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// When the platform fails to read or delete an entry, use
+    /// [`try_strip_extended()`](Self::try_strip_extended) to handle errors.
+    pub fn strip_extended(&mut self) -> Vec<ACLEntry> {
+        or_panic(self.try_strip_extended())
+    }
+
+    /// Like [`strip_extended()`](Self::strip_extended), but return an error instead of panicking. On error, some
+    /// entries may already be removed.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to read or update an entry, e.g. failed to
+    ///   allocate memory.
+    pub fn try_strip_extended(&mut self) -> Result<Vec<ACLEntry>, ACLError> {
+        let removed: Vec<ACLEntry> = self
+            .try_entries()?
+            .into_iter()
+            .filter(|entry| matches!(entry.qual, User(_) | Group(_) | Mask))
+            .collect();
+        for entry in &removed {
+            self.try_remove(entry.qual)?;
+        }
+        Ok(removed)
+    }
+
+    /// Remove all entries, keeping the allocated ACL for reuse. NB! Empty ACLs are NOT
+    /// considered valid.
+    ///
+    /// # Panics
+    ///
+    /// When the platform fails to delete an entry, use [`try_clear()`](Self::try_clear) to handle
+    /// errors.
+    pub fn clear(&mut self) {
+        or_panic(self.try_clear());
+    }
+
+    /// Like [`clear()`](Self::clear), but return an error instead of panicking. On error, some
+    /// entries may already be removed.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to delete an entry.
+    pub fn try_clear(&mut self) -> Result<(), ACLError> {
+        while let Some(entry) = self.raw_iter().next() {
+            try_return(unsafe { acl_delete_entry(self.acl, entry?) }, FLAGS_MODIFY)?;
+        }
+        Ok(())
+    }
+
+    /// Replace all entries with the minimal ACL for `file_mode`, same as
+    /// [`PosixACL::new()`](Self::new) but reusing the allocated ACL. Useful for long-running
+    /// code that processes many files with a single `PosixACL` value.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+    /// let mut acl = PosixACL::new(0o640);
+    /// acl.set(Qualifier::User(1234), ACL_READ);
+    /// acl.reset(0o751);
+    /// assert_eq!(acl, PosixACL::new(0o751));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// When the platform fails to allocate memory, use [`try_reset()`](Self::try_reset) to
+    /// handle errors.
+    pub fn reset(&mut self, file_mode: u32) {
+        or_panic(self.try_reset(file_mode));
+    }
+
+    /// Like [`reset()`](Self::reset), but return an error instead of panicking. On error, the
+    /// ACL may be left incomplete.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to read or update an entry, e.g. failed to
+    ///   allocate memory.
+    pub fn try_reset(&mut self, file_mode: u32) -> Result<(), ACLError> {
+        self.try_clear()?;
+        self.try_set(UserObj, (file_mode >> 6) & ACL_RWX)?;
+        self.try_set(GroupObj, (file_mode >> 3) & ACL_RWX)?;
+        self.try_set(Other, file_mode & ACL_RWX)?;
+        Ok(())
+    }
+
+    /// Keep only the entries for which `keep` returns `true`, removing all others, like
+    /// `HashMap::retain()`.
+    ///
+    /// Note that removing required entries leaves the ACL invalid, and the `Mask` entry is not
+    /// recalculated.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+    /// let mut acl = PosixACL::new(0o640);
+    /// acl.set(Qualifier::User(1234), 0);
+    /// acl.set(Qualifier::User(5678), ACL_READ);
+    ///
+    /// // Remove named entries granting nothing
+    /// acl.retain(|qual, perm| !matches!(qual, Qualifier::User(_) | Qualifier::Group(_)) || perm != 0);
+    /// assert_eq!(acl.get(Qualifier::User(1234)), None);
+    /// assert_eq!(acl.get(Qualifier::User(5678)), Some(ACL_READ));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// When the platform fails to read or delete an entry, use
+    /// [`try_retain()`](Self::try_retain) to handle errors.
+    pub fn retain<F: FnMut(Qualifier, u32) -> bool>(&mut self, keep: F) {
+        or_panic(self.try_retain(keep));
+    }
+
+    /// Like [`retain()`](Self::retain), but return an error instead of panicking. On error, some entries
+    /// may already be removed.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to read or update an entry, e.g. failed to
+    ///   allocate memory.
+    pub fn try_retain<F: FnMut(Qualifier, u32) -> bool>(
+        &mut self,
+        mut keep: F,
+    ) -> Result<(), ACLError> {
+        // Entries can't be deleted while iterating, the cursor would be invalidated.
+        let removed: Vec<Qualifier> = self
+            .try_entries()?
+            .into_iter()
+            .filter(|entry| !keep(entry.qual, entry.perm))
+            .map(|entry| entry.qual)
+            .collect();
+        for qual in removed {
+            self.try_remove(qual)?;
+        }
+        Ok(())
+    }
+
+    /// Replace the qualifier of each entry with the one returned by `map`, keeping permissions.
+    /// Useful for shifting UIDs and GIDs of named entries into a user namespace, or translating
+    /// them between hosts. Returns `true` if any entry was changed.
+    ///
+    /// Entries are not merged: all changed entries are removed first and then added with their
+    /// new qualifier. So a mapped entry replaces an unchanged entry that already has the new
+    /// qualifier, e.g. mapping `User(1)` to `User(2)` keeps the permissions of `User(1)`. If
+    /// multiple entries are mapped to the same qualifier, the one that comes last in
+    /// [`entries()`](Self::entries) order wins.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+    /// let mut acl = PosixACL::new(0o640);
+    /// acl.set(Qualifier::User(1000), ACL_READ);
+    ///
+    /// acl.remap_ids(|qual| match qual {
+    ///     Qualifier::User(uid) => Qualifier::User(uid + 100_000),
+    ///     Qualifier::Group(gid) => Qualifier::Group(gid + 100_000),
+    ///     other => other,
+    /// });
+    /// assert_eq!(acl.get(Qualifier::User(101_000)), Some(ACL_READ));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// When mapping to a `Qualifier::Unknown` entry, libacl does not allow creating those. Also
+    /// when the platform fails to allocate memory, use [`try_remap_ids()`](Self::try_remap_ids)
+    /// to handle errors.
+    pub fn remap_ids<F: FnMut(Qualifier) -> Qualifier>(&mut self, map: F) -> bool {
+        or_panic(self.try_remap_ids(map))
+    }
+
+    /// Like [`remap_ids()`](Self::remap_ids), but return an error instead of panicking. On error, the ACL
+    /// may be left partially remapped.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: An entry was mapped to `Qualifier::Unknown` or
+    ///   `Qualifier::Undefined`, or the platform failed to allocate memory.
+    pub fn try_remap_ids<F: FnMut(Qualifier) -> Qualifier>(
+        &mut self,
+        mut map: F,
+    ) -> Result<bool, ACLError> {
+        let changed: Vec<(Qualifier, ACLEntry)> = self
+            .try_entries()?
+            .into_iter()
+            .filter_map(|entry| {
+                let qual = map(entry.qual);
+                if qual == entry.qual {
+                    None
+                } else {
+                    Some((
+                        entry.qual,
+                        ACLEntry {
+                            qual,
+                            perm: entry.perm,
+                        },
+                    ))
+                }
+            })
+            .collect();
+        // Remove all old entries first, so that swapping two IDs works.
+        for (old, _) in &changed {
+            self.try_remove(*old)?;
+        }
+        for (_, new) in &changed {
+            self.try_set(new.qual, new.perm)?;
+        }
+        Ok(!changed.is_empty())
+    }
+
+    /// Reorder entries into the POSIX canonical order: `UserObj`, `User` entries by UID,
+    /// `GroupObj`, `Group` entries by GID, `Mask`, `Other`. Unrecognized entries are moved last.
+    ///
+    /// libacl on Linux already keeps entries in this order, other platforms may return them in
+    /// insertion order. Useful before comparing [`entries()`](Self::entries) or text output
+    /// against ACLs produced elsewhere.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+    /// let mut acl = PosixACL::empty();
+    /// acl.set(Qualifier::Other, 0);
+    /// acl.set(Qualifier::Group(1234), ACL_READ);
+    /// acl.set(Qualifier::GroupObj, ACL_READ);
+    /// acl.set(Qualifier::UserObj, ACL_READ);
+    /// acl.canonicalize();
+    ///
+    /// let order: Vec<Qualifier> = acl.entries().iter().map(|entry| entry.qual).collect();
+    /// assert_eq!(
+    ///     order,
+    ///     [Qualifier::UserObj, Qualifier::GroupObj, Qualifier::Group(1234), Qualifier::Other]
+    /// );
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// When the platform fails to allocate memory, use
+    /// [`try_canonicalize()`](Self::try_canonicalize) to handle errors.
+    pub fn canonicalize(&mut self) {
+        or_panic(self.try_canonicalize());
+    }
+
+    /// Like [`canonicalize()`](Self::canonicalize), but return an error instead of panicking.
+    /// The ACL is left unchanged on error.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to allocate memory.
+    pub fn try_canonicalize(&mut self) -> Result<(), ACLError> {
+        let mut entries = self
+            .raw_iter()
+            .map(|entry| {
+                let entry = entry?;
+                Ok((Qualifier::try_from_entry(entry)?, entry))
+            })
+            .collect::<Result<Vec<(Qualifier, acl_entry_t)>, ACLError>>()?;
+        entries.sort_by_key(|(qual, _)| qual.canonical_key());
+
+        let mut sorted = PosixACL::try_with_capacity(entries.len())?;
+        for (_, src) in entries {
+            let mut dest: acl_entry_t = null_mut();
+            unsafe {
+                try_return(acl_create_entry(&mut sorted.acl, &mut dest), FLAGS_MODIFY)?;
+                try_return(acl_copy_entry(dest, src), FLAGS_MODIFY)?;
+            }
+        }
+        *self = sorted;
+        Ok(())
+    }
+
+    /// Copy the entry with matching `qual` from the `other` ACL into this one, overwriting the
+    /// existing entry if any. Returns `false` if `other` has no such entry.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+    /// let mut template = PosixACL::new(0o640);
+    /// template.set(Qualifier::Group(1234), ACL_READ);
+    ///
+    /// let mut acl = PosixACL::new(0o600);
+    /// assert!(acl.copy_entry_from(&template, Qualifier::Group(1234)));
+    /// assert_eq!(acl.get(Qualifier::Group(1234)), Some(ACL_READ));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// When the platform fails to allocate memory, use
+    /// [`try_copy_entry_from()`](Self::try_copy_entry_from) to handle errors.
+    pub fn copy_entry_from(&mut self, other: &PosixACL, qual: Qualifier) -> bool {
+        or_panic(self.try_copy_entry_from(other, qual))
+    }
+
+    /// Like [`copy_entry_from()`](Self::copy_entry_from), but return an error instead of
+    /// panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to allocate memory.
+    pub fn try_copy_entry_from(
+        &mut self,
+        other: &PosixACL,
+        qual: Qualifier,
+    ) -> Result<bool, ACLError> {
+        let src = match other.raw_get_entry(&qual)? {
+            Some(v) => v,
+            None => return Ok(false),
+        };
+        let dest = match self.raw_get_entry(&qual)? {
+            Some(v) => v,
+            None => self.raw_add_entry(&qual)?,
+        };
+        try_return(unsafe { acl_copy_entry(dest, src) }, FLAGS_MODIFY)?;
+        Ok(true)
+    }
+
+    pub(crate) fn raw_set_permset(entry: acl_entry_t, perm: u32) -> Result<(), ACLError> {
+        unsafe {
             let mut permset: acl_permset_t = null_mut();
-            check_return(acl_get_permset(entry, &mut permset), "acl_get_permset");
-            check_return(acl_clear_perms(permset), "acl_clear_perms");
-            check_return(acl_add_perm(permset, perm), "acl_add_perm");
-            check_return(acl_set_permset(entry, permset), "acl_set_permset");
+            try_return(acl_get_permset(entry, &mut permset), FLAGS_MODIFY)?;
+            try_return(acl_clear_perms(permset), FLAGS_MODIFY)?;
+            try_return(acl_add_perm(permset, perm), FLAGS_MODIFY)?;
+            try_return(acl_set_permset(entry, permset), FLAGS_MODIFY)
         }
     }
 
-    fn raw_get_entry(&self, qual: &Qualifier) -> Option<acl_entry_t> {
-        unsafe { self.raw_iter() }.find(
-            // XXX this is slightly inefficient, calls to get_entry_uid() could be short-circuited.
-            |&entry| Qualifier::from_entry(entry) == *qual,
-        )
+    fn raw_get_entry(&self, qual: &Qualifier) -> Result<Option<acl_entry_t>, ACLError> {
+        for entry in self.raw_iter() {
+            let entry = entry?;
+            if qual.matches_entry(entry)? {
+                return Ok(Some(entry));
+            }
+        }
+        Ok(None)
     }
 
-    fn raw_add_entry(&mut self, qual: &Qualifier) -> acl_entry_t {
+    /// Add an entry for `qual`. On error, no entry is left behind.
+    fn raw_add_entry(&mut self, qual: &Qualifier) -> Result<acl_entry_t, ACLError> {
         let mut entry: acl_entry_t = null_mut();
         unsafe {
-            check_return(
-                acl_create_entry(&mut self.acl, &mut entry),
-                "acl_create_entry",
-            );
-            check_return(acl_set_tag_type(entry, qual.tag_type()), "acl_set_tag_type");
-            if let Some(uid) = qual.uid() {
-                check_return(
-                    acl_set_qualifier(entry, addr_of!(uid).cast::<c_void>()),
-                    "acl_set_qualifier",
-                );
+            try_return(acl_create_entry(&mut self.acl, &mut entry), FLAGS_MODIFY)?;
+            let mut result = try_return(acl_set_tag_type(entry, qual.tag_type()), FLAGS_MODIFY);
+            if let (Ok(()), Some(uid)) = (&result, qual.uid()) {
+                let ret = acl_set_qualifier(entry, addr_of!(uid).cast::<c_void>());
+                result = try_return(ret, FLAGS_MODIFY);
+            }
+            if result.is_err() {
+                acl_delete_entry(self.acl, entry);
             }
+            result.map(|()| entry)
         }
-        entry
     }
 
     /// Re-calculate the `Qualifier::Mask` entry.
     ///
     /// Usually there is no need to call this directly, as this is done during
     /// `write_acl/write_default_acl()` automatically.
+    ///
+    /// # Panics
+    ///
+    /// When the platform fails to allocate memory, use [`try_fix_mask()`](Self::try_fix_mask)
+    /// to handle errors.
     pub fn fix_mask(&mut self) {
-        unsafe {
-            check_return(acl_calc_mask(&mut self.acl), "acl_calc_mask");
+        or_panic(self.try_fix_mask());
+    }
+
+    /// Like [`fix_mask()`](Self::fix_mask), but return an error instead of panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to allocate memory.
+    pub fn try_fix_mask(&mut self) -> Result<(), ACLError> {
+        try_return(unsafe { acl_calc_mask(&mut self.acl) }, FLAGS_MODIFY)
+    }
+
+    /// Return the permissions that [`fix_mask()`](Self::fix_mask) would set the `Mask` entry to,
+    /// without modifying the ACL: the union of `User`, `GroupObj` and `Group` entries.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ, ACL_WRITE};
+    /// let mut acl = PosixACL::new(0o640);
+    /// acl.set(Qualifier::User(1234), ACL_READ | ACL_WRITE);
+    /// assert_eq!(acl.compute_mask(), ACL_READ | ACL_WRITE);
+    /// assert!(acl.mask_is_stale());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// When the platform fails to read an entry, use [`try_compute_mask()`](Self::try_compute_mask)
+    /// to handle errors.
+    #[must_use]
+    pub fn compute_mask(&self) -> u32 {
+        or_panic(self.try_compute_mask())
+    }
+
+    /// Like [`compute_mask()`](Self::compute_mask), but return an error instead of panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to read an entry, e.g. failed to allocate
+    ///   memory.
+    pub fn try_compute_mask(&self) -> Result<u32, ACLError> {
+        Ok(self
+            .try_entries()?
+            .iter()
+            .filter(|entry| matches!(entry.qual, User(_) | GroupObj | Group(_)))
+            .fold(0, |mask, entry| mask | entry.perm))
+    }
+
+    /// Return `true` if the `Mask` entry differs from [`compute_mask()`](Self::compute_mask), or
+    /// is missing although the ACL has `User` or `Group` entries. Writing the ACL would then
+    /// change the mask, see [`fix_mask()`](Self::fix_mask).
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+    /// let mut acl = PosixACL::new(0o640);
+    /// assert!(!acl.mask_is_stale());
+    /// acl.set(Qualifier::User(1234), ACL_READ);
+    /// acl.set(Qualifier::Mask, 0);
+    /// assert!(acl.mask_is_stale());
+    /// acl.fix_mask();
+    /// assert!(!acl.mask_is_stale());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// When the platform fails to read an entry, use
+    /// [`try_mask_is_stale()`](Self::try_mask_is_stale) to handle errors.
+    #[must_use]
+    pub fn mask_is_stale(&self) -> bool {
+        or_panic(self.try_mask_is_stale())
+    }
+
+    /// Like [`mask_is_stale()`](Self::mask_is_stale), but return an error instead of panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to read an entry, e.g. failed to allocate
+    ///   memory.
+    pub fn try_mask_is_stale(&self) -> Result<bool, ACLError> {
+        Ok(match self.try_get(Mask)? {
+            Some(mask) => mask != self.try_compute_mask()?,
+            None => self
+                .try_entries()?
+                .iter()
+                .any(|entry| matches!(entry.qual, User(_) | Group(_))),
+        })
+    }
+
+    /// Get the effective permissions of `qual`, as enforced by the kernel: if the ACL has a
+    /// `Mask` entry, permissions of `User`, `GroupObj` and `Group` entries are limited by it.
+    /// Other entries are returned unchanged.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ, ACL_WRITE};
+    /// let mut acl = PosixACL::new(0o640);
+    /// acl.set(Qualifier::User(1234), ACL_READ | ACL_WRITE);
+    /// acl.set(Qualifier::Mask, ACL_READ);
+    /// assert_eq!(acl.effective(Qualifier::User(1234)), Some(ACL_READ));
+    /// assert_eq!(acl.effective(Qualifier::UserObj), Some(ACL_READ | ACL_WRITE));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// When the platform fails to read an entry, use [`try_effective()`](Self::try_effective) to
+    /// handle errors.
+    #[must_use]
+    pub fn effective(&self, qual: Qualifier) -> Option<u32> {
+        or_panic(self.try_effective(qual))
+    }
+
+    /// Like [`effective()`](Self::effective), but return an error instead of panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to read an entry, e.g. failed to allocate
+    ///   memory.
+    pub fn try_effective(&self, qual: Qualifier) -> Result<Option<u32>, ACLError> {
+        let perm = match self.try_get(qual)? {
+            Some(perm) => perm,
+            None => return Ok(None),
+        };
+        Ok(Some(Self::apply_mask(qual, perm, self.try_get(Mask)?)))
+    }
+
+    /// Like [`entries()`](Self::entries), but with [effective](Self::effective) permissions.
+    ///
+    /// # Panics
+    ///
+    /// When the platform fails to read an entry, use
+    /// [`try_effective_entries()`](Self::try_effective_entries) to handle errors.
+    #[must_use]
+    pub fn effective_entries(&self) -> Vec<ACLEntry> {
+        or_panic(self.try_effective_entries())
+    }
+
+    /// Like [`effective_entries()`](Self::effective_entries), but return an error instead of panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to read an entry, e.g. failed to allocate
+    ///   memory.
+    pub fn try_effective_entries(&self) -> Result<Vec<ACLEntry>, ACLError> {
+        let mask = self.try_get(Mask)?;
+        Ok(self
+            .try_entries()?
+            .into_iter()
+            .map(|entry| ACLEntry {
+                qual: entry.qual,
+                perm: Self::apply_mask(entry.qual, entry.perm, mask),
+            })
+            .collect())
+    }
+
+    fn apply_mask(qual: Qualifier, perm: u32, mask: Option<u32>) -> u32 {
+        match (qual, mask) {
+            (User(_) | GroupObj | Group(_), Some(mask)) => perm & mask,
+            _ => perm,
+        }
+    }
+
+    /// Return the file mode bits that `stat()` would report for a file with this ACL.
+    ///
+    /// Owner and other bits come from `UserObj` and `Other` entries. If the ACL has a `Mask`
+    /// entry, the group bits reflect the mask, otherwise `GroupObj`. Missing entries are treated
+    /// as having no permissions.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ, ACL_WRITE};
+    /// let mut acl = PosixACL::new(0o640);
+    /// assert_eq!(acl.reported_mode(), 0o640);
+    ///
+    /// acl.set(Qualifier::User(0), ACL_READ | ACL_WRITE);
+    /// acl.fix_mask();
+    /// assert_eq!(acl.reported_mode(), 0o660);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// When the platform fails to read an entry, use
+    /// [`try_reported_mode()`](Self::try_reported_mode) to handle errors.
+    #[must_use]
+    pub fn reported_mode(&self) -> u32 {
+        or_panic(self.try_reported_mode())
+    }
+
+    /// Like [`reported_mode()`](Self::reported_mode), but return an error instead of panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to read an entry, e.g. failed to allocate
+    ///   memory.
+    pub fn try_reported_mode(&self) -> Result<u32, ACLError> {
+        let group = match self.try_get(Mask)? {
+            Some(mask) => Some(mask),
+            None => self.try_get(GroupObj)?,
+        };
+        Ok((self.try_get(UserObj)?.unwrap_or(0) << 6)
+            | (group.unwrap_or(0) << 3)
+            | self.try_get(Other)?.unwrap_or(0))
+    }
+
+    /// Whether the ACL has named `User` or `Group` entries, which cannot be stored as mode bits.
+    fn has_named_entries(&self) -> bool {
+        self.iter()
+            .any(|entry| matches!(entry.qual, User(_) | Group(_)))
+    }
+
+    /// Return the file mode bits equivalent to this ACL, if it has no extended entries (only
+    /// `UserObj`, `GroupObj` and `Other`).
+    ///
+    /// Returns `None` if the ACL cannot be represented by mode bits alone. Note that the ACL is
+    /// not validated; missing entries are treated as having no permissions.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+    /// let mut acl = PosixACL::new(0o640);
+    /// assert_eq!(acl.equivalent_mode(), Some(0o640));
+    /// acl.set(Qualifier::User(1234), ACL_READ);
+    /// assert_eq!(acl.equivalent_mode(), None);
+    /// ```
+    #[must_use]
+    pub fn equivalent_mode(&self) -> Option<u32> {
+        let mut mode: libc::mode_t = 0;
+        match unsafe { capabilities::acl_equiv_mode(self.acl, &mut mode) } {
+            Some(0) => Some(mode),
+            Some(_) => None,
+            None => self
+                .iter()
+                .try_fold(0, |mode, ACLEntry { qual, perm }| match qual {
+                    UserObj => Some(mode | perm << 6),
+                    GroupObj => Some(mode | perm << 3),
+                    Other => Some(mode | perm),
+                    _ => None,
+                }),
         }
     }
 
@@ -282,16 +1766,34 @@ impl PosixACL {
     ///
     /// # Panics
     ///
-    /// When platform returns a string that is not valid UTF-8.
+    /// When platform returns a string that is not valid UTF-8, or fails to allocate memory. Use
+    /// [`try_as_text()`](Self::try_as_text) to handle errors.
     #[must_use]
     pub fn as_text(&self) -> String {
+        or_panic(self.try_as_text())
+    }
+
+    /// Like [`as_text()`](Self::as_text), but return an error instead of panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to allocate memory, or returned a string that
+    ///   is not valid UTF-8 (`ErrorKind::InvalidData`).
+    pub fn try_as_text(&self) -> Result<String, ACLError> {
         let mut len: ssize_t = 0;
-        let txt = AutoPtr(unsafe { acl_to_text(self.acl, &mut len) });
-        check_pointer(txt.0, "acl_to_text");
-        let len = usize::try_from(len).expect("Length should be positive");
-        let chars = unsafe { from_raw_parts(txt.0.cast::<u8>(), len) };
+        let ptr = unsafe { acl_to_text(self.acl, &mut len) };
+        try_pointer(ptr, ACL_TYPE_ACCESS)?;
+        let owned = AutoPtr(ptr);
+        // Length is never negative on success.
+        let len = usize::try_from(len).unwrap_or_default();
+        let chars = unsafe { from_raw_parts(owned.0.cast::<u8>(), len) };
 
-        from_utf8(chars).expect("Not valid UTF-8").to_string()
+        match from_utf8(chars) {
+            Ok(string) => Ok(string.to_string()),
+            Err(err) => Err(ACLError::io_error(
+                io::Error::new(io::ErrorKind::InvalidData, err),
+                ACL_TYPE_ACCESS,
+            )),
+        }
     }
 
     fn compact_text(&self) -> String {
@@ -306,20 +1808,44 @@ impl PosixACL {
     /// `fix_mask()` prior to `validate()`.
     ///
     /// # Errors
-    /// * `ACLError::ValidationError`: The ACL failed validation.
-    ///
-    /// Unfortunately it is not possible to provide detailed error reasons, but mainly it can be:
-    /// * Required entries are missing (`UserObj`, `GroupObj`, `Mask` and `Other`).
-    /// * ACL contains entries that are not unique.
+    /// * `ACLError::ValidationError`: The ACL failed validation. The reason is available from
+    ///   [`ACLError::validation_reason()`], mainly it can be:
+    ///   * Required entries are missing (`UserObj`, `GroupObj`, `Mask` and `Other`).
+    ///   * ACL contains entries that are not unique.
     pub fn validate(&self) -> Result<(), ACLError> {
         let ret = unsafe { acl_valid(self.acl) };
         if ret == 0 {
             Ok(())
         } else {
-            Err(ACLError::validation_error())
+            instrument::validation_failure();
+            Err(ACLError::validation_error(self.check_reason()))
+        }
+    }
+
+    /// Use the platform's `acl_check()`, if available, to find out why validation failed.
+    fn check_reason(&self) -> ValidationReason {
+        let mut last: c_int = 0;
+        match unsafe { capabilities::acl_check(self.acl, &mut last) } {
+            Some(ACL_MULTI_ERROR) => ValidationReason::MultipleEntries,
+            Some(ACL_DUPLICATE_ERROR) => ValidationReason::DuplicateEntry,
+            Some(ACL_MISS_ERROR) => ValidationReason::MissingEntry,
+            Some(ACL_ENTRY_ERROR) => ValidationReason::InvalidEntry,
+            _ => ValidationReason::Unknown,
         }
     }
 
+    /// Returns the wrapped `acl_t` without giving up ownership, for passing the ACL to FFI calls
+    /// of the acl library.
+    ///
+    /// The pointer is borrowed: it is only valid as long as this `PosixACL` exists, and must not
+    /// be freed with `acl_free()`. It should only be used for reading; modifying the ACL through
+    /// it, or iterating with `acl_get_entry()` while an [`iter()`](Self::iter) iterator is in
+    /// use, breaks the assumptions of `PosixACL`.
+    #[must_use]
+    pub fn as_raw(&self) -> acl_t {
+        self.acl
+    }
+
     /// Consumes the `PosixACL`, returning the wrapped `acl_t`.
     /// This can then be used directly in FFI calls to the acl library.
     ///
@@ -349,6 +1875,9 @@ impl PosixACL {
     /// Improper usage of this function may lead to memory unsafety (e.g.
     /// calling it twice on the same acl may lead to a double free).
     pub unsafe fn from_raw(acl: acl_t) -> Self {
-        Self { acl }
+        Self {
+            acl,
+            cursor: Cell::new(0),
+        }
     }
 }
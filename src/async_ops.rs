@@ -0,0 +1,145 @@
+//! Async wrappers running blocking ACL calls on the tokio blocking thread pool. Enabled by the
+//! `tokio` feature.
+use crate::{ACLError, PosixACL, Qualifier, RecursiveReport};
+use std::panic;
+use std::path::Path;
+
+/// Run `op` on the blocking thread pool of the current tokio runtime. Panics in `op` are
+/// propagated to the caller.
+async fn blocking<T, F>(op: F) -> T
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    match tokio::task::spawn_blocking(op).await {
+        Ok(value) => value,
+        Err(err) => match err.try_into_panic() {
+            Ok(payload) => panic::resume_unwind(payload),
+            // Blocking tasks are only cancelled when the runtime is shutting down.
+            Err(err) => panic!("{}", err),
+        },
+    }
+}
+
+/// Async versions of the filesystem operations, for use in tokio applications. The blocking
+/// libacl calls run on tokio's blocking thread pool via `spawn_blocking()`, so they don't stall
+/// the executor.
+///
+/// They must be called from within a tokio runtime. With other runtimes, run the blocking
+/// methods through the runtime's own `spawn_blocking()` equivalent; [`PosixACL`] is `Send`.
+impl PosixACL {
+    /// Like [`read_acl()`](Self::read_acl), but async.
+    ///
+    /// ```no_run
+    /// # async fn example() {
+    /// use posix_acl::PosixACL;
+    /// let acl = PosixACL::read_acl_async("/tmp/posix-acl-testfile").await.unwrap();
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    ///
+    /// # Panics
+    ///
+    /// When called outside of a tokio runtime.
+    pub async fn read_acl_async<P: AsRef<Path>>(path: P) -> Result<PosixACL, ACLError> {
+        let path = path.as_ref().to_path_buf();
+        blocking(move || PosixACL::read_acl(path)).await
+    }
+
+    /// Like [`read_default_acl()`](Self::read_default_acl), but async.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    ///
+    /// # Panics
+    ///
+    /// When called outside of a tokio runtime.
+    pub async fn read_default_acl_async<P: AsRef<Path>>(path: P) -> Result<PosixACL, ACLError> {
+        let path = path.as_ref().to_path_buf();
+        blocking(move || PosixACL::read_default_acl(path)).await
+    }
+
+    /// Like [`write_acl()`](Self::write_acl), but async. A copy of the ACL is written, and the
+    /// recalculated `Mask` entry is stored back into `self` on success.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    /// * `ACLError::ValidationError`: The ACL failed validation.
+    ///
+    /// # Panics
+    ///
+    /// When called outside of a tokio runtime.
+    pub async fn write_acl_async<P: AsRef<Path>>(&mut self, path: P) -> Result<(), ACLError> {
+        let path = path.as_ref().to_path_buf();
+        let mut acl = self.clone();
+        *self = blocking(move || acl.write_acl(path).map(|()| acl)).await?;
+        Ok(())
+    }
+
+    /// Like [`write_default_acl()`](Self::write_default_acl), but async. A copy of the ACL is
+    /// written, and the recalculated `Mask` entry is stored back into `self` on success.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    /// * `ACLError::ValidationError`: The ACL failed validation.
+    ///
+    /// # Panics
+    ///
+    /// When called outside of a tokio runtime.
+    pub async fn write_default_acl_async<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<(), ACLError> {
+        let path = path.as_ref().to_path_buf();
+        let mut acl = self.clone();
+        *self = blocking(move || acl.write_default_acl(path).map(|()| acl)).await?;
+        Ok(())
+    }
+
+    /// Like [`delete_default_acl_recursive()`](Self::delete_default_acl_recursive), but async.
+    /// The whole tree is processed on a single blocking thread.
+    ///
+    /// # Panics
+    ///
+    /// When called outside of a tokio runtime.
+    pub async fn delete_default_acl_recursive_async<P: AsRef<Path>>(root: P) -> RecursiveReport {
+        let root = root.as_ref().to_path_buf();
+        blocking(move || PosixACL::delete_default_acl_recursive(root)).await
+    }
+
+    /// Like [`modify_recursive()`](Self::modify_recursive), but async. The whole tree is
+    /// processed on a single blocking thread.
+    ///
+    /// # Errors
+    /// * `ACLError::ParseError`: The spec is not valid, or contains an unknown user/group name.
+    ///   Nothing is modified in this case.
+    ///
+    /// # Panics
+    ///
+    /// When called outside of a tokio runtime.
+    pub async fn modify_recursive_async<P: AsRef<Path>>(
+        root: P,
+        spec: &str,
+    ) -> Result<RecursiveReport, ACLError> {
+        let root = root.as_ref().to_path_buf();
+        let spec = spec.to_string();
+        blocking(move || PosixACL::modify_recursive(root, &spec)).await
+    }
+
+    /// Like [`remap_ids_recursive()`](Self::remap_ids_recursive), but async. The whole tree is
+    /// processed on a single blocking thread.
+    ///
+    /// # Panics
+    ///
+    /// When called outside of a tokio runtime.
+    pub async fn remap_ids_recursive_async<P, F>(root: P, map: F) -> RecursiveReport
+    where
+        P: AsRef<Path>,
+        F: FnMut(Qualifier) -> Qualifier + Send + 'static,
+    {
+        let root = root.as_ref().to_path_buf();
+        blocking(move || PosixACL::remap_ids_recursive(root, map)).await
+    }
+}
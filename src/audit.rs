@@ -0,0 +1,147 @@
+//! Detecting risky grants in ACLs, for security scanners.
+//!
+//! [`PosixACL::audit()`] inspects a single ACL, [`audit_tree()`] the ACLs of a whole directory
+//! tree.
+use crate::recursive::walk;
+use crate::util::{gid_to_name, uid_to_name};
+use crate::Qualifier::{Group, Mask, Other, User};
+use crate::{ACLEntry, PosixACL, Qualifier, RecursiveReport, ACL_WRITE};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Kind of a [`Finding`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FindingKind {
+    /// The `Other` entry grants write permission to everyone.
+    OtherWritable,
+    /// A `User` or `Group` entry grants permissions to a UID/GID that does not exist in the
+    /// user/group database, e.g. a deleted account whose ID may be reused later.
+    UnknownId,
+    /// The `Mask` entry grants permissions that no entry needs, so adding permissions to
+    /// entries later takes effect unexpectedly. The finding's `perm` contains the extra bits.
+    WideMask,
+    /// A `User` entry for root (UID 0), which is redundant since root bypasses permission
+    /// checks anyway. It often indicates a mistaken grant.
+    RootEntry,
+}
+
+/// A risky grant found by [`PosixACL::audit()`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Finding {
+    /// What was found.
+    pub kind: FindingKind,
+    /// The offending entry. Permissions are effective permissions, i.e. limited by the `Mask`,
+    /// except for `WideMask` findings.
+    pub entry: ACLEntry,
+}
+
+/// A [`Finding`] in an ACL of a file, returned by [`audit_tree()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TreeFinding {
+    /// Path of the file.
+    pub path: PathBuf,
+    /// `true` if found in the default ACL of a directory, `false` for access ACLs.
+    pub default: bool,
+    /// What was found.
+    pub finding: Finding,
+}
+
+impl PosixACL {
+    /// Inspect the ACL for risky grants, see [`FindingKind`] for what is reported. Entries with
+    /// no effective permissions are not reported.
+    ///
+    /// ```
+    /// use posix_acl::audit::FindingKind;
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+    /// let mut acl = PosixACL::new(0o646);
+    /// acl.set(Qualifier::User(0), ACL_READ);
+    /// acl.fix_mask();
+    ///
+    /// let kinds: Vec<FindingKind> = acl.audit().iter().map(|finding| finding.kind).collect();
+    /// assert_eq!(kinds, vec![FindingKind::RootEntry, FindingKind::OtherWritable]);
+    /// ```
+    #[must_use]
+    pub fn audit(&self) -> Vec<Finding> {
+        self.audit_with(&mut is_known_id)
+    }
+
+    fn audit_with(&self, is_known: &mut dyn FnMut(Qualifier) -> bool) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        let mut push = |kind, qual, perm| {
+            findings.push(Finding {
+                kind,
+                entry: ACLEntry { qual, perm },
+            });
+        };
+        for entry in self.effective_entries() {
+            if entry.perm == 0 {
+                continue;
+            }
+            match entry.qual {
+                User(0) => push(FindingKind::RootEntry, entry.qual, entry.perm),
+                User(_) | Group(_) if !is_known(entry.qual) => {
+                    push(FindingKind::UnknownId, entry.qual, entry.perm);
+                }
+                Other if entry.perm & ACL_WRITE != 0 => {
+                    push(FindingKind::OtherWritable, entry.qual, entry.perm);
+                }
+                _ => {}
+            }
+        }
+        if let Some(mask) = self.get(Mask) {
+            let extra = mask & !self.compute_mask();
+            if extra != 0 {
+                push(FindingKind::WideMask, Mask, extra);
+            }
+        }
+        findings
+    }
+}
+
+/// Audit the access ACL of `root` and all files and directories below it, and default ACLs of
+/// directories, see [`PosixACL::audit()`].
+///
+/// Symbolic links are not followed, except if `root` itself is one. Errors are collected in the
+/// returned report instead of stopping the operation.
+///
+/// ```no_run
+/// use posix_acl::audit::audit_tree;
+/// let (findings, report) = audit_tree("/srv");
+/// for found in &findings {
+///     println!("{}: {:?}", found.path.display(), found.finding);
+/// }
+/// ```
+pub fn audit_tree<P: AsRef<Path>>(root: P) -> (Vec<TreeFinding>, RecursiveReport) {
+    let mut known: HashMap<Qualifier, bool> = HashMap::new();
+    let mut is_known = |qual| *known.entry(qual).or_insert_with(|| is_known_id(qual));
+    let mut findings = Vec::new();
+    let report =
+        walk(root.as_ref(), true, |path, is_dir| {
+            let mut acls = vec![(false, PosixACL::read_acl(path)?)];
+            if is_dir {
+                acls.push((true, PosixACL::read_default_acl(path)?));
+            }
+            for (default, acl) in acls {
+                findings.extend(acl.audit_with(&mut is_known).into_iter().map(|finding| {
+                    TreeFinding {
+                        path: path.to_path_buf(),
+                        default,
+                        finding,
+                    }
+                }));
+            }
+            Ok(())
+        });
+    (findings, report)
+}
+
+fn is_known_id(qual: Qualifier) -> bool {
+    match qual {
+        User(uid) => uid_to_name(uid).is_some(),
+        Group(gid) => gid_to_name(gid).is_some(),
+        _ => true,
+    }
+}
@@ -0,0 +1,194 @@
+//! Abstraction over where ACLs are stored, allowing the filesystem to be replaced in tests.
+use crate::error::{ACLError, FLAG_DELETE, FLAG_WRITE};
+use crate::PosixACL;
+use acl_sys::{ACL_TYPE_ACCESS, ACL_TYPE_DEFAULT};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Storage of ACLs by path. Application code that takes a `&dyn ACLBackend` can be tested with
+/// [`MemoryBackend`] instead of a real ACL-enabled filesystem, while using [`FsBackend`] in
+/// production.
+///
+/// Methods mirror the corresponding [`PosixACL`] methods.
+#[allow(clippy::upper_case_acronyms)]
+#[allow(clippy::module_name_repetitions)]
+pub trait ACLBackend {
+    /// See [`PosixACL::read_acl()`].
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    fn read_acl(&self, path: &Path) -> Result<PosixACL, ACLError>;
+
+    /// See [`PosixACL::read_default_acl()`].
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    fn read_default_acl(&self, path: &Path) -> Result<PosixACL, ACLError>;
+
+    /// See [`PosixACL::write_acl()`].
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    /// * `ACLError::ValidationError`: The ACL failed validation.
+    fn write_acl(&self, path: &Path, acl: &mut PosixACL) -> Result<(), ACLError>;
+
+    /// See [`PosixACL::write_default_acl()`].
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    /// * `ACLError::ValidationError`: The ACL failed validation.
+    fn write_default_acl(&self, path: &Path, acl: &mut PosixACL) -> Result<(), ACLError>;
+
+    /// See [`PosixACL::delete_default_acl()`].
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    fn delete_default_acl(&self, path: &Path) -> Result<(), ACLError>;
+}
+
+/// [`ACLBackend`] using the real filesystem.
+#[derive(Copy, Clone, Debug, Default)]
+#[allow(clippy::module_name_repetitions)]
+pub struct FsBackend;
+
+impl ACLBackend for FsBackend {
+    fn read_acl(&self, path: &Path) -> Result<PosixACL, ACLError> {
+        PosixACL::read_acl(path)
+    }
+
+    fn read_default_acl(&self, path: &Path) -> Result<PosixACL, ACLError> {
+        PosixACL::read_default_acl(path)
+    }
+
+    fn write_acl(&self, path: &Path, acl: &mut PosixACL) -> Result<(), ACLError> {
+        acl.write_acl(path)
+    }
+
+    fn write_default_acl(&self, path: &Path, acl: &mut PosixACL) -> Result<(), ACLError> {
+        acl.write_default_acl(path)
+    }
+
+    fn delete_default_acl(&self, path: &Path) -> Result<(), ACLError> {
+        PosixACL::delete_default_acl(path)
+    }
+}
+
+/// In-memory [`ACLBackend`] for tests, mapping paths to ACLs.
+///
+/// Files and directories have to be added with [`add_file()`](Self::add_file) and
+/// [`add_dir()`](Self::add_dir) first, other paths are reported as not found. Like the kernel,
+/// default ACLs can only be written to directories, and written ACLs are validated.
+///
+/// ```
+/// use posix_acl::{ACLBackend, MemoryBackend, Qualifier, ACL_READ};
+/// use std::path::Path;
+///
+/// let backend = MemoryBackend::new();
+/// backend.add_file("/srv/report.txt", 0o640);
+///
+/// let path = Path::new("/srv/report.txt");
+/// let mut acl = backend.read_acl(path).unwrap();
+/// acl.set(Qualifier::User(1234), ACL_READ);
+/// backend.write_acl(path, &mut acl).unwrap();
+/// assert_eq!(backend.read_acl(path).unwrap().get(Qualifier::User(1234)), Some(ACL_READ));
+/// ```
+#[derive(Debug, Default)]
+#[allow(clippy::module_name_repetitions)]
+pub struct MemoryBackend {
+    files: RefCell<HashMap<PathBuf, MemoryFile>>,
+}
+
+#[derive(Debug)]
+struct MemoryFile {
+    access: PosixACL,
+    /// `None` for non-directories.
+    default: Option<PosixACL>,
+}
+
+impl MemoryBackend {
+    /// Create a backend with no files.
+    #[must_use]
+    pub fn new() -> MemoryBackend {
+        MemoryBackend::default()
+    }
+
+    /// Add a file with permissions `mode` and no extended ACL, replacing any existing entry.
+    pub fn add_file<P: AsRef<Path>>(&self, path: P, mode: u32) {
+        self.add(path.as_ref(), mode, None);
+    }
+
+    /// Add a directory with permissions `mode` and no extended or default ACL, replacing any
+    /// existing entry.
+    pub fn add_dir<P: AsRef<Path>>(&self, path: P, mode: u32) {
+        self.add(path.as_ref(), mode, Some(PosixACL::empty()));
+    }
+
+    fn add(&self, path: &Path, mode: u32, default: Option<PosixACL>) {
+        let file = MemoryFile {
+            access: PosixACL::new(mode),
+            default,
+        };
+        self.files.borrow_mut().insert(path.to_path_buf(), file);
+    }
+
+    fn with_file<T>(
+        &self,
+        path: &Path,
+        flags: u32,
+        func: impl FnOnce(&mut MemoryFile) -> Result<T, ACLError>,
+    ) -> Result<T, ACLError> {
+        match self.files.borrow_mut().get_mut(path) {
+            Some(file) => func(file),
+            None => Err(os_error(libc::ENOENT, flags, path)),
+        }
+    }
+}
+
+impl ACLBackend for MemoryBackend {
+    fn read_acl(&self, path: &Path) -> Result<PosixACL, ACLError> {
+        self.with_file(path, ACL_TYPE_ACCESS, |file| Ok(file.access.clone()))
+    }
+
+    fn read_default_acl(&self, path: &Path) -> Result<PosixACL, ACLError> {
+        self.with_file(path, ACL_TYPE_DEFAULT, |file| {
+            Ok(file.default.clone().unwrap_or_else(PosixACL::empty))
+        })
+    }
+
+    fn write_acl(&self, path: &Path, acl: &mut PosixACL) -> Result<(), ACLError> {
+        self.with_file(path, FLAG_WRITE | ACL_TYPE_ACCESS, |file| {
+            acl.try_fix_mask()?;
+            acl.validate()?;
+            file.access = acl.clone();
+            Ok(())
+        })
+    }
+
+    fn write_default_acl(&self, path: &Path, acl: &mut PosixACL) -> Result<(), ACLError> {
+        let flags = FLAG_WRITE | ACL_TYPE_DEFAULT;
+        self.with_file(path, flags, |file| match &mut file.default {
+            Some(default) => {
+                acl.try_fix_mask()?;
+                acl.validate()?;
+                *default = acl.clone();
+                Ok(())
+            }
+            None => Err(os_error(libc::EACCES, flags, path)),
+        })
+    }
+
+    fn delete_default_acl(&self, path: &Path) -> Result<(), ACLError> {
+        self.with_file(path, FLAG_DELETE | ACL_TYPE_DEFAULT, |file| {
+            if let Some(default) = &mut file.default {
+                *default = PosixACL::empty();
+            }
+            Ok(())
+        })
+    }
+}
+
+fn os_error(errno: i32, flags: u32, path: &Path) -> ACLError {
+    ACLError::io_error(io::Error::from_raw_os_error(errno), flags).with_path(path)
+}
@@ -0,0 +1,134 @@
+//! Fluent construction of ACLs.
+use crate::error::ACLError;
+use crate::names::{resolve_group, resolve_user};
+use crate::Qualifier::{Group, GroupObj, Mask, Other, Undefined, Unknown, User, UserObj};
+use crate::{PosixACL, Qualifier, ValidationReason, ACL_RWX};
+
+/// Builder for a [`PosixACL`], created with [`PosixACL::builder()`]. The ACL is validated in
+/// [`build()`](Self::build).
+///
+/// Later entries for the same qualifier replace earlier ones, like [`PosixACL::set()`].
+///
+/// ```
+/// use posix_acl::{PosixACL, Qualifier, ACL_READ, ACL_RWX, ACL_WRITE};
+/// let acl = PosixACL::builder()
+///     .mode(0o750)
+///     .user(1000, ACL_READ | ACL_WRITE)
+///     .group_name("root", ACL_READ)
+///     .mask_auto()
+///     .build()
+///     .unwrap();
+/// assert_eq!(acl.get(Qualifier::Group(0)), Some(ACL_READ));
+/// assert_eq!(acl.get(Qualifier::Mask), Some(ACL_RWX));
+/// ```
+#[derive(Debug, Default)]
+#[allow(clippy::module_name_repetitions)]
+pub struct PosixACLBuilder {
+    entries: Vec<(Qualifier, u32)>,
+    mask_auto: bool,
+    /// First error from resolving a name, reported by `build()`.
+    error: Option<ACLError>,
+}
+
+impl PosixACL {
+    /// Create a [`PosixACLBuilder`] with no entries.
+    #[must_use]
+    pub fn builder() -> PosixACLBuilder {
+        PosixACLBuilder::default()
+    }
+}
+
+impl PosixACLBuilder {
+    /// Set the `UserObj`, `GroupObj` and `Other` entries from file mode bits, like
+    /// [`PosixACL::new()`].
+    #[must_use]
+    pub fn mode(self, mode: u32) -> PosixACLBuilder {
+        self.entry(UserObj, (mode >> 6) & ACL_RWX)
+            .entry(GroupObj, (mode >> 3) & ACL_RWX)
+            .entry(Other, mode & ACL_RWX)
+    }
+
+    /// Add an entry with permissions `perm`.
+    #[must_use]
+    pub fn entry(mut self, qual: Qualifier, perm: u32) -> PosixACLBuilder {
+        self.entries.push((qual, perm));
+        self
+    }
+
+    /// Add a `User` entry for `uid`.
+    #[must_use]
+    pub fn user(self, uid: u32, perm: u32) -> PosixACLBuilder {
+        self.entry(User(uid), perm)
+    }
+
+    /// Add a `Group` entry for `gid`.
+    #[must_use]
+    pub fn group(self, gid: u32, perm: u32) -> PosixACLBuilder {
+        self.entry(Group(gid), perm)
+    }
+
+    /// Add a `User` entry for user `name`. If the user does not exist,
+    /// [`build()`](Self::build) fails.
+    #[must_use]
+    pub fn user_name(self, name: &str, perm: u32) -> PosixACLBuilder {
+        match resolve_user(name) {
+            Ok(uid) => self.user(uid, perm),
+            Err(err) => self.fail(err),
+        }
+    }
+
+    /// Add a `Group` entry for group `name`. If the group does not exist,
+    /// [`build()`](Self::build) fails.
+    #[must_use]
+    pub fn group_name(self, name: &str, perm: u32) -> PosixACLBuilder {
+        match resolve_group(name) {
+            Ok(gid) => self.group(gid, perm),
+            Err(err) => self.fail(err),
+        }
+    }
+
+    /// Set the `Mask` entry explicitly.
+    #[must_use]
+    pub fn mask(self, perm: u32) -> PosixACLBuilder {
+        self.entry(Mask, perm)
+    }
+
+    /// Calculate the `Mask` entry when building, like [`PosixACL::fix_mask()`]. Overrides
+    /// [`mask()`](Self::mask).
+    #[must_use]
+    pub fn mask_auto(mut self) -> PosixACLBuilder {
+        self.mask_auto = true;
+        self
+    }
+
+    fn fail(mut self, err: ACLError) -> PosixACLBuilder {
+        self.error.get_or_insert(err);
+        self
+    }
+
+    /// Build and validate the ACL.
+    ///
+    /// # Errors
+    /// * `ACLError::ParseError`: A user or group name does not exist.
+    /// * `ACLError::ValidationError`: The ACL failed validation, e.g. a `Mask` entry is needed
+    ///   but neither [`mask()`](Self::mask) nor [`mask_auto()`](Self::mask_auto) was used. See
+    ///   [`PosixACL::validate()`] for more information.
+    /// * `ACLError::IoError`: The platform failed to allocate memory.
+    pub fn build(self) -> Result<PosixACL, ACLError> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        let mut acl = PosixACL::empty();
+        for (qual, perm) in self.entries {
+            if matches!(qual, Undefined | Unknown(_)) {
+                return Err(ACLError::validation_error(ValidationReason::InvalidEntry));
+            }
+            acl.try_set(qual, perm)?;
+        }
+        if self.mask_auto {
+            acl.try_fix_mask()?;
+        }
+        acl.validate()?;
+        Ok(acl)
+    }
+}
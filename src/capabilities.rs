@@ -0,0 +1,189 @@
+//! Runtime introspection of the libacl library the process is linked against.
+//!
+//! The non-POSIX libacl extension functions used by the crate are resolved here at runtime, so
+//! that the crate also works with libraries that lack them. Each wrapper returns `None` when the
+//! function is not available and the caller falls back to an implementation of its own.
+use acl_sys::acl_t;
+use libc::mode_t;
+#[cfg(feature = "testing")]
+use std::cell::Cell;
+use std::ffi::{CStr, CString};
+use std::mem;
+use std::os::raw::{c_char, c_int, c_void};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Optional libacl features available at runtime, see [`Capabilities::detect()`].
+///
+/// The `acl_*` fields tell whether the libacl extension function of the same name is provided
+/// by the loaded library. These are non-POSIX functions that older or non-Linux implementations
+/// may lack.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Capabilities {
+    /// Version of the libacl shared library file, e.g. `1.1.2301` for acl 2.3.1. `None` if it
+    /// could not be determined, for example when libacl is linked statically.
+    pub version: Option<String>,
+    /// Reports why validation failed. Without it, [`ACLError::ValidationError`] always has
+    /// reason [`ValidationReason::Unknown`].
+    ///
+    /// [`ACLError::ValidationError`]: crate::ACLError::ValidationError
+    /// [`ValidationReason::Unknown`]: crate::ValidationReason::Unknown
+    pub acl_check: bool,
+    /// Compares two ACLs. Without it, `PosixACL` equality compares entries one by one.
+    pub acl_cmp: bool,
+    /// Counts entries. Without it, [`PosixACL::len()`] iterates over the entries.
+    ///
+    /// [`PosixACL::len()`]: crate::PosixACL::len
+    pub acl_entries: bool,
+    /// Converts a minimal ACL to mode bits. Without it, [`PosixACL::equivalent_mode()`]
+    /// inspects the entries itself.
+    ///
+    /// [`PosixACL::equivalent_mode()`]: crate::PosixACL::equivalent_mode
+    pub acl_equiv_mode: bool,
+    /// Checks an open file for an extended ACL. Without it,
+    /// [`PosixACL::has_extended_acl_fd()`] reads the size of the ACL extended attributes.
+    ///
+    /// [`PosixACL::has_extended_acl_fd()`]: crate::PosixACL::has_extended_acl_fd
+    pub acl_extended_fd: bool,
+    /// Checks a path for an extended ACL. Without it, [`PosixACL::has_extended_acl()`] reads
+    /// the size of the ACL extended attributes.
+    ///
+    /// [`PosixACL::has_extended_acl()`]: crate::PosixACL::has_extended_acl
+    pub acl_extended_file: bool,
+    /// Like `acl_extended_file`, without following symbolic links. Not used by the crate.
+    pub acl_extended_file_nofollow: bool,
+    /// Creates a minimal ACL from mode bits. Without it, [`PosixACL::new()`] adds the
+    /// `UserObj`, `GroupObj` and `Other` entries itself.
+    ///
+    /// [`PosixACL::new()`]: crate::PosixACL::new
+    pub acl_from_mode: bool,
+    /// Tests a single permission bit. Not used by the crate.
+    pub acl_get_perm: bool,
+    /// Formats an ACL with options. Without it, [`PosixACL::as_numeric_text()`] formats the
+    /// entries itself.
+    ///
+    /// [`PosixACL::as_numeric_text()`]: crate::PosixACL::as_numeric_text
+    pub acl_to_any_text: bool,
+}
+
+impl Capabilities {
+    /// Detect capabilities of the libacl library loaded in the current process.
+    ///
+    /// ```
+    /// use posix_acl::Capabilities;
+    /// let caps = Capabilities::detect();
+    /// if caps.acl_extended_file_nofollow {
+    ///     // ...
+    /// }
+    /// ```
+    #[must_use]
+    pub fn detect() -> Capabilities {
+        Capabilities {
+            version: library_version(),
+            acl_check: has_symbol("acl_check"),
+            acl_cmp: has_symbol("acl_cmp"),
+            acl_entries: has_symbol("acl_entries"),
+            acl_equiv_mode: has_symbol("acl_equiv_mode"),
+            acl_extended_fd: has_symbol("acl_extended_fd"),
+            acl_extended_file: has_symbol("acl_extended_file"),
+            acl_extended_file_nofollow: has_symbol("acl_extended_file_nofollow"),
+            acl_from_mode: has_symbol("acl_from_mode"),
+            acl_get_perm: has_symbol("acl_get_perm"),
+            acl_to_any_text: has_symbol("acl_to_any_text"),
+        }
+    }
+}
+
+/// Address of an extension function that has not been looked up yet. Missing functions are
+/// cached as 0.
+const UNRESOLVED: usize = usize::MAX;
+
+/// Look up the symbol `name` (NUL-terminated) once and cache its address in `cache`.
+fn resolve(cache: &AtomicUsize, name: &str) -> usize {
+    let mut addr = cache.load(Ordering::Relaxed);
+    if addr == UNRESOLVED {
+        let c_name = CStr::from_bytes_with_nul(name.as_bytes()).unwrap();
+        addr = unsafe { libc::dlsym(libc::RTLD_DEFAULT, c_name.as_ptr()) } as usize;
+        cache.store(addr, Ordering::Relaxed);
+    }
+    addr
+}
+
+#[cfg(feature = "testing")]
+thread_local! {
+    /// Set by [`without_extensions()`](crate::testing::without_extensions).
+    pub(crate) static DISABLED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether extension functions are treated as missing on the current thread.
+#[cfg(feature = "testing")]
+fn disabled() -> bool {
+    DISABLED.with(Cell::get)
+}
+
+#[cfg(not(feature = "testing"))]
+fn disabled() -> bool {
+    false
+}
+
+/// Define a wrapper for the libacl extension function `$name`, returning `None` if the loaded
+/// library does not provide it.
+macro_rules! extension {
+    ($name:ident($($arg:ident: $ty:ty),*) -> $ret:ty) => {
+        pub(crate) unsafe fn $name($($arg: $ty),*) -> Option<$ret> {
+            static ADDR: AtomicUsize = AtomicUsize::new(UNRESOLVED);
+            if disabled() {
+                return None;
+            }
+            match resolve(&ADDR, concat!(stringify!($name), "\0")) {
+                0 => None,
+                addr => {
+                    let func: unsafe extern "C" fn($($ty),*) -> $ret = mem::transmute(addr);
+                    Some(func($($arg),*))
+                }
+            }
+        }
+    };
+}
+
+extension!(acl_check(acl: acl_t, last: *mut c_int) -> c_int);
+extension!(acl_cmp(acl1: acl_t, acl2: acl_t) -> c_int);
+extension!(acl_entries(acl: acl_t) -> c_int);
+extension!(acl_equiv_mode(acl: acl_t, mode: *mut mode_t) -> c_int);
+extension!(acl_extended_fd(fd: c_int) -> c_int);
+extension!(acl_extended_file(path: *const c_char) -> c_int);
+extension!(acl_from_mode(mode: mode_t) -> acl_t);
+extension!(
+    acl_to_any_text(acl: acl_t, prefix: *const c_char, separator: c_char, options: c_int)
+        -> *mut c_char
+);
+
+fn lookup_symbol(name: &str) -> *mut c_void {
+    let c_name = CString::new(name).unwrap();
+    unsafe { libc::dlsym(libc::RTLD_DEFAULT, c_name.as_ptr()) }
+}
+
+fn has_symbol(name: &str) -> bool {
+    !lookup_symbol(name).is_null()
+}
+
+/// Find the file name of the shared library that defines `acl_init` and extract the version
+/// suffix, e.g. `libacl.so.1.1.2301` -> `1.1.2301`.
+fn library_version() -> Option<String> {
+    let addr = lookup_symbol("acl_init");
+    if addr.is_null() {
+        return None;
+    }
+    let mut info: libc::Dl_info = unsafe { mem::zeroed() };
+    if unsafe { libc::dladdr(addr, &mut info) } == 0 || info.dli_fname.is_null() {
+        return None;
+    }
+    let fname = unsafe { CStr::from_ptr(info.dli_fname) }.to_str().ok()?;
+    // Usually loaded through the SONAME symlink (libacl.so.1), the real file has the full version.
+    let path = Path::new(fname).canonicalize().ok()?;
+    let name = path.file_name()?.to_str()?;
+    let (_, version) = name.split_once(".so.")?;
+    Some(version.to_string())
+}
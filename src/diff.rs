@@ -0,0 +1,60 @@
+//! Comparing two ACLs entry by entry.
+use crate::{ACLEntry, PosixACL, Qualifier};
+
+/// Differences between two ACLs, returned by [`PosixACL::diff()`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+#[allow(clippy::upper_case_acronyms)]
+pub struct ACLDiff {
+    /// Entries that only exist in the new ACL.
+    pub added: Vec<ACLEntry>,
+    /// Entries that only exist in the old ACL.
+    pub removed: Vec<ACLEntry>,
+    /// Entries that exist in both ACLs with different permissions: qualifier, old `perm` and new
+    /// `perm`.
+    pub changed: Vec<(Qualifier, u32, u32)>,
+}
+
+impl ACLDiff {
+    /// Return `true` if the ACLs have the same entries.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl PosixACL {
+    /// Compare this ACL to `other`, returning the entries that writing `other` in place of this
+    /// ACL would add, remove or change.
+    ///
+    /// ```
+    /// use posix_acl::{ACLEntry, PosixACL, Qualifier, ACL_READ, ACL_WRITE};
+    /// let old = PosixACL::new(0o640);
+    /// let mut new = PosixACL::new(0o600);
+    /// new.set(Qualifier::User(1234), ACL_READ);
+    ///
+    /// let diff = old.diff(&new);
+    /// assert_eq!(diff.added, vec![ACLEntry { qual: Qualifier::User(1234), perm: ACL_READ }]);
+    /// assert_eq!(diff.removed, vec![]);
+    /// assert_eq!(diff.changed, vec![(Qualifier::GroupObj, ACL_READ, 0)]);
+    /// ```
+    #[must_use]
+    pub fn diff(&self, other: &PosixACL) -> ACLDiff {
+        let mut diff = ACLDiff::default();
+        for entry in self.entries() {
+            match other.get(entry.qual) {
+                None => diff.removed.push(entry),
+                Some(perm) if perm != entry.perm => {
+                    diff.changed.push((entry.qual, entry.perm, perm));
+                }
+                Some(_) => {}
+            }
+        }
+        for entry in other.entries() {
+            if self.get(entry.qual).is_none() {
+                diff.added.push(entry);
+            }
+        }
+        diff
+    }
+}
@@ -0,0 +1,514 @@
+//! Reading and writing the dump format of `getfacl -R`, as restored by `setfacl --restore`.
+//!
+//! A dump consists of one record per file, separated by blank lines:
+//!
+//! ```text
+//! # file: srv/data
+//! # owner: root
+//! # group: staff
+//! user::rwx
+//! group::r-x
+//! other::---
+//! default:user::rwx
+//! default:group::r-x
+//! default:other::---
+//! ```
+//!
+//! [`DumpReader`] parses records one at a time from any [`BufRead`], so large dumps don't need to
+//! fit in memory. [`DumpEntry::write_to()`] serializes a record. When restoring dumps from other
+//! hosts, user and group names can be translated with a [`NameMap`].
+//!
+//! Entries can carry free-form annotations, e.g. why a grant was made, which are kept in the dump
+//! only. They are written as comments after the entry, which `setfacl --restore` ignores:
+//!
+//! ```text
+//! user:alice:rw-  # granted for ticket OPS-1234
+//! ```
+//!
+//! ```
+//! use posix_acl::dump::{DumpEntry, DumpReader};
+//! # std::fs::File::create("/tmp/posix-acl-dump").unwrap();
+//! let entry = DumpEntry::read("/tmp/posix-acl-dump").unwrap();
+//! let mut out = Vec::new();
+//! entry.write_to(&mut out).unwrap();
+//!
+//! let parsed: Vec<DumpEntry> = DumpReader::new(&out[..]).collect::<Result<_, _>>().unwrap();
+//! assert_eq!(parsed, vec![entry]);
+//! ```
+use crate::text::named_entry_text;
+use crate::util::{gid_to_name, uid_to_name};
+use crate::{ACLError, PosixACL, Qualifier};
+use acl_sys::{ACL_TYPE_ACCESS, ACL_TYPE_DEFAULT};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fmt::{self, Write as _};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+const PREFIX_FILE: &str = "# file: ";
+const PREFIX_OWNER: &str = "# owner: ";
+const PREFIX_GROUP: &str = "# group: ";
+const PREFIX_FLAGS: &str = "# flags: ";
+const PREFIX_DEFAULT: &str = "default:";
+/// Comment added by `getfacl` to entries limited by the mask; not an annotation.
+const PREFIX_EFFECTIVE: &str = "effective:";
+
+/// ACLs and ownership of a single file in a dump.
+#[derive(Clone, Debug, PartialEq)]
+#[allow(clippy::module_name_repetitions)]
+pub struct DumpEntry {
+    /// Path of the file. `getfacl` removes the leading `/` of absolute paths, making them
+    /// relative to the directory where the dump is restored.
+    pub path: PathBuf,
+    /// Owner of the file, as user name or numeric UID.
+    pub owner: Option<String>,
+    /// Owning group of the file, as group name or numeric GID.
+    pub group: Option<String>,
+    /// Setuid, setgid and sticky bits in `getfacl` notation, e.g. `-s-`. `None` if none are set.
+    pub flags: Option<String>,
+    /// The access ACL.
+    pub access: PosixACL,
+    /// The default ACL, for directories that have one.
+    pub default: Option<PosixACL>,
+    /// Annotations of access ACL entries, see the [module documentation](self). Line breaks
+    /// are written as spaces.
+    pub annotations: HashMap<Qualifier, String>,
+    /// Annotations of default ACL entries, like `annotations`.
+    pub default_annotations: HashMap<Qualifier, String>,
+}
+
+impl DumpEntry {
+    /// Create a dump entry from the current ACLs and ownership of `path`, like `getfacl` does.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<DumpEntry, ACLError> {
+        let path = path.as_ref();
+        let meta = fs::metadata(path).map_err(|err| ACLError::io_error(err, 0).with_path(path))?;
+        let mode = meta.permissions().mode();
+        let flags = if mode & 0o7000 == 0 {
+            None
+        } else {
+            let flag = |bit: u32, ch: char| if mode & bit == 0 { '-' } else { ch };
+            Some(
+                [flag(0o4000, 's'), flag(0o2000, 's'), flag(0o1000, 't')]
+                    .iter()
+                    .collect(),
+            )
+        };
+        let default = if meta.is_dir() {
+            Some(PosixACL::read_default_acl(path)?).filter(|acl| !acl.is_empty())
+        } else {
+            None
+        };
+        Ok(DumpEntry {
+            path: path.to_path_buf(),
+            owner: Some(uid_to_name(meta.uid()).unwrap_or_else(|| meta.uid().to_string())),
+            group: Some(gid_to_name(meta.gid()).unwrap_or_else(|| meta.gid().to_string())),
+            flags,
+            access: PosixACL::read_acl(path)?,
+            default,
+            annotations: HashMap::new(),
+            default_annotations: HashMap::new(),
+        })
+    }
+
+    /// Write the ACLs of this entry to its path, like `setfacl --restore`. ACLs are written
+    /// exactly, including the `Mask` entry. If the path is a directory and the entry has no
+    /// default ACL, any existing default ACL is removed.
+    ///
+    /// Relative paths are resolved against the current directory. Ownership and flags are not
+    /// restored.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    /// * `ACLError::ValidationError`: An ACL failed validation.
+    pub fn restore(&self) -> Result<(), ACLError> {
+        let meta = fs::metadata(&self.path)
+            .map_err(|err| ACLError::io_error(err, 0).with_path(&self.path))?;
+        self.access.write_acl_exact(&self.path, ACL_TYPE_ACCESS)?;
+        match &self.default {
+            Some(acl) => acl.write_acl_exact(&self.path, ACL_TYPE_DEFAULT),
+            None if meta.is_dir() => PosixACL::delete_default_acl(&self.path),
+            None => Ok(()),
+        }
+    }
+
+    /// Serialize the entry in the dump format, followed by a blank line.
+    ///
+    /// The path is written as it is, like `getfacl -p`. The `Display` implementation removes
+    /// the leading `/` like plain `getfacl`.
+    ///
+    /// # Errors
+    /// Errors from writing to `out` are returned.
+    pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        out.write_all(self.record_text(self.path.as_os_str()).as_bytes())
+    }
+
+    fn record_text(&self, path: &OsStr) -> String {
+        let mut out = String::new();
+        // Writing to String cannot fail.
+        let _ = writeln!(out, "{PREFIX_FILE}{}", quote(path));
+        for (prefix, value) in &[
+            (PREFIX_OWNER, &self.owner),
+            (PREFIX_GROUP, &self.group),
+            (PREFIX_FLAGS, &self.flags),
+        ] {
+            if let Some(value) = value {
+                let _ = writeln!(out, "{prefix}{value}");
+            }
+        }
+        write_entries(&mut out, "", &self.access, &self.annotations);
+        if let Some(acl) = &self.default {
+            write_entries(&mut out, PREFIX_DEFAULT, acl, &self.default_annotations);
+        }
+        out.push('\n');
+        out
+    }
+}
+
+/// Write the entries of `acl` with their annotations, one per line.
+fn write_entries(
+    out: &mut String,
+    prefix: &str,
+    acl: &PosixACL,
+    annotations: &HashMap<Qualifier, String>,
+) {
+    for entry in acl.entries() {
+        let text = named_entry_text(entry);
+        // Writing to String cannot fail.
+        let _ = match annotations.get(&entry.qual) {
+            Some(note) => writeln!(out, "{prefix}{text}  # {}", note.replace('\n', " ")),
+            None => writeln!(out, "{prefix}{text}"),
+        };
+    }
+}
+
+/// Formats the entry like the output of `getfacl`, including the leading `/` of absolute paths
+/// being removed. Combined with [`DumpEntry::read()`], this matches `getfacl` output.
+///
+/// ```
+/// use posix_acl::dump::DumpEntry;
+/// use posix_acl::PosixACL;
+/// # use std::collections::HashMap;
+/// let entry = DumpEntry {
+///     path: "/srv/data".into(),
+///     owner: Some("root".to_string()),
+///     group: Some("staff".to_string()),
+///     flags: None,
+///     access: PosixACL::new(0o640),
+///     default: None,
+///     annotations: HashMap::new(),
+///     default_annotations: HashMap::new(),
+/// };
+/// assert_eq!(
+///     entry.to_string(),
+///     concat!(
+///         "# file: srv/data\n",
+///         "# owner: root\n",
+///         "# group: staff\n",
+///         "user::rw-\n",
+///         "group::r--\n",
+///         "other::---\n",
+///         "\n",
+///     )
+/// );
+/// ```
+impl fmt::Display for DumpEntry {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = self.path.strip_prefix("/").unwrap_or(&self.path);
+        fmt.write_str(&self.record_text(path.as_os_str()))
+    }
+}
+
+/// Iterator parsing [`DumpEntry`] records from a dump, one at a time.
+///
+/// Iteration stops after the first error.
+#[derive(Debug)]
+#[allow(clippy::module_name_repetitions)]
+pub struct DumpReader<R> {
+    lines: io::Lines<R>,
+    line_no: usize,
+    done: bool,
+    names: NameMap,
+}
+
+impl<R: BufRead> DumpReader<R> {
+    /// Create a reader parsing records from `reader`.
+    pub fn new(reader: R) -> DumpReader<R> {
+        DumpReader {
+            lines: reader.lines(),
+            line_no: 0,
+            done: false,
+            names: NameMap::default(),
+        }
+    }
+
+    /// Translate user and group names in ACL entries using `names`, e.g. when restoring a dump
+    /// made on another host where accounts have different IDs.
+    #[must_use]
+    pub fn name_map(mut self, names: NameMap) -> DumpReader<R> {
+        self.names = names;
+        self
+    }
+
+    /// Read lines of the next record, skipping leading blank lines. Returns the line number where
+    /// the record starts, or `None` at the end of input.
+    fn read_record(&mut self) -> Result<Option<(usize, Vec<String>)>, ACLError> {
+        let mut start = 0;
+        let mut record = Vec::new();
+        for line in &mut self.lines {
+            let line = line.map_err(|err| ACLError::io_error(err, 0))?;
+            self.line_no += 1;
+            if line.trim().is_empty() {
+                if record.is_empty() {
+                    continue;
+                }
+                break;
+            }
+            if record.is_empty() {
+                start = self.line_no;
+            }
+            record.push(line);
+        }
+        Ok(if record.is_empty() {
+            None
+        } else {
+            Some((start, record))
+        })
+    }
+
+    fn parse_record(&self, start: usize, record: &[String]) -> Result<DumpEntry, ACLError> {
+        let invalid = |msg: &str| ACLError::parse_error(format!("{msg} in dump at line {start}"));
+        let mut path = None;
+        let (mut owner, mut group, mut flags) = (None, None, None);
+        let mut access = String::new();
+        let mut default = String::new();
+        let mut annotations = Vec::new();
+
+        for line in record {
+            if let Some(value) = line.strip_prefix(PREFIX_FILE) {
+                path = Some(unquote(value).ok_or_else(|| invalid("Invalid file name"))?);
+            } else if let Some(value) = line.strip_prefix(PREFIX_OWNER) {
+                owner = Some(value.to_string());
+            } else if let Some(value) = line.strip_prefix(PREFIX_GROUP) {
+                group = Some(value.to_string());
+            } else if let Some(value) = line.strip_prefix(PREFIX_FLAGS) {
+                flags = Some(value.to_string());
+            } else {
+                let (entry, comment) = match line.split_once('#') {
+                    Some((entry, comment)) => (entry.trim(), comment.trim()),
+                    None => (line.trim(), ""),
+                };
+                if entry.is_empty() {
+                    continue;
+                }
+                let (is_default, entry) = match entry.strip_prefix(PREFIX_DEFAULT) {
+                    Some(entry) => (true, entry),
+                    None => (false, entry),
+                };
+                let entry = match self.names.map_entry(entry).map_err(|msg| invalid(&msg))? {
+                    Some(entry) => entry,
+                    None => continue,
+                };
+                if !comment.is_empty() && !comment.starts_with(PREFIX_EFFECTIVE) {
+                    annotations.push((is_default, entry.clone(), comment.to_string()));
+                }
+                let text = if is_default {
+                    &mut default
+                } else {
+                    &mut access
+                };
+                text.push_str(&entry);
+                text.push('\n');
+            }
+        }
+
+        let path = path.ok_or_else(|| invalid("Missing file name"))?;
+        if access.is_empty() {
+            return Err(invalid("Missing ACL entries"));
+        }
+        let parse = |text: &str| PosixACL::from_text(text).map_err(|_| invalid("Invalid ACL"));
+        let (mut access_notes, mut default_notes) = (HashMap::new(), HashMap::new());
+        for (is_default, entry, note) in annotations {
+            // Parse the entry again to find its qualifier, names are resolved the same way.
+            if let Some(qual) = parse(&entry)?.entries().first().map(|entry| entry.qual) {
+                let notes = if is_default {
+                    &mut default_notes
+                } else {
+                    &mut access_notes
+                };
+                notes.insert(qual, note);
+            }
+        }
+        Ok(DumpEntry {
+            path,
+            owner,
+            group,
+            flags,
+            access: parse(&access)?,
+            default: if default.is_empty() {
+                None
+            } else {
+                Some(parse(&default)?)
+            },
+            annotations: access_notes,
+            default_annotations: default_notes,
+        })
+    }
+}
+
+impl<R: BufRead> Iterator for DumpReader<R> {
+    type Item = Result<DumpEntry, ACLError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = match self.read_record() {
+            Ok(Some((start, record))) => self.parse_record(start, &record),
+            Ok(None) => {
+                self.done = true;
+                return None;
+            }
+            Err(err) => Err(err),
+        };
+        self.done = result.is_err();
+        Some(result)
+    }
+}
+
+/// Translation of user and group names in ACL entries to numeric IDs, used by
+/// [`DumpReader::name_map()`].
+///
+/// Names not listed in the map are handled according to [`UnmappedNames`]. Numeric IDs are
+/// always used as they are.
+///
+/// ```
+/// use posix_acl::dump::{DumpReader, NameMap, UnmappedNames};
+/// use posix_acl::{Qualifier, ACL_READ};
+/// let dump = "# file: data\nuser::rw-\nuser:alice:r--\ngroup::r--\nmask::r--\nother::---\n";
+/// let names = NameMap::new()
+///     .user("alice", 1234)
+///     .unmapped(UnmappedNames::Error);
+/// let mut reader = DumpReader::new(dump.as_bytes()).name_map(names);
+///
+/// let entry = reader.next().unwrap().unwrap();
+/// assert_eq!(entry.access.get(Qualifier::User(1234)), Some(ACL_READ));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct NameMap {
+    users: HashMap<String, u32>,
+    groups: HashMap<String, u32>,
+    unmapped: UnmappedNames,
+}
+
+/// What to do with user and group names that are not listed in a [`NameMap`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum UnmappedNames {
+    /// Resolve the name using the local user and group database. An error is returned if it
+    /// does not exist.
+    Local,
+    /// Leave out the ACL entry.
+    Skip,
+    /// Return an error.
+    Error,
+}
+
+impl Default for UnmappedNames {
+    fn default() -> Self {
+        UnmappedNames::Local
+    }
+}
+
+impl NameMap {
+    /// Create a map without names, resolving all names locally.
+    #[must_use]
+    pub fn new() -> NameMap {
+        NameMap::default()
+    }
+
+    /// Map user `name` to `uid`.
+    #[must_use]
+    pub fn user(mut self, name: &str, uid: u32) -> NameMap {
+        self.users.insert(name.to_string(), uid);
+        self
+    }
+
+    /// Map group `name` to `gid`.
+    #[must_use]
+    pub fn group(mut self, name: &str, gid: u32) -> NameMap {
+        self.groups.insert(name.to_string(), gid);
+        self
+    }
+
+    /// Set how names not in the map are handled, see [`UnmappedNames`].
+    #[must_use]
+    pub fn unmapped(mut self, unmapped: UnmappedNames) -> NameMap {
+        self.unmapped = unmapped;
+        self
+    }
+
+    /// Replace the name in a text ACL entry by its mapped ID. Returns `None` if the entry should
+    /// be skipped.
+    fn map_entry(&self, entry: &str) -> Result<Option<String>, String> {
+        let fields: Vec<&str> = entry.splitn(3, ':').collect();
+        let (map, kind) = match fields.as_slice() {
+            [_, name, _] if name.is_empty() || name.parse::<u32>().is_ok() => {
+                return Ok(Some(entry.to_string()))
+            }
+            ["u" | "user", _, _] => (&self.users, "user"),
+            ["g" | "group", _, _] => (&self.groups, "group"),
+            _ => return Ok(Some(entry.to_string())),
+        };
+        if let Some(id) = map.get(fields[1]) {
+            return Ok(Some(format!("{}:{id}:{}", fields[0], fields[2])));
+        }
+        match self.unmapped {
+            UnmappedNames::Local => Ok(Some(entry.to_string())),
+            UnmappedNames::Skip => Ok(None),
+            UnmappedNames::Error => Err(format!("Unmapped {kind} name {:?}", fields[1])),
+        }
+    }
+}
+
+/// Escape file names like `getfacl`: backslashes, whitespace and unprintable bytes are written as
+/// octal escapes `\ooo`.
+fn quote(name: &OsStr) -> String {
+    let mut out = String::new();
+    for &byte in name.as_bytes() {
+        if byte == b'\\' {
+            out.push_str("\\\\");
+        } else if byte.is_ascii_graphic() {
+            out.push(char::from(byte));
+        } else {
+            // Writing to String cannot fail.
+            let _ = write!(out, "\\{byte:03o}");
+        }
+    }
+    out
+}
+
+/// Reverse of [`quote()`]. Returns `None` for invalid escapes.
+fn unquote(text: &str) -> Option<PathBuf> {
+    let mut out = Vec::new();
+    let mut bytes = text.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte != b'\\' {
+            out.push(byte);
+            continue;
+        }
+        match bytes.next()? {
+            b'\\' => out.push(b'\\'),
+            first => {
+                let digits = [first, bytes.next()?, bytes.next()?];
+                let digits = std::str::from_utf8(&digits).ok()?;
+                out.push(u8::from_str_radix(digits, 8).ok()?);
+            }
+        }
+    }
+    Some(PathBuf::from(OsStr::from_bytes(&out)))
+}
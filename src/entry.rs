@@ -1,13 +1,18 @@
-use crate::util::{check_pointer, check_return, AutoPtr};
-use crate::Qualifier::{Group, GroupObj, Mask, Other, Undefined, User, UserObj};
+use crate::util::{try_pointer, try_return, AutoPtr};
+use crate::ACLError;
+use crate::Qualifier::{Group, GroupObj, Mask, Other, Undefined, Unknown, User, UserObj};
 use acl_sys::{
     acl_entry_t, acl_get_permset, acl_get_qualifier, acl_get_tag_type, acl_permset_t, ACL_GROUP,
-    ACL_GROUP_OBJ, ACL_MASK, ACL_OTHER, ACL_UNDEFINED_TAG, ACL_USER, ACL_USER_OBJ,
+    ACL_GROUP_OBJ, ACL_MASK, ACL_OTHER, ACL_TYPE_ACCESS, ACL_UNDEFINED_TAG, ACL_USER, ACL_USER_OBJ,
 };
+use std::cmp::Ordering;
 use std::ptr::null_mut;
 
 /// The subject of a permission grant.
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+///
+/// Qualifiers are ordered in the POSIX canonical entry order, see
+/// [`PosixACL::canonicalize()`](crate::PosixACL::canonicalize).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Qualifier {
     /// Unrecognized/corrupt entries
     Undefined,
@@ -23,6 +28,11 @@ pub enum Qualifier {
     Group(u32),
     /// Auto-generated entry
     Mask,
+    /// Entry with a tag type not known to this crate, containing the raw tag value.
+    ///
+    /// Allows reading and removing entries with tags introduced by future platforms. libacl does
+    /// not allow creating such entries, so [`PosixACL::set()`](crate::PosixACL::set) panics.
+    Unknown(i32),
 }
 
 impl Qualifier {
@@ -35,6 +45,7 @@ impl Qualifier {
             Group(_) => ACL_GROUP,
             Mask => ACL_MASK,
             Other => ACL_OTHER,
+            Unknown(tag) => tag,
         }
     }
     pub(crate) fn uid(self) -> Option<u32> {
@@ -43,36 +54,87 @@ impl Qualifier {
             _ => None,
         }
     }
+    /// Sort key of the POSIX canonical entry order: `UserObj`, `User` entries by UID, `GroupObj`,
+    /// `Group` entries by GID, `Mask`, `Other`. Unrecognized entries sort last.
+    pub(crate) fn canonical_key(self) -> (u8, u32) {
+        match self {
+            UserObj => (0, 0),
+            User(uid) => (1, uid),
+            GroupObj => (2, 0),
+            Group(gid) => (3, gid),
+            Mask => (4, 0),
+            Other => (5, 0),
+            Undefined | Unknown(_) => (6, 0),
+        }
+    }
     /// Convert C type `acl_entry_t` to Rust Qualifier
-    pub(crate) fn from_entry(entry: acl_entry_t) -> Qualifier {
-        let tag_type = 0;
-        let ret = unsafe { acl_get_tag_type(entry, &tag_type) };
-        check_return(ret, "acl_get_tag_type");
-        match tag_type {
+    pub(crate) fn try_from_entry(entry: acl_entry_t) -> Result<Qualifier, ACLError> {
+        let tag_type = Qualifier::get_entry_tag(entry)?;
+        Ok(match tag_type {
             ACL_UNDEFINED_TAG => Undefined,
             ACL_USER_OBJ => UserObj,
             ACL_GROUP_OBJ => GroupObj,
-            ACL_USER => User(Qualifier::get_entry_uid(entry)),
-            ACL_GROUP => Group(Qualifier::get_entry_uid(entry)),
+            ACL_USER => User(Qualifier::try_get_entry_uid(entry)?),
+            ACL_GROUP => Group(Qualifier::try_get_entry_uid(entry)?),
             ACL_MASK => Mask,
             ACL_OTHER => Other,
-            _ => {
-                panic!("Unexpected tag type {}", tag_type);
-            }
-        }
+            _ => Unknown(tag_type),
+        })
     }
-    /// Helper function for `from_entry()`
-    fn get_entry_uid(entry: acl_entry_t) -> u32 {
-        unsafe {
-            let uid = AutoPtr(acl_get_qualifier(entry).cast::<u32>());
-            check_pointer(uid.0, "acl_get_qualifier");
-            *uid.0
+    /// Check whether C type `acl_entry_t` has this qualifier. Cheaper than comparing to
+    /// `try_from_entry()`, the UID/GID is only read if the tag type matches.
+    pub(crate) fn matches_entry(self, entry: acl_entry_t) -> Result<bool, ACLError> {
+        if Qualifier::get_entry_tag(entry)? != self.tag_type() {
+            return Ok(false);
+        }
+        match self.uid() {
+            Some(uid) => Ok(Qualifier::try_get_entry_uid(entry)? == uid),
+            None => Ok(true),
         }
     }
+    /// Helper function for `try_from_entry()`
+    fn get_entry_tag(entry: acl_entry_t) -> Result<i32, ACLError> {
+        let tag_type = 0;
+        try_return(
+            unsafe { acl_get_tag_type(entry, &tag_type) },
+            ACL_TYPE_ACCESS,
+        )?;
+        Ok(tag_type)
+    }
+    /// Helper function for `try_from_entry()`. The UID/GID is returned in memory allocated by the
+    /// platform, so this can fail.
+    fn try_get_entry_uid(entry: acl_entry_t) -> Result<u32, ACLError> {
+        let ptr = unsafe { acl_get_qualifier(entry) }.cast::<u32>();
+        try_pointer(ptr, ACL_TYPE_ACCESS)?;
+        let uid = AutoPtr(ptr);
+        Ok(unsafe { *uid.0 })
+    }
+}
+
+impl Ord for Qualifier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let tag = |qual: &Qualifier| match qual {
+            Unknown(tag) => Some(*tag),
+            _ => None,
+        };
+        self.canonical_key()
+            .cmp(&other.canonical_key())
+            .then_with(|| tag(self).cmp(&tag(other)))
+    }
+}
+
+impl PartialOrd for Qualifier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 /// Returned from [`PosixACL::entries()`](crate::PosixACL::entries).
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+///
+/// Entries are ordered by qualifier, in the POSIX canonical entry order, then by permissions.
+/// Sorting entries gives the same order as
+/// [`PosixACL::entries_sorted()`](crate::PosixACL::entries_sorted).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[allow(clippy::upper_case_acronyms)]
 #[allow(clippy::module_name_repetitions)]
 pub struct ACLEntry {
@@ -82,17 +144,18 @@ pub struct ACLEntry {
 
 impl ACLEntry {
     /// Convert C type `acl_entry_t` to Rust `ACLEntry`
-    pub(crate) fn from_entry(entry: acl_entry_t) -> ACLEntry {
-        let perm;
+    pub(crate) fn try_from_entry(entry: acl_entry_t) -> Result<ACLEntry, ACLError> {
+        Ok(ACLEntry {
+            qual: Qualifier::try_from_entry(entry)?,
+            perm: ACLEntry::perm_from_entry(entry)?,
+        })
+    }
+    /// Read only the permissions of C type `acl_entry_t`
+    pub(crate) fn perm_from_entry(entry: acl_entry_t) -> Result<u32, ACLError> {
         let mut permset: acl_permset_t = null_mut();
         unsafe {
-            let ret = acl_get_permset(entry, &mut permset);
-            check_return(ret, "acl_get_permset");
-            perm = *(permset as *const u32);
-        }
-        ACLEntry {
-            qual: Qualifier::from_entry(entry),
-            perm,
+            try_return(acl_get_permset(entry, &mut permset), ACL_TYPE_ACCESS)?;
+            Ok(*(permset as *const u32))
         }
     }
 }
@@ -1,11 +1,18 @@
-use crate::error::ACLError::{IoError, ValidationError};
+use crate::error::ACLError::{Conflict, IoError, ParseError, Unsupported, ValidationError};
 use acl_sys::{ACL_TYPE_ACCESS, ACL_TYPE_DEFAULT};
 use std::error::Error;
 use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
 use std::{fmt, io};
 
 /// Use a bit flag to track whether error was caused by read or write
 pub(crate) const FLAG_WRITE: u32 = 0x4000_0000;
+/// Bit flag for errors from changing file ownership
+pub(crate) const FLAG_CHOWN: u32 = 0x2000_0000;
+/// Bit flag for errors from deleting an ACL
+pub(crate) const FLAG_DELETE: u32 = 0x1000_0000;
+/// All bit flags describing the operation
+const FLAGS_OP: u32 = FLAG_WRITE | FLAG_CHOWN | FLAG_DELETE;
 
 /// Error type from ACL operations. To distinguish different causes, use the [`kind()`](Self::kind)
 /// method.
@@ -13,38 +20,106 @@ pub(crate) const FLAG_WRITE: u32 = 0x4000_0000;
 // Perhaps an overkill, I could have used io::Error instead.
 // But now that I wrote this, might as well keep it. :)
 #[derive(Debug)]
+#[non_exhaustive]
 #[allow(clippy::upper_case_acronyms)]
 #[allow(clippy::module_name_repetitions)]
 pub enum ACLError {
     /// Filesystem error while reading or writing ACL (file not found, permission denied, etc).
     IoError(IoErrorDetail),
-    /// ACL is not valid and cannot be written.
-    ///
-    /// Unfortunately it is not possible to provide detailed reasons, but mainly it can mean:
-    /// * Required entries are missing (`UserObj`, `GroupObj`, `Mask` and `Other`).
-    /// * ACL contains entries that are not unique.
+    /// ACL is not valid and cannot be written. Use [`validation_reason()`](Self::validation_reason)
+    /// to find out why.
     ValidationError(ValidationErrorDetail),
+    /// Input could not be parsed as an ACL.
+    ParseError(ParseErrorDetail),
+    /// The filesystem does not support POSIX ACLs (`EOPNOTSUPP`), e.g. FAT or tmpfs mounted
+    /// without ACL support. Applications can fall back to plain file mode bits in this case.
+    ///
+    /// Otherwise behaves like `IoError`, [`as_io_error()`](Self::as_io_error) returns the
+    /// underlying error.
+    Unsupported(IoErrorDetail),
+    /// The ACL was changed by someone else since it was read, so it was not overwritten. See
+    /// [`PosixACL::write_acl_if_unchanged()`](crate::PosixACL::write_acl_if_unchanged).
+    Conflict(ConflictErrorDetail),
 }
 
-// Stores private fields for ACLError::IoError
+// Stores private fields for ACLError::IoError and ACLError::Unsupported
 #[derive(Debug)]
 pub struct IoErrorDetail {
     err: io::Error,
     flags: u32,
+    path: Option<PathBuf>,
+}
+
+// Stores private fields for ACLError::Conflict
+#[derive(Debug)]
+pub struct ConflictErrorDetail {
+    flags: u32,
+    path: PathBuf,
 }
 
-// Currently an empty struct, created for future extensibility
+// Stores private fields for ACLError::ValidationError
 #[derive(Debug)]
 pub struct ValidationErrorDetail {
-    _private: (),
+    reason: ValidationReason,
+}
+
+/// Reason why an ACL failed validation, returned by [`ACLError::validation_reason()`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValidationReason {
+    /// An entry that must be unique (`UserObj`, `GroupObj`, `Mask` or `Other`) occurs multiple
+    /// times.
+    MultipleEntries,
+    /// Multiple `User` or `Group` entries have the same UID/GID.
+    DuplicateEntry,
+    /// A required entry is missing: `UserObj`, `GroupObj`, `Other`, and `Mask` if the ACL has
+    /// named `User` or `Group` entries.
+    MissingEntry,
+    /// An entry has an invalid tag type.
+    InvalidEntry,
+    /// The platform did not report a reason.
+    Unknown,
+}
+
+// Stores private fields for ACLError::ParseError
+#[derive(Debug)]
+pub struct ParseErrorDetail {
+    msg: String,
+}
+
+/// Serializable summary of an [`ACLError`], returned by [`ACLError::report()`].
+///
+/// With the `serde` feature enabled, this implements `Serialize` and `Deserialize`, e.g. for
+/// shipping structured failure reports to a central service.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[allow(clippy::upper_case_acronyms)]
+#[allow(clippy::module_name_repetitions)]
+pub struct ACLErrorReport {
+    /// General category of the error: the name of the [`std::io::ErrorKind`] variant, such as
+    /// `"NotFound"`.
+    pub kind: String,
+    /// OS error code (`errno`), if any.
+    pub errno: Option<i32>,
+    /// Path of the file the failed operation was applied to, if known, see
+    /// [`ACLError::path()`]. Converted lossily to a string, so it can be serialized in any
+    /// format.
+    pub path: Option<String>,
+    /// The operation that failed, such as `"reading ACL"`. `None` for validation and parse
+    /// errors.
+    pub operation: Option<String>,
+    /// Human-readable error message, same as the `Display` output of the error.
+    pub message: String,
 }
 
 impl Error for ACLError {
     /// Get underlying [`std::io::Error`] value.
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
-            ValidationError(..) => None,
-            IoError(IoErrorDetail { ref err, .. }) => Some(err),
+            ValidationError(..) | ParseError(..) | Conflict(..) => None,
+            IoError(IoErrorDetail { ref err, .. }) | Unsupported(IoErrorDetail { ref err, .. }) => {
+                Some(err)
+            }
         }
     }
 }
@@ -52,7 +127,8 @@ impl Error for ACLError {
 impl fmt::Display for ACLError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            IoError(IoErrorDetail { flags, err }) => write!(
+            IoError(IoErrorDetail { flags, err, .. })
+            | Unsupported(IoErrorDetail { flags, err, .. }) => write!(
                 f,
                 "Error {} {}: {}",
                 op_display(*flags),
@@ -60,13 +136,22 @@ impl fmt::Display for ACLError {
                 err
             ),
             ValidationError(_) => write!(f, "ACL failed validation"),
+            ParseError(ParseErrorDetail { msg }) => write!(f, "Error parsing ACL: {msg}"),
+            Conflict(ConflictErrorDetail { flags, path }) => write!(
+                f,
+                "Error {} {}: {} was changed concurrently",
+                op_display(*flags),
+                type_display(*flags),
+                path.display()
+            ),
         }
     }
 }
 
 impl ACLError {
     /// Get a general category of error, as [`std::io::ErrorKind`].
-    /// Validation errors get returned as `InvalidData`.
+    /// Validation errors get returned as `InvalidData`, parse errors as `InvalidInput`, conflicts
+    /// as `Other`.
     ///
     /// ```
     /// use posix_acl::PosixACL;
@@ -78,7 +163,28 @@ impl ACLError {
     pub fn kind(&self) -> ErrorKind {
         match self {
             ValidationError(_) => ErrorKind::InvalidData,
-            IoError(IoErrorDetail { ref err, .. }) => err.kind(),
+            ParseError(_) => ErrorKind::InvalidInput,
+            Conflict(_) => ErrorKind::Other,
+            IoError(IoErrorDetail { ref err, .. }) | Unsupported(IoErrorDetail { ref err, .. }) => {
+                err.kind()
+            }
+        }
+    }
+
+    /// Get the reason for a validation error, `None` for other errors.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ValidationReason, ACL_READ};
+    /// let mut acl = PosixACL::new(0o640);
+    /// acl.set(Qualifier::User(1234), ACL_READ);
+    /// let err = acl.validate().unwrap_err();
+    /// assert_eq!(err.validation_reason(), Some(ValidationReason::MissingEntry));
+    /// ```
+    #[must_use]
+    pub fn validation_reason(&self) -> Option<ValidationReason> {
+        match self {
+            ValidationError(ValidationErrorDetail { reason }) => Some(*reason),
+            IoError(_) | Unsupported(_) | ParseError(_) | Conflict(_) => None,
         }
     }
 
@@ -93,26 +199,118 @@ impl ACLError {
     #[must_use]
     pub fn as_io_error(&self) -> Option<&io::Error> {
         match self {
-            ValidationError(_) => None,
-            IoError(IoErrorDetail { ref err, .. }) => Some(err),
+            ValidationError(_) | ParseError(_) | Conflict(_) => None,
+            IoError(IoErrorDetail { ref err, .. }) | Unsupported(IoErrorDetail { ref err, .. }) => {
+                Some(err)
+            }
+        }
+    }
+
+    /// Get the path of the file that the failed operation was applied to, if known. Useful for
+    /// reporting which file failed when processing many.
+    ///
+    /// ```
+    /// use posix_acl::PosixACL;
+    /// use std::path::Path;
+    /// let err = PosixACL::read_acl("/tmp/this-file-does-not-exist").unwrap_err();
+    /// assert_eq!(err.path(), Some(Path::new("/tmp/this-file-does-not-exist")));
+    /// ```
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            ValidationError(_) | ParseError(_) => None,
+            IoError(IoErrorDetail { ref path, .. })
+            | Unsupported(IoErrorDetail { ref path, .. }) => path.as_deref(),
+            Conflict(ConflictErrorDetail { ref path, .. }) => Some(path),
+        }
+    }
+
+    /// Get a serializable summary of this error.
+    ///
+    /// ```
+    /// use posix_acl::PosixACL;
+    /// let err = PosixACL::read_acl("/tmp/this-file-does-not-exist").unwrap_err();
+    /// let report = err.report();
+    /// assert_eq!(report.kind, "NotFound");
+    /// assert_eq!(report.errno, Some(libc::ENOENT));
+    /// assert_eq!(report.path.as_deref(), Some("/tmp/this-file-does-not-exist"));
+    /// assert_eq!(report.operation.as_deref(), Some("reading ACL"));
+    /// ```
+    #[must_use]
+    pub fn report(&self) -> ACLErrorReport {
+        ACLErrorReport {
+            kind: format!("{:?}", self.kind()),
+            errno: self.as_io_error().and_then(io::Error::raw_os_error),
+            path: self.path().map(|path| path.to_string_lossy().into_owned()),
+            operation: match self {
+                ValidationError(_) | ParseError(_) => None,
+                IoError(IoErrorDetail { flags, .. })
+                | Unsupported(IoErrorDetail { flags, .. })
+                | Conflict(ConflictErrorDetail { flags, .. }) => {
+                    Some(format!("{} {}", op_display(*flags), type_display(*flags)))
+                }
+            },
+            message: self.to_string(),
         }
     }
 
     pub(crate) fn last_os_error(flags: u32) -> ACLError {
-        IoError(IoErrorDetail {
-            err: io::Error::last_os_error(),
+        ACLError::io_error(io::Error::last_os_error(), flags)
+    }
+
+    pub(crate) fn io_error(err: io::Error, flags: u32) -> ACLError {
+        let detail = IoErrorDetail {
+            err,
             flags,
+            path: None,
+        };
+        if detail.err.raw_os_error() == Some(libc::EOPNOTSUPP) {
+            Unsupported(detail)
+        } else {
+            IoError(detail)
+        }
+    }
+
+    /// Record `path` in I/O errors.
+    #[must_use]
+    pub(crate) fn with_path(mut self, path: &Path) -> ACLError {
+        if let IoError(detail) | Unsupported(detail) = &mut self {
+            detail.path = Some(path.to_path_buf());
+        }
+        self
+    }
+
+    pub(crate) fn conflict(flags: u32, path: &Path) -> ACLError {
+        Conflict(ConflictErrorDetail {
+            flags,
+            path: path.to_path_buf(),
         })
     }
 
-    pub(crate) fn validation_error() -> ACLError {
-        ValidationError(ValidationErrorDetail { _private: () })
+    pub(crate) fn validation_error(reason: ValidationReason) -> ACLError {
+        ValidationError(ValidationErrorDetail { reason })
+    }
+
+    pub(crate) fn parse_error<S: Into<String>>(msg: S) -> ACLError {
+        ParseError(ParseErrorDetail { msg: msg.into() })
     }
 }
 
-/// `acl_type_t` string representation for error messages
+/// Serializes as [`ACLErrorReport`].
+#[cfg(feature = "serde")]
+impl serde::Serialize for ACLError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.report().serialize(serializer)
+    }
+}
+
+/// Operation string representation for error messages
 pub(crate) fn op_display(flags: u32) -> &'static str {
-    if flags & FLAG_WRITE == FLAG_WRITE {
+    if flags & FLAG_CHOWN == FLAG_CHOWN {
+        "changing owner of"
+    } else if flags & FLAG_DELETE == FLAG_DELETE {
+        "deleting"
+    } else if flags & FLAG_WRITE == FLAG_WRITE {
         "writing"
     } else {
         "reading"
@@ -121,8 +319,9 @@ pub(crate) fn op_display(flags: u32) -> &'static str {
 
 /// `acl_type_t` string representation for error messages
 pub(crate) fn type_display(flags: u32) -> &'static str {
-    let flags = flags & !FLAG_WRITE;
+    let flags = flags & !FLAGS_OP;
     match flags {
+        0 => "file",
         ACL_TYPE_ACCESS => "ACL",
         ACL_TYPE_DEFAULT => "default ACL",
         _ => panic!("Invalid flags"),
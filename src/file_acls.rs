@@ -0,0 +1,64 @@
+//! Access and default ACL of a file handled together.
+use crate::error::ACLError;
+use crate::PosixACL;
+use std::fs;
+use std::path::Path;
+
+/// The access ACL and, for directories, the default ACL of a file.
+///
+/// ```
+/// use posix_acl::{FileACLs, Qualifier, ACL_READ};
+/// # std::fs::create_dir_all("/tmp/posix-acl-file-acls").unwrap();
+/// let mut acls = FileACLs::read_all("/tmp/posix-acl-file-acls").unwrap();
+/// acls.access.set(Qualifier::User(1234), ACL_READ);
+/// if let Some(default) = &mut acls.default {
+///     *default = acls.access.clone();
+/// }
+/// acls.write_all("/tmp/posix-acl-file-acls").unwrap();
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+pub struct FileACLs {
+    /// The access ACL.
+    pub access: PosixACL,
+    /// The default ACL. `None` for non-directories; for directories without a default ACL, an
+    /// empty ACL.
+    pub default: Option<PosixACL>,
+}
+
+impl FileACLs {
+    /// Read the access ACL and, if `path` is a directory, the default ACL.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    pub fn read_all<P: AsRef<Path>>(path: P) -> Result<FileACLs, ACLError> {
+        let path = path.as_ref();
+        let meta = fs::metadata(path).map_err(|err| ACLError::io_error(err, 0).with_path(path))?;
+        Ok(FileACLs {
+            access: PosixACL::read_acl(path)?,
+            default: if meta.is_dir() {
+                Some(PosixACL::read_default_acl(path)?)
+            } else {
+                None
+            },
+        })
+    }
+
+    /// Write the access ACL and, unless `default` is `None`, the default ACL to `path`. An empty
+    /// default ACL removes the default ACL of the directory.
+    ///
+    /// Like [`PosixACL::write_acl()`], the `Mask` entries are re-calculated.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    /// * `ACLError::ValidationError`: An ACL failed validation.
+    pub fn write_all<P: AsRef<Path>>(&mut self, path: P) -> Result<(), ACLError> {
+        let path = path.as_ref();
+        self.access.write_acl(path)?;
+        match &mut self.default {
+            Some(acl) if acl.is_empty() => PosixACL::delete_default_acl(path),
+            Some(acl) => acl.write_default_acl(path),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,47 @@
+//! Optional instrumentation through the [`metrics`](https://docs.rs/metrics) crate facade,
+//! enabled by the `metrics` feature. Without the feature, everything here is a no-op.
+use crate::ACLError;
+#[cfg(feature = "metrics")]
+use std::time::Instant;
+
+/// Measures the latency and outcome of a single filesystem operation.
+pub(crate) struct Timer {
+    #[cfg(feature = "metrics")]
+    start: Instant,
+}
+
+impl Timer {
+    pub(crate) fn start() -> Timer {
+        Timer {
+            #[cfg(feature = "metrics")]
+            start: Instant::now(),
+        }
+    }
+
+    /// Record operation `op` (e.g. `"read"`) as completed with `result`.
+    #[allow(clippy::unused_self)]
+    pub(crate) fn finish<T>(self, op: &'static str, result: &Result<T, ACLError>) {
+        #[cfg(feature = "metrics")]
+        {
+            let outcome = if result.is_ok() { "ok" } else { "error" };
+            ::metrics::counter!("posix_acl_operations_total", "op" => op, "result" => outcome)
+                .increment(1);
+            ::metrics::histogram!("posix_acl_operation_duration_seconds", "op" => op)
+                .record(self.start.elapsed().as_secs_f64());
+        }
+        #[cfg(not(feature = "metrics"))]
+        let _ = (op, result);
+    }
+}
+
+/// Record a write that fell back to changing file mode bits, because ACLs are not supported.
+pub(crate) fn chmod_fallback() {
+    #[cfg(feature = "metrics")]
+    ::metrics::counter!("posix_acl_chmod_fallbacks_total").increment(1);
+}
+
+/// Record an ACL that failed validation.
+pub(crate) fn validation_failure() {
+    #[cfg(feature = "metrics")]
+    ::metrics::counter!("posix_acl_validation_failures_total").increment(1);
+}
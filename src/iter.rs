@@ -1,53 +1,246 @@
-use crate::util::check_return;
-use crate::PosixACL;
-use acl_sys::{acl_entry_t, acl_get_entry, ACL_FIRST_ENTRY, ACL_NEXT_ENTRY};
+use crate::util::or_panic;
+use crate::{ACLEntry, ACLError, PosixACL, Qualifier};
+use acl_sys::{acl_entry_t, acl_get_entry, ACL_FIRST_ENTRY, ACL_NEXT_ENTRY, ACL_TYPE_ACCESS};
+use std::marker::PhantomData;
 use std::ptr::null_mut;
 
+/// Iterator of raw `acl_entry_t` handles.
+///
+/// The POSIX ACL C API keeps a single internal cursor per ACL, so iterators existing in parallel
+/// would trample each other's position. To stay correct, every step bumps the cursor generation
+/// stored in `PosixACL`. An iterator that finds the generation changed since its own last step
+/// rewinds the cursor and skips over the entries it has already returned.
+///
+/// If the platform fails to return an entry, the error is returned and iteration stops.
 #[allow(clippy::upper_case_acronyms)]
 pub(crate) struct RawACLIterator<'a> {
     acl: &'a PosixACL,
-    next: i32,
+    /// Number of entries returned so far
+    pos: usize,
+    /// Cursor generation after our last step, `None` if not started yet
+    generation: Option<u64>,
+    /// An error was returned, iteration has stopped
+    failed: bool,
 }
 
 impl<'a> RawACLIterator<'a> {
     pub(crate) fn new(acl: &'a PosixACL) -> RawACLIterator<'a> {
         RawACLIterator {
             acl,
-            next: ACL_FIRST_ENTRY,
+            pos: 0,
+            generation: None,
+            failed: false,
         }
     }
+
+    fn get_entry(&self, entry_id: i32) -> Result<Option<acl_entry_t>, ACLError> {
+        let mut entry: acl_entry_t = null_mut();
+        // The returned entry is owned by the ACL itself, no need to free it.
+        match unsafe { acl_get_entry(self.acl.acl, entry_id, &mut entry) } {
+            0 => Ok(None),
+            1 => Ok(Some(entry)),
+            _ => Err(ACLError::last_os_error(ACL_TYPE_ACCESS)),
+        }
+    }
+
+    fn step(&self) -> Result<Option<acl_entry_t>, ACLError> {
+        if self.generation == Some(self.acl.cursor.get()) {
+            return self.get_entry(ACL_NEXT_ENTRY);
+        }
+        // Cursor was moved by somebody else, or this is the first step.
+        let mut entry = self.get_entry(ACL_FIRST_ENTRY)?;
+        for _ in 0..self.pos {
+            if entry.is_none() {
+                break;
+            }
+            entry = self.get_entry(ACL_NEXT_ENTRY)?;
+        }
+        Ok(entry)
+    }
 }
 
-impl<'a> Iterator for RawACLIterator<'a> {
-    type Item = acl_entry_t;
+impl Iterator for RawACLIterator<'_> {
+    type Item = Result<acl_entry_t, ACLError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut entry: acl_entry_t = null_mut();
-        // The returned entry is owned by the ACL itself, no need to free it.
-        let ret = unsafe { acl_get_entry(self.acl.acl, self.next, &mut entry) };
-        if ret == 0 {
+        if self.failed {
             return None;
-        } else if ret != 1 {
-            check_return(ret, "acl_get_entry");
         }
-        // OK, ret == 1
-        self.next = ACL_NEXT_ENTRY;
-        Some(entry)
+        let entry = self.step();
+
+        let generation = self.acl.cursor.get().wrapping_add(1);
+        self.acl.cursor.set(generation);
+        self.generation = Some(generation);
+        match entry {
+            Ok(Some(entry)) => {
+                self.pos += 1;
+                Some(Ok(entry))
+            }
+            Ok(None) => None,
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// Iterator of [`ACLEntry`] items, returned by [`PosixACL::iter()`].
+///
+/// Entries are read from the ACL lazily, without allocating. The iterator borrows the ACL, so it
+/// cannot be modified while iterating, but any number of iterators can exist in parallel.
+#[allow(clippy::upper_case_acronyms)]
+#[allow(clippy::module_name_repetitions)]
+pub struct ACLIter<'a> {
+    raw: RawACLIterator<'a>,
+}
+
+impl<'a> ACLIter<'a> {
+    pub(crate) fn new(acl: &'a PosixACL) -> ACLIter<'a> {
+        ACLIter {
+            raw: RawACLIterator::new(acl),
+        }
+    }
+}
+
+impl Iterator for ACLIter<'_> {
+    type Item = ACLEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.raw
+            .next()
+            .map(|entry| or_panic(entry.and_then(ACLEntry::try_from_entry)))
+    }
+}
+
+impl<'a> IntoIterator for &'a PosixACL {
+    type Item = ACLEntry;
+    type IntoIter = ACLIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator of [`EntryRef`] items, returned by [`PosixACL::entries_mut()`].
+///
+/// The iterator borrows the ACL mutably, so entries cannot be added or removed while any
+/// `EntryRef` is alive.
+#[allow(clippy::upper_case_acronyms)]
+#[allow(clippy::module_name_repetitions)]
+pub struct ACLIterMut<'a> {
+    raw: RawACLIterator<'a>,
+}
+
+impl<'a> ACLIterMut<'a> {
+    pub(crate) fn new(acl: &'a mut PosixACL) -> ACLIterMut<'a> {
+        ACLIterMut {
+            raw: RawACLIterator::new(acl),
+        }
+    }
+}
+
+impl<'a> Iterator for ACLIterMut<'a> {
+    type Item = EntryRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.raw.next().map(|entry| EntryRef {
+            entry: or_panic(entry),
+            _acl: PhantomData,
+        })
     }
 }
 
-/** Demonstrate that multiple iterators cannot exist in parallel :( */
+/// An entry of an ACL, operating directly on the underlying `acl_entry_t`. See
+/// [`PosixACL::entries_mut()`].
+pub struct EntryRef<'a> {
+    entry: acl_entry_t,
+    _acl: PhantomData<&'a mut PosixACL>,
+}
+
+impl EntryRef<'_> {
+    /// Get the qualifier of the entry.
+    ///
+    /// # Panics
+    ///
+    /// When the platform fails to read the entry, use [`try_qualifier()`](Self::try_qualifier)
+    /// to handle errors.
+    #[must_use]
+    pub fn qualifier(&self) -> Qualifier {
+        or_panic(self.try_qualifier())
+    }
+
+    /// Like [`qualifier()`](Self::qualifier), but return an error instead of panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to read the entry.
+    pub fn try_qualifier(&self) -> Result<Qualifier, ACLError> {
+        Qualifier::try_from_entry(self.entry)
+    }
+
+    /// Get the permissions of the entry.
+    ///
+    /// # Panics
+    ///
+    /// When the platform fails to read the entry, use [`try_perm()`](Self::try_perm) to handle
+    /// errors.
+    #[must_use]
+    pub fn perm(&self) -> u32 {
+        or_panic(self.try_perm())
+    }
+
+    /// Like [`perm()`](Self::perm), but return an error instead of panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to read the entry.
+    pub fn try_perm(&self) -> Result<u32, ACLError> {
+        ACLEntry::perm_from_entry(self.entry)
+    }
+
+    /// Replace the permissions of the entry.
+    ///
+    /// # Panics
+    ///
+    /// When the platform fails to update the entry, use [`try_set_perm()`](Self::try_set_perm)
+    /// to handle errors.
+    pub fn set_perm(&mut self, perm: u32) {
+        or_panic(self.try_set_perm(perm));
+    }
+
+    /// Like [`set_perm()`](Self::set_perm), but return an error instead of panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to update the entry.
+    pub fn try_set_perm(&mut self, perm: u32) -> Result<(), ACLError> {
+        PosixACL::raw_set_permset(self.entry, perm)
+    }
+}
+
+/** Multiple iterators can exist in parallel */
 #[test]
 fn multi_iterator() {
     let acl = PosixACL::new(0o640);
-    let iter1 = unsafe { acl.raw_iter() };
-    let iter2 = unsafe { acl.raw_iter() };
+    let iter1 = acl.raw_iter();
+    let iter2 = acl.raw_iter();
 
     let results: Vec<_> = iter1.zip(iter2).collect();
-    // First item from each iterator is correct
-    assert_eq!(results[0].0, results[0].1);
-    // Then it gets out of whack
-    assert_ne!(results[1].0, results[1].1);
+    for (entry1, entry2) in &results {
+        assert_eq!(entry1.as_ref().unwrap(), entry2.as_ref().unwrap());
+    }
+    assert_eq!(results.len(), 3);
+}
 
-    assert_eq!(results.len(), 2);
+/** Looking up entries does not disturb an iterator in progress */
+#[test]
+fn iterate_while_get() {
+    use crate::ACLEntry;
+
+    let acl = PosixACL::new(0o640);
+    let mut count = 0;
+    for entry in acl.raw_iter() {
+        let ACLEntry { qual, perm } = ACLEntry::try_from_entry(entry.unwrap()).unwrap();
+        assert_eq!(acl.get(qual), Some(perm));
+        count += 1;
+    }
+    assert_eq!(count, 3);
 }
@@ -9,6 +9,9 @@
 //! This is without any loss of functionality, as duplicate entries with the same Qualifier are
 //! disallowed by POSIX anyway.
 //!
+//! Methods that take a path accept any `AsRef<Path>` type, including `&str`, `PathBuf` and
+//! UTF-8 path types such as `camino::Utf8Path`.
+//!
 //! For background information about ACL behavior, read [POSIX Access Control Lists on Linux](
 //! https://www.usenix.org/legacy/publications/library/proceedings/usenix03/tech/freenix03/full_papers/gruenbacher/gruenbacher_html/main.html).
 //!
@@ -38,13 +41,64 @@
 //! acl.write_acl("/tmp/posix-acl-testfile").unwrap();
 //! ```
 
+//! ## Optional features
+//! * `serde`: Implements `Serialize` for [`ACLError`] and `Serialize`/`Deserialize` for
+//!   [`ACLErrorReport`].
+//! * `metrics`: Records metrics through the [`metrics`](https://docs.rs/metrics) crate facade:
+//!   * `posix_acl_operations_total` counter of filesystem operations, with labels `op` (`read`,
+//!     `write`, `delete`) and `result` (`ok`, `error`).
+//!   * `posix_acl_operation_duration_seconds` histogram of operation latencies, with label `op`.
+//!   * `posix_acl_validation_failures_total` counter of ACLs that failed validation.
+//!
+//!   This feature needs Rust 1.71.1 or newer, the minimum supported Rust version of `metrics`
+//!   0.24. The rest of the crate supports Rust 1.60.
+//! * `rayon`: [`ParallelWalk`] running recursive operations on multiple threads. rayon 1.7
+//!   supports Rust 1.60, later releases need a newer compiler.
+//! * `tokio`: Async versions of the filesystem operations, e.g.
+//!   [`PosixACL::read_acl_async()`], running on the tokio blocking thread pool. tokio 1.29
+//!   supports Rust 1.60, later releases need a newer compiler.
+//! * `testing`: The [`testing`] module with helpers for verifying ACLs against the kernel and
+//!   exercising the fallbacks for missing libacl extension functions.
+//! * `tempfile`: [`TempDirWithACL`] and [`NamedTempFileWithACL`] builders for temporary files and
+//!   directories with ACLs applied.
+
 #![warn(clippy::pedantic)]
 
+mod access;
 mod acl;
+#[cfg(feature = "tokio")]
+mod async_ops;
+pub mod audit;
+mod backend;
+mod builder;
+mod capabilities;
+mod diff;
+pub mod dump;
 mod entry;
 mod error;
+mod file_acls;
+mod instrument;
 mod iter;
+mod mode;
+mod names;
+#[cfg(target_os = "linux")]
+mod nofollow;
+mod ops;
+#[cfg(feature = "rayon")]
+mod parallel;
+pub mod pax;
+pub mod presets;
+mod recursive;
+mod scoped;
+mod shadow;
+mod spec;
+#[cfg(feature = "tempfile")]
+mod temp;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod text;
 mod util;
+pub mod xattr;
 
 /// Read permission
 pub const ACL_READ: u32 = acl_sys::ACL_READ;
@@ -55,8 +109,28 @@ pub const ACL_EXECUTE: u32 = acl_sys::ACL_EXECUTE;
 /// All possible permissions combined: `ACL_READ | ACL_WRITE | ACL_EXECUTE`
 pub const ACL_RWX: u32 = ACL_READ | ACL_WRITE | ACL_EXECUTE;
 
+/// `CAP_DAC_OVERRIDE` capability for [`PosixACL::allows_privileged()`]. Capability constants use
+/// the same bits as the kernel's capability sets, e.g. `CapEff` in `/proc/<pid>/status`.
+pub const CAP_DAC_OVERRIDE: u32 = 1 << 1;
+/// `CAP_DAC_READ_SEARCH` capability for [`PosixACL::allows_privileged()`].
+pub const CAP_DAC_READ_SEARCH: u32 = 1 << 2;
+
 // Re-export public structs
+pub use access::{AccessClass, AccessExplanation, Principal};
 pub use acl::PosixACL;
+pub use backend::{ACLBackend, FsBackend, MemoryBackend};
+pub use builder::PosixACLBuilder;
+pub use capabilities::Capabilities;
+pub use diff::ACLDiff;
 pub use entry::ACLEntry;
 pub use entry::Qualifier;
-pub use error::ACLError;
+pub use error::{ACLError, ACLErrorReport, ValidationReason};
+pub use file_acls::FileACLs;
+pub use iter::{ACLIter, ACLIterMut, EntryRef};
+pub use ops::{chown_with_acl_fixup, copy_acl, setup_shared_dir, OwnerFixup};
+#[cfg(feature = "rayon")]
+pub use parallel::ParallelWalk;
+pub use recursive::RecursiveReport;
+pub use scoped::ScopedACL;
+#[cfg(feature = "tempfile")]
+pub use temp::{NamedTempFileWithACL, TempDirWithACL};
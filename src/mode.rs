@@ -0,0 +1,132 @@
+//! Symbolic file mode (`chmod u+rwx,g-w`) support.
+use crate::Qualifier::{GroupObj, Mask, Other, UserObj};
+use crate::{ACLError, PosixACL, Qualifier, ACL_EXECUTE, ACL_READ, ACL_WRITE};
+
+const WHO_USER: u8 = 1;
+const WHO_GROUP: u8 = 2;
+const WHO_OTHER: u8 = 4;
+const WHO_ALL: u8 = WHO_USER | WHO_GROUP | WHO_OTHER;
+
+/// A single `who op perm` action, e.g. `ug+rw`.
+struct Action {
+    who: u8,
+    op: char,
+    perm: u32,
+    /// `X` was given
+    cond_execute: bool,
+}
+
+impl PosixACL {
+    /// Apply a symbolic mode in the syntax of `chmod`, e.g. `u+rwx,g-w,o=r`.
+    ///
+    /// Like `chmod` on a file with an ACL, this changes the `UserObj` and `Other` entries, and
+    /// for group permissions the `Mask` entry if there is one, otherwise `GroupObj`. Named `User`
+    /// and `Group` entries are preserved.
+    ///
+    /// Each comma-separated clause consists of zero or more of `u`, `g`, `o`, `a` (who), followed
+    /// by one or more operations: `+`, `-` or `=`, followed by zero or more of `r`, `w`, `x`, `X`.
+    /// If who is omitted, `a` is assumed; unlike `chmod`, the umask is not taken into account.
+    ///
+    /// `X` means execute permission if the owner, group or others already have it. Unlike
+    /// `chmod`, it does not apply to directories automatically, since the ACL does not know
+    /// the file type.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ, ACL_WRITE};
+    /// let mut acl = PosixACL::new(0o640);
+    /// acl.set(Qualifier::User(1234), ACL_READ | ACL_WRITE);
+    /// acl.fix_mask();
+    ///
+    /// acl.apply_symbolic_mode("u+x,g-w,o=r").unwrap();
+    /// assert_eq!(acl.reported_mode(), 0o744);
+    /// assert_eq!(acl.get(Qualifier::Mask), Some(ACL_READ));
+    /// assert_eq!(acl.get(Qualifier::User(1234)), Some(ACL_READ | ACL_WRITE));
+    /// ```
+    ///
+    /// # Errors
+    /// * `ACLError::ParseError`: The mode is not valid. The ACL is not modified in this case.
+    pub fn apply_symbolic_mode(&mut self, mode: &str) -> Result<(), ACLError> {
+        let actions = parse_symbolic_mode(mode)?;
+        let group = if self.try_contains(Mask)? {
+            Mask
+        } else {
+            GroupObj
+        };
+        for action in actions {
+            let perm = if action.cond_execute && self.try_reported_mode()? & 0o111 != 0 {
+                action.perm | ACL_EXECUTE
+            } else {
+                action.perm
+            };
+            for &(bit, qual) in &[(WHO_USER, UserObj), (WHO_GROUP, group), (WHO_OTHER, Other)] {
+                if action.who & bit != 0 {
+                    self.apply_action(qual, action.op, perm)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_action(&mut self, qual: Qualifier, op: char, perm: u32) -> Result<(), ACLError> {
+        let current = self.try_get(qual)?.unwrap_or(0);
+        let new = match op {
+            '+' => current | perm,
+            '-' => current & !perm,
+            _ => perm,
+        };
+        self.try_set(qual, new)?;
+        Ok(())
+    }
+}
+
+fn parse_symbolic_mode(mode: &str) -> Result<Vec<Action>, ACLError> {
+    let mut actions = Vec::new();
+    for clause in mode.split(',') {
+        let invalid = || ACLError::parse_error(format!("Invalid symbolic mode {clause:?}"));
+        let mut chars = clause.chars().peekable();
+
+        let mut who = 0;
+        while let Some(&ch) = chars.peek() {
+            who |= match ch {
+                'u' => WHO_USER,
+                'g' => WHO_GROUP,
+                'o' => WHO_OTHER,
+                'a' => WHO_ALL,
+                _ => break,
+            };
+            chars.next();
+        }
+        if who == 0 {
+            who = WHO_ALL;
+        }
+
+        // At least one operation is required.
+        if chars.peek().is_none() {
+            return Err(invalid());
+        }
+        while let Some(op) = chars.next() {
+            if !matches!(op, '+' | '-' | '=') {
+                return Err(invalid());
+            }
+            let mut perm = 0;
+            let mut cond_execute = false;
+            while let Some(&ch) = chars.peek() {
+                match ch {
+                    'r' => perm |= ACL_READ,
+                    'w' => perm |= ACL_WRITE,
+                    'x' => perm |= ACL_EXECUTE,
+                    'X' => cond_execute = true,
+                    _ => break,
+                }
+                chars.next();
+            }
+            actions.push(Action {
+                who,
+                op,
+                perm,
+                cond_execute,
+            });
+        }
+    }
+    Ok(actions)
+}
@@ -0,0 +1,77 @@
+//! Accessing entries by user and group name instead of numeric ID.
+use crate::error::ACLError;
+use crate::util::{name_to_gid, name_to_uid};
+use crate::PosixACL;
+use crate::Qualifier::{Group, User};
+
+impl PosixACL {
+    /// Get the permissions of the `User` entry for user `name`, like [`get()`](Self::get).
+    ///
+    /// # Errors
+    /// * `ACLError::ParseError`: The user does not exist in the user database.
+    pub fn get_user_by_name(&self, name: &str) -> Result<Option<u32>, ACLError> {
+        self.try_get(User(resolve_user(name)?))
+    }
+
+    /// Set the permissions of the `User` entry for user `name`, like [`set()`](Self::set).
+    /// Returns the previous permissions if the entry existed.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+    /// let mut acl = PosixACL::new(0o640);
+    /// acl.set_user_by_name("root", ACL_READ).unwrap();
+    /// assert_eq!(acl.get(Qualifier::User(0)), Some(ACL_READ));
+    /// assert!(acl.set_user_by_name("no-such-user", ACL_READ).is_err());
+    /// ```
+    ///
+    /// # Errors
+    /// * `ACLError::ParseError`: The user does not exist in the user database. The ACL is left
+    ///   unchanged.
+    pub fn set_user_by_name(&mut self, name: &str, perm: u32) -> Result<Option<u32>, ACLError> {
+        self.try_set(User(resolve_user(name)?), perm)
+    }
+
+    /// Remove the `User` entry for user `name`, like [`remove()`](Self::remove).
+    ///
+    /// # Errors
+    /// * `ACLError::ParseError`: The user does not exist in the user database.
+    pub fn remove_user_by_name(&mut self, name: &str) -> Result<Option<u32>, ACLError> {
+        self.try_remove(User(resolve_user(name)?))
+    }
+
+    /// Get the permissions of the `Group` entry for group `name`, like [`get()`](Self::get).
+    ///
+    /// # Errors
+    /// * `ACLError::ParseError`: The group does not exist in the group database.
+    pub fn get_group_by_name(&self, name: &str) -> Result<Option<u32>, ACLError> {
+        self.try_get(Group(resolve_group(name)?))
+    }
+
+    /// Set the permissions of the `Group` entry for group `name`, like [`set()`](Self::set).
+    /// Returns the previous permissions if the entry existed.
+    ///
+    /// # Errors
+    /// * `ACLError::ParseError`: The group does not exist in the group database. The ACL is left
+    ///   unchanged.
+    pub fn set_group_by_name(&mut self, name: &str, perm: u32) -> Result<Option<u32>, ACLError> {
+        self.try_set(Group(resolve_group(name)?), perm)
+    }
+
+    /// Remove the `Group` entry for group `name`, like [`remove()`](Self::remove).
+    ///
+    /// # Errors
+    /// * `ACLError::ParseError`: The group does not exist in the group database.
+    pub fn remove_group_by_name(&mut self, name: &str) -> Result<Option<u32>, ACLError> {
+        self.try_remove(Group(resolve_group(name)?))
+    }
+}
+
+/// Look up the UID of user `name`, with an error naming the user if it does not exist.
+pub(crate) fn resolve_user(name: &str) -> Result<u32, ACLError> {
+    name_to_uid(name).ok_or_else(|| ACLError::parse_error(format!("Unknown user {name:?}")))
+}
+
+/// Look up the GID of group `name`, with an error naming the group if it does not exist.
+pub(crate) fn resolve_group(name: &str) -> Result<u32, ACLError> {
+    name_to_gid(name).ok_or_else(|| ACLError::parse_error(format!("Unknown group {name:?}")))
+}
@@ -0,0 +1,187 @@
+//! ACL operations that never follow a symbolic link in the final path component, optionally
+//! relative to an open directory. Linux only.
+use crate::error::{ACLError, FLAG_WRITE};
+use crate::util::path_to_cstring;
+use crate::PosixACL;
+use acl_sys::{acl_type_t, ACL_TYPE_ACCESS, ACL_TYPE_DEFAULT};
+use std::fs::File;
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::{io, mem};
+
+impl PosixACL {
+    /// Like [`read_acl()`](Self::read_acl), but fails if the last component of `path` is a
+    /// symbolic link, instead of reading the ACL of the link target.
+    ///
+    /// The file is opened with `O_PATH | O_NOFOLLOW` and the ACL is accessed through
+    /// `/proc/self/fd`, so the file cannot be replaced by a symlink between the check and the
+    /// ACL operation. Requires `/proc` to be mounted.
+    ///
+    /// ```
+    /// use posix_acl::PosixACL;
+    /// # let _ = std::fs::remove_file("/tmp/posix-acl-nofollow");
+    /// std::os::unix::fs::symlink("/etc/shells", "/tmp/posix-acl-nofollow").unwrap();
+    /// assert!(PosixACL::read_acl_nofollow("/tmp/posix-acl-nofollow").is_err());
+    /// ```
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc). If
+    ///   `path` is a symbolic link, the error is `ELOOP`.
+    pub fn read_acl_nofollow<P: AsRef<Path>>(path: P) -> Result<PosixACL, ACLError> {
+        let fd = open_nofollow(libc::AT_FDCWD, path.as_ref(), ACL_TYPE_ACCESS)?;
+        PosixACL::read_acl_flags(&proc_path(&fd), ACL_TYPE_ACCESS)
+            // Report the caller's path rather than the /proc path
+            .map_err(|err| err.with_path(path.as_ref()))
+    }
+
+    /// Like [`read_default_acl()`](Self::read_default_acl), but fails if the last component of
+    /// `path` is a symbolic link. See [`read_acl_nofollow()`](Self::read_acl_nofollow).
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc). If
+    ///   `path` is a symbolic link, the error is `ELOOP`.
+    pub fn read_default_acl_nofollow<P: AsRef<Path>>(path: P) -> Result<PosixACL, ACLError> {
+        let fd = open_nofollow(libc::AT_FDCWD, path.as_ref(), ACL_TYPE_DEFAULT)?;
+        PosixACL::read_acl_flags(&proc_path(&fd), ACL_TYPE_DEFAULT)
+            .map_err(|err| err.with_path(path.as_ref()))
+    }
+
+    /// Like [`write_acl()`](Self::write_acl), but fails if the last component of `path` is a
+    /// symbolic link. See [`read_acl_nofollow()`](Self::read_acl_nofollow).
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc). If
+    ///   `path` is a symbolic link, the error is `ELOOP`.
+    /// * `ACLError::ValidationError`: The ACL failed validation.
+    pub fn write_acl_nofollow<P: AsRef<Path>>(&mut self, path: P) -> Result<(), ACLError> {
+        let fd = open_nofollow(libc::AT_FDCWD, path.as_ref(), FLAG_WRITE | ACL_TYPE_ACCESS)?;
+        self.write_acl_flags(&proc_path(&fd), ACL_TYPE_ACCESS)
+            .map_err(|err| err.with_path(path.as_ref()))
+    }
+
+    /// Like [`write_default_acl()`](Self::write_default_acl), but fails if the last component
+    /// of `path` is a symbolic link. See [`read_acl_nofollow()`](Self::read_acl_nofollow).
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc). If
+    ///   `path` is a symbolic link, the error is `ELOOP`.
+    /// * `ACLError::ValidationError`: The ACL failed validation.
+    pub fn write_default_acl_nofollow<P: AsRef<Path>>(&mut self, path: P) -> Result<(), ACLError> {
+        let fd = open_nofollow(libc::AT_FDCWD, path.as_ref(), FLAG_WRITE | ACL_TYPE_DEFAULT)?;
+        self.write_acl_flags(&proc_path(&fd), ACL_TYPE_DEFAULT)
+            .map_err(|err| err.with_path(path.as_ref()))
+    }
+
+    /// Read the access ACL of `path` relative to the open directory `dir`, like `openat()`.
+    /// Absolute paths ignore `dir`.
+    ///
+    /// Anchoring operations to a directory handle avoids races with concurrent renames of parent
+    /// directories, e.g. when walking untrusted trees. For the same reason, like
+    /// [`read_acl_nofollow()`](Self::read_acl_nofollow), a symbolic link in the last component of
+    /// `path` is not followed.
+    ///
+    /// ```
+    /// use posix_acl::PosixACL;
+    /// let dir = std::fs::File::open("/etc").unwrap();
+    /// let acl = PosixACL::read_acl_at(&dir, "shells").unwrap();
+    /// ```
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc). If
+    ///   `path` is a symbolic link, the error is `ELOOP`.
+    pub fn read_acl_at<F, P>(dir: &F, path: P) -> Result<PosixACL, ACLError>
+    where
+        F: AsRawFd + ?Sized,
+        P: AsRef<Path>,
+    {
+        let fd = open_nofollow(dir.as_raw_fd(), path.as_ref(), ACL_TYPE_ACCESS)?;
+        PosixACL::read_acl_flags(&proc_path(&fd), ACL_TYPE_ACCESS)
+            .map_err(|err| err.with_path(path.as_ref()))
+    }
+
+    /// Read the default ACL of `path` relative to the open directory `dir`. See
+    /// [`read_acl_at()`](Self::read_acl_at).
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc). If
+    ///   `path` is a symbolic link, the error is `ELOOP`.
+    pub fn read_default_acl_at<F, P>(dir: &F, path: P) -> Result<PosixACL, ACLError>
+    where
+        F: AsRawFd + ?Sized,
+        P: AsRef<Path>,
+    {
+        let fd = open_nofollow(dir.as_raw_fd(), path.as_ref(), ACL_TYPE_DEFAULT)?;
+        PosixACL::read_acl_flags(&proc_path(&fd), ACL_TYPE_DEFAULT)
+            .map_err(|err| err.with_path(path.as_ref()))
+    }
+
+    /// Write the access ACL of `path` relative to the open directory `dir`. See
+    /// [`read_acl_at()`](Self::read_acl_at) and [`write_acl()`](Self::write_acl).
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc). If
+    ///   `path` is a symbolic link, the error is `ELOOP`.
+    /// * `ACLError::ValidationError`: The ACL failed validation.
+    pub fn write_acl_at<F, P>(&mut self, dir: &F, path: P) -> Result<(), ACLError>
+    where
+        F: AsRawFd + ?Sized,
+        P: AsRef<Path>,
+    {
+        let flags = FLAG_WRITE | ACL_TYPE_ACCESS;
+        let fd = open_nofollow(dir.as_raw_fd(), path.as_ref(), flags)?;
+        self.write_acl_flags(&proc_path(&fd), ACL_TYPE_ACCESS)
+            .map_err(|err| err.with_path(path.as_ref()))
+    }
+
+    /// Write the default ACL of `path` relative to the open directory `dir`. See
+    /// [`read_acl_at()`](Self::read_acl_at) and [`write_default_acl()`](Self::write_default_acl).
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc). If
+    ///   `path` is a symbolic link, the error is `ELOOP`.
+    /// * `ACLError::ValidationError`: The ACL failed validation.
+    pub fn write_default_acl_at<F, P>(&mut self, dir: &F, path: P) -> Result<(), ACLError>
+    where
+        F: AsRawFd + ?Sized,
+        P: AsRef<Path>,
+    {
+        let flags = FLAG_WRITE | ACL_TYPE_DEFAULT;
+        let fd = open_nofollow(dir.as_raw_fd(), path.as_ref(), flags)?;
+        self.write_acl_flags(&proc_path(&fd), ACL_TYPE_DEFAULT)
+            .map_err(|err| err.with_path(path.as_ref()))
+    }
+}
+
+/// Open an `O_PATH` file descriptor to `path` relative to `dirfd`, failing with `ELOOP` if it is
+/// a symlink. `flags` are used for error reporting.
+fn open_nofollow(dirfd: RawFd, path: &Path, flags: acl_type_t) -> Result<File, ACLError> {
+    let c_path = path_to_cstring(path);
+    let fd = unsafe {
+        libc::openat(
+            dirfd,
+            c_path.as_ptr(),
+            libc::O_PATH | libc::O_NOFOLLOW | libc::O_CLOEXEC,
+        )
+    };
+    if fd < 0 {
+        return Err(ACLError::last_os_error(flags).with_path(path));
+    }
+    // File closes the descriptor when dropped.
+    let file = unsafe { File::from_raw_fd(fd) };
+
+    // With O_PATH, O_NOFOLLOW opens the symlink itself instead of failing.
+    let mut stat: libc::stat = unsafe { mem::zeroed() };
+    if unsafe { libc::fstat(fd, &mut stat) } != 0 {
+        return Err(ACLError::last_os_error(flags).with_path(path));
+    }
+    if stat.st_mode & libc::S_IFMT == libc::S_IFLNK {
+        let err = io::Error::from_raw_os_error(libc::ELOOP);
+        return Err(ACLError::io_error(err, flags).with_path(path));
+    }
+    Ok(file)
+}
+
+/// Path through which the file referred to by `fd` can be accessed.
+fn proc_path(fd: &File) -> PathBuf {
+    PathBuf::from(format!("/proc/self/fd/{}", fd.as_raw_fd()))
+}
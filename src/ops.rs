@@ -0,0 +1,200 @@
+//! Higher-level operations on files, combining ACL changes with other filesystem calls.
+use crate::error::{ACLError, FLAG_CHOWN, FLAG_WRITE};
+use crate::util::path_to_cstring;
+use crate::Qualifier::{Group, GroupObj, User};
+use crate::{PosixACL, Qualifier};
+use acl_sys::{ACL_TYPE_ACCESS, ACL_TYPE_DEFAULT};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::Path;
+use std::{fs, io};
+
+/// The setgid mode bit. `libc::S_ISGID` is not `u32` on all platforms.
+const S_ISGID: u32 = 0o2000;
+
+/// What [`chown_with_acl_fixup()`] does with named ACL entries that refer to the previous owner
+/// or group of the file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OwnerFixup {
+    /// Leave ACL entries untouched, like plain `chown`.
+    Keep,
+    /// Rewrite `User`/`Group` entries of the previous owner/group to refer to the new owner/group.
+    /// If an entry for the new owner/group already exists, permissions are merged.
+    Rebind,
+    /// Remove `User`/`Group` entries of the previous owner/group.
+    Drop,
+}
+
+/// Change the owner and/or group of `path` like `chown`, then fix up named ACL entries that
+/// referred to the previous owner or group according to `fixup`.
+///
+/// Passing `None` as `uid` or `gid` leaves it unchanged. The access ACL and, for directories,
+/// the default ACL are fixed up. ACLs are only written if something was changed.
+///
+/// ```no_run
+/// use posix_acl::{chown_with_acl_fixup, OwnerFixup};
+/// // Migrate a file from UID 1000 to UID 2000, including any "user:1000" ACL entries.
+/// chown_with_acl_fixup("/srv/data/report.txt", Some(2000), None, OwnerFixup::Rebind).unwrap();
+/// ```
+///
+/// # Errors
+/// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+/// * `ACLError::ValidationError`: The fixed up ACL failed validation. In this case ownership has
+///   already been changed.
+pub fn chown_with_acl_fixup<P: AsRef<Path>>(
+    path: P,
+    uid: Option<u32>,
+    gid: Option<u32>,
+    fixup: OwnerFixup,
+) -> Result<(), ACLError> {
+    let path = path.as_ref();
+    let meta = fs::metadata(path).map_err(|err| ACLError::io_error(err, 0).with_path(path))?;
+
+    let mut renames = Vec::new();
+    if let Some(uid) = uid.filter(|&uid| uid != meta.uid()) {
+        renames.push((User(meta.uid()), User(uid)));
+    }
+    if let Some(gid) = gid.filter(|&gid| gid != meta.gid()) {
+        renames.push((Group(meta.gid()), Group(gid)));
+    }
+
+    // Read ACLs before changing ownership, in case we lose access to them afterwards.
+    let mut access = None;
+    let mut default = None;
+    if fixup != OwnerFixup::Keep && !renames.is_empty() {
+        access = Some(PosixACL::read_acl(path)?);
+        if meta.is_dir() {
+            default = Some(PosixACL::read_default_acl(path)?);
+        }
+    }
+
+    chown(path, uid, gid)?;
+
+    if let Some(mut acl) = access {
+        if fixup_entries(&mut acl, &renames, fixup)? {
+            acl.write_acl(path)?;
+        }
+    }
+    if let Some(mut acl) = default {
+        if fixup_entries(&mut acl, &renames, fixup)? {
+            acl.write_default_acl(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Set up `path` as a directory shared by members of group `gid`, the classic "team share"
+/// recipe:
+///
+/// * The owning group is changed to `gid`.
+/// * The owning group gets permissions `perm` in the access ACL (other entries are kept).
+/// * The default ACL is set to match the access ACL, so new files and directories inherit it.
+/// * The setgid bit is set, so new files and directories are owned by `gid` as well. Other
+///   special mode bits, such as the sticky bit, are kept.
+///
+/// Both ACLs are validated before the directory is modified. Changes are not rolled back if a
+/// later step fails, e.g. the owning group may already be changed when writing an ACL fails.
+///
+/// ```no_run
+/// use posix_acl::{setup_shared_dir, ACL_RWX};
+/// setup_shared_dir("/srv/team", 1234, ACL_RWX).unwrap();
+/// ```
+///
+/// # Errors
+/// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc), or `path`
+///   is not a directory.
+/// * `ACLError::ValidationError`: The resulting ACL failed validation.
+pub fn setup_shared_dir<P: AsRef<Path>>(path: P, gid: u32, perm: u32) -> Result<(), ACLError> {
+    let path = path.as_ref();
+    let meta = fs::metadata(path).map_err(|err| ACLError::io_error(err, 0).with_path(path))?;
+    if !meta.is_dir() {
+        let err = io::Error::from_raw_os_error(libc::ENOTDIR);
+        return Err(ACLError::io_error(err, 0).with_path(path));
+    }
+
+    let mut access = PosixACL::read_acl(path)?;
+    access.try_set(GroupObj, perm)?;
+    access.try_fix_mask()?;
+    access.validate()?;
+    let mut default = access.clone();
+    default.validate()?;
+
+    chown(path, None, Some(gid))?;
+    access.write_acl(path)?;
+    default.write_default_acl(path)?;
+
+    // chmod after writing ACLs: writing an ACL may clear the setgid bit.
+    let mode = (meta.mode() & 0o7000) | access.try_reported_mode()? | S_ISGID;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .map_err(|err| ACLError::io_error(err, FLAG_WRITE).with_path(path))
+}
+
+/// Copy the access ACL and, if both are directories, the default ACL from `src` to `dst`, like
+/// `getfacl src | setfacl --set-file=- dst`.
+///
+/// ACLs are copied exactly, including the `Mask` entry. If `src` has no default ACL, any
+/// default ACL of `dst` is removed.
+///
+/// ```no_run
+/// use posix_acl::copy_acl;
+/// copy_acl("/srv/data", "/backup/data").unwrap();
+/// ```
+///
+/// # Errors
+/// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+/// * `ACLError::ValidationError`: The source ACL failed validation.
+pub fn copy_acl<P: AsRef<Path>, Q: AsRef<Path>>(src: P, dst: Q) -> Result<(), ACLError> {
+    let (src, dst) = (src.as_ref(), dst.as_ref());
+    let is_dir = |path: &Path| -> Result<bool, ACLError> {
+        let meta = fs::metadata(path).map_err(|err| ACLError::io_error(err, 0).with_path(path))?;
+        Ok(meta.is_dir())
+    };
+
+    let access = PosixACL::read_acl(src)?;
+    let default = if is_dir(src)? && is_dir(dst)? {
+        Some(PosixACL::read_default_acl(src)?)
+    } else {
+        None
+    };
+
+    access.write_acl_exact(dst, ACL_TYPE_ACCESS)?;
+    match default {
+        Some(acl) if acl.is_empty() => PosixACL::delete_default_acl(dst),
+        Some(acl) => acl.write_acl_exact(dst, ACL_TYPE_DEFAULT),
+        None => Ok(()),
+    }
+}
+
+/// Apply `fixup` to entries listed in `renames`. Returns `true` if the ACL was modified.
+fn fixup_entries(
+    acl: &mut PosixACL,
+    renames: &[(Qualifier, Qualifier)],
+    fixup: OwnerFixup,
+) -> Result<bool, ACLError> {
+    let mut changed = false;
+    for &(old, new) in renames {
+        if let Some(perm) = acl.try_remove(old)? {
+            if fixup == OwnerFixup::Rebind {
+                acl.try_add_perm(new, perm)?;
+            }
+            changed = true;
+        }
+    }
+    Ok(changed)
+}
+
+fn chown(path: &Path, uid: Option<u32>, gid: Option<u32>) -> Result<(), ACLError> {
+    let c_path = path_to_cstring(path);
+    // -1 means "leave unchanged"
+    let ret = unsafe {
+        libc::chown(
+            c_path.as_ptr(),
+            uid.unwrap_or(libc::uid_t::MAX),
+            gid.unwrap_or(libc::gid_t::MAX),
+        )
+    };
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(ACLError::last_os_error(FLAG_CHOWN).with_path(path))
+    }
+}
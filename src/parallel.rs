@@ -0,0 +1,123 @@
+//! Recursive operations on multiple threads. Enabled by the `rayon` feature.
+use crate::error::ACLError;
+use crate::recursive::{modify_path, push_children, remap_path};
+use crate::spec::parse_modify_spec;
+use crate::{PosixACL, Qualifier, RecursiveReport};
+use rayon::{Scope, ThreadPool, ThreadPoolBuilder};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+/// Runs the recursive operations of [`PosixACL`] on a pool of threads, so that large directory
+/// trees are processed as fast as the storage allows. Enabled by the `rayon` feature.
+///
+/// The operations behave like their single-threaded counterparts, but paths are processed in no
+/// particular order, so the order of errors in the [`RecursiveReport`] varies between runs.
+///
+/// ```no_run
+/// use posix_acl::ParallelWalk;
+/// let walk = ParallelWalk::new(8).unwrap();
+/// let report = walk.modify("/srv/projects/web", "g:developers:rwX").unwrap();
+/// assert!(report.is_ok());
+/// ```
+#[derive(Debug)]
+pub struct ParallelWalk {
+    pool: ThreadPool,
+}
+
+impl ParallelWalk {
+    /// Create a thread pool with `threads` threads. If `threads` is 0, rayon chooses the number,
+    /// by default the number of CPUs.
+    ///
+    /// # Errors
+    /// Errors from creating the threads are returned.
+    pub fn new(threads: usize) -> io::Result<ParallelWalk> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        Ok(ParallelWalk { pool })
+    }
+
+    /// Like [`PosixACL::delete_default_acl_recursive()`].
+    pub fn delete_default_acl<P: AsRef<Path>>(&self, root: P) -> RecursiveReport {
+        self.walk(root.as_ref(), false, &|path, _| {
+            PosixACL::delete_default_acl(path)
+        })
+    }
+
+    /// Like [`PosixACL::modify_recursive()`].
+    ///
+    /// # Errors
+    /// * `ACLError::ParseError`: The spec is not valid, or contains an unknown user/group name.
+    ///   Nothing is modified in this case.
+    pub fn modify<P: AsRef<Path>>(&self, root: P, spec: &str) -> Result<RecursiveReport, ACLError> {
+        let changes = parse_modify_spec(spec)?;
+        Ok(self.walk(root.as_ref(), true, &|path, is_dir| {
+            modify_path(&changes, path, is_dir)
+        }))
+    }
+
+    /// Like [`PosixACL::remap_ids_recursive()`]. `map` is called from multiple threads.
+    pub fn remap_ids<P, F>(&self, root: P, map: F) -> RecursiveReport
+    where
+        P: AsRef<Path>,
+        F: Fn(Qualifier) -> Qualifier + Sync,
+    {
+        self.walk(root.as_ref(), true, &|path, is_dir| {
+            remap_path(&mut |qual| map(qual), path, is_dir)
+        })
+    }
+
+    /// Parallel version of [`walk()`](crate::recursive::walk).
+    fn walk<F>(&self, root: &Path, files: bool, op: &F) -> RecursiveReport
+    where
+        F: Fn(&Path, bool) -> Result<(), ACLError> + Sync,
+    {
+        let report = Mutex::new(RecursiveReport::default());
+        match fs::metadata(root) {
+            Ok(meta) => self.pool.scope(|scope| {
+                visit(scope, root.to_path_buf(), meta.is_dir(), files, op, &report);
+            }),
+            Err(err) => lock(&report).errors.push((
+                root.to_path_buf(),
+                ACLError::io_error(err, 0).with_path(root),
+            )),
+        }
+        report.into_inner().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Apply `op` to `path`, then spawn tasks for its children.
+fn visit<'s, F>(
+    scope: &Scope<'s>,
+    path: PathBuf,
+    is_dir: bool,
+    files: bool,
+    op: &'s F,
+    report: &'s Mutex<RecursiveReport>,
+) where
+    F: Fn(&Path, bool) -> Result<(), ACLError> + Sync,
+{
+    if let Err(err) = op(&path, is_dir) {
+        lock(report).errors.push((path, err));
+        return;
+    }
+    lock(report).processed += 1;
+    if is_dir {
+        let mut children = Vec::new();
+        if let Err(err) = push_children(&path, files, &mut children) {
+            lock(report).errors.push((path, err));
+        }
+        for (child, is_dir) in children {
+            scope.spawn(move |scope| visit(scope, child, is_dir, files, op, report));
+        }
+    }
+}
+
+/// Lock the report. A panic in another task is propagated by rayon, so a poisoned lock can be
+/// used as it is.
+fn lock(report: &Mutex<RecursiveReport>) -> std::sync::MutexGuard<'_, RecursiveReport> {
+    report.lock().unwrap_or_else(PoisonError::into_inner)
+}
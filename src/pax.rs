@@ -0,0 +1,116 @@
+//! Interoperability with ACLs in tar archives.
+//!
+//! GNU tar, star and bsdtar store ACLs in the `SCHILY.acl.access` and `SCHILY.acl.default` PAX
+//! extended header records. [`PosixACL::to_pax()`] and [`PosixACL::from_pax()`] convert between
+//! `PosixACL` and the text format of these records.
+use crate::spec::{invalid_entry, parse_qualifier};
+use crate::text::perm_text;
+use crate::util::{gid_to_name, uid_to_name};
+use crate::Qualifier::{Group, GroupObj, Mask, Other, Undefined, Unknown, User, UserObj};
+use crate::{ACLEntry, ACLError, PosixACL, ACL_EXECUTE, ACL_READ, ACL_WRITE};
+
+/// Name of the PAX header record storing the access ACL.
+pub const ACCESS: &str = "SCHILY.acl.access";
+/// Name of the PAX header record storing the default ACL of a directory.
+pub const DEFAULT: &str = "SCHILY.acl.default";
+
+impl PosixACL {
+    /// Encode the ACL in the text format of the `SCHILY.acl.access` and `SCHILY.acl.default`
+    /// PAX header records, as written by star and bsdtar.
+    ///
+    /// Entries are separated by commas. Named entries contain the user/group name, falling back
+    /// to the numeric ID, and the numeric ID as additional field.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+    /// let mut acl = PosixACL::new(0o640);
+    /// acl.set(Qualifier::User(0), ACL_READ);
+    /// acl.fix_mask();
+    /// assert_eq!(
+    ///     acl.to_pax(),
+    ///     "user::rw-,user:root:r--:0,group::r--,mask::r--,other::---"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn to_pax(&self) -> String {
+        self.entries()
+            .into_iter()
+            .filter_map(|ACLEntry { qual, perm }| {
+                let perm = perm_text(perm, 0);
+                Some(match qual {
+                    UserObj => format!("user::{perm}"),
+                    User(uid) => {
+                        let name = uid_to_name(uid).unwrap_or_else(|| uid.to_string());
+                        format!("user:{name}:{perm}:{uid}")
+                    }
+                    GroupObj => format!("group::{perm}"),
+                    Group(gid) => {
+                        let name = gid_to_name(gid).unwrap_or_else(|| gid.to_string());
+                        format!("group:{name}:{perm}:{gid}")
+                    }
+                    Mask => format!("mask::{perm}"),
+                    Other => format!("other::{perm}"),
+                    Undefined | Unknown(_) => return None,
+                })
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Decode an ACL from the text format of the `SCHILY.acl.access` and `SCHILY.acl.default`
+    /// PAX header records.
+    ///
+    /// Both the star/bsdtar format with numeric IDs (`user:alice:rw-:1000`), and the GNU tar
+    /// format without (`user:alice:rw-`) are accepted. If a numeric ID is present, it is used;
+    /// otherwise the name is resolved using the local user and group database.
+    ///
+    /// # Errors
+    /// * `ACLError::ParseError`: The text is not valid, or contains an unknown user/group name.
+    pub fn from_pax(text: &str) -> Result<PosixACL, ACLError> {
+        let entries: Vec<&str> = text
+            .split(&[',', '\n'][..])
+            .filter(|entry| !entry.is_empty())
+            .collect();
+        let mut acl = PosixACL::with_capacity(entries.len());
+        for entry in entries {
+            let fields: Vec<&str> = entry.split(':').collect();
+            let (qual, perm) = match fields.as_slice() {
+                [tag, name, perm] => (parse_qualifier(tag, name, entry)?, *perm),
+                [tag, name, perm, id] if !name.is_empty() => {
+                    // Use the numeric ID instead of the name.
+                    id.parse::<u32>().map_err(|_| invalid_entry(entry))?;
+                    (parse_qualifier(tag, id, entry)?, *perm)
+                }
+                _ => return Err(invalid_entry(entry)),
+            };
+            let perm = parse_perm(perm).ok_or_else(|| invalid_entry(entry))?;
+            if acl.try_set(qual, perm)?.is_some() {
+                return Err(ACLError::parse_error(format!(
+                    "Duplicate ACL entry {entry:?}"
+                )));
+            }
+        }
+        Ok(acl)
+    }
+}
+
+/// Parse permissions in the fixed `rwx` format.
+fn parse_perm(text: &str) -> Option<u32> {
+    let bytes = text.as_bytes();
+    if bytes.len() != 3 {
+        return None;
+    }
+    let mut perm = 0;
+    for (&byte, &(ch, bit)) in
+        bytes
+            .iter()
+            .zip(&[(b'r', ACL_READ), (b'w', ACL_WRITE), (b'x', ACL_EXECUTE)])
+    {
+        if byte == ch {
+            perm |= bit;
+        } else if byte != b'-' {
+            return None;
+        }
+    }
+    Some(perm)
+}
@@ -0,0 +1,62 @@
+//! Ready-made ACLs for common sharing patterns.
+//!
+//! Each preset takes `perm`, the permissions of the principals with full access, typically
+//! `ACL_READ | ACL_WRITE` for files and `ACL_RWX` for directories. Principals with read-only
+//! access get `perm` without `ACL_WRITE`. The returned ACLs pass [`PosixACL::validate()`], the
+//! `Mask` entry is calculated where needed.
+//!
+//! ```
+//! use posix_acl::{presets, Qualifier, ACL_EXECUTE, ACL_READ, ACL_RWX};
+//! let acl = presets::group_readable(1234, ACL_RWX);
+//! assert_eq!(acl.get(Qualifier::Group(1234)), Some(ACL_READ | ACL_EXECUTE));
+//! assert_eq!(acl.reported_mode(), 0o750);
+//! ```
+use crate::Qualifier::{Group, User};
+use crate::{PosixACL, Qualifier, ACL_WRITE};
+
+/// Only the file owner and user `owner_uid` have access.
+///
+/// The named `User` entry keeps `owner_uid` access even if the file ends up owned by another
+/// user, e.g. when it is created by a service or inherited through a default ACL.
+#[must_use]
+pub fn private(owner_uid: u32, perm: u32) -> PosixACL {
+    with_entries(perm, 0, 0, &[(User(owner_uid), perm)])
+}
+
+/// The file owner and members of group `gid` have full access, others have none.
+///
+/// Access is granted with a named `Group` entry, so it does not depend on the owning group of
+/// the file. The owning group has no access.
+#[must_use]
+pub fn group_shared(gid: u32, perm: u32) -> PosixACL {
+    with_entries(perm, 0, 0, &[(Group(gid), perm)])
+}
+
+/// The file owner has full access, members of group `gid` have read-only access, others have
+/// none. Like [`group_shared()`], the owning group has no access.
+#[must_use]
+pub fn group_readable(gid: u32, perm: u32) -> PosixACL {
+    with_entries(perm, 0, 0, &[(Group(gid), perm & !ACL_WRITE)])
+}
+
+/// The file owner has full access, everyone else has read-only access, like mode `0644` for
+/// files and `0755` for directories.
+#[must_use]
+pub fn world_readable(perm: u32) -> PosixACL {
+    let read_only = perm & !ACL_WRITE;
+    with_entries(perm, read_only, read_only, &[])
+}
+
+fn with_entries(
+    user_obj: u32,
+    group_obj: u32,
+    other: u32,
+    entries: &[(Qualifier, u32)],
+) -> PosixACL {
+    let mut acl = PosixACL::new((user_obj << 6) | (group_obj << 3) | other);
+    acl.extend(entries.iter().copied());
+    if !entries.is_empty() {
+        acl.fix_mask();
+    }
+    acl
+}
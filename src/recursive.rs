@@ -0,0 +1,189 @@
+//! Operations applied to whole directory trees.
+use crate::error::ACLError;
+use crate::spec::{parse_modify_spec, Modification};
+use crate::{PosixACL, Qualifier};
+use acl_sys::{ACL_TYPE_ACCESS, ACL_TYPE_DEFAULT};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Outcome of a recursive operation. Errors do not abort the operation, they are collected here
+/// along with the path they occurred at.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct RecursiveReport {
+    /// Number of paths the operation was applied to successfully.
+    pub processed: usize,
+    /// Paths where the operation, or reading the directory, failed.
+    pub errors: Vec<(PathBuf, ACLError)>,
+}
+
+impl RecursiveReport {
+    /// Return `true` if no errors occurred.
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl PosixACL {
+    /// Delete the default ACL of `root` and all directories below it, like `setfacl -k -R`.
+    ///
+    /// Symbolic links are not followed, except if `root` itself is one. Errors are collected in
+    /// the returned report instead of stopping the operation.
+    ///
+    /// ```no_run
+    /// use posix_acl::PosixACL;
+    /// let report = PosixACL::delete_default_acl_recursive("/srv/projects/old");
+    /// for (path, err) in &report.errors {
+    ///     eprintln!("{}: {}", path.display(), err);
+    /// }
+    /// ```
+    pub fn delete_default_acl_recursive<P: AsRef<Path>>(root: P) -> RecursiveReport {
+        walk(root.as_ref(), false, |path, _| {
+            PosixACL::delete_default_acl(path)
+        })
+    }
+
+    /// Merge entries in the syntax of `setfacl -m` into the access ACL of `root` and all files
+    /// and directories below it, like `setfacl -R -m`. See
+    /// [`modify_from_text()`](Self::modify_from_text) for the syntax.
+    ///
+    /// `X` grants execute permission to directories, and to files that already have execute
+    /// permission for some entry. This allows granting access to a tree without making all
+    /// data files executable.
+    ///
+    /// Symbolic links are not followed, except if `root` itself is one. Errors are collected in
+    /// the returned report instead of stopping the operation.
+    ///
+    /// ```no_run
+    /// use posix_acl::PosixACL;
+    /// let report = PosixACL::modify_recursive("/srv/projects/web", "g:developers:rwX").unwrap();
+    /// assert!(report.is_ok());
+    /// ```
+    ///
+    /// # Errors
+    /// * `ACLError::ParseError`: The spec is not valid, or contains an unknown user/group name.
+    ///   Nothing is modified in this case.
+    pub fn modify_recursive<P: AsRef<Path>>(
+        root: P,
+        spec: &str,
+    ) -> Result<RecursiveReport, ACLError> {
+        let changes = parse_modify_spec(spec)?;
+        Ok(walk(root.as_ref(), true, |path, is_dir| {
+            modify_path(&changes, path, is_dir)
+        }))
+    }
+
+    /// Replace qualifiers of entries in the access ACL of `root` and all files and directories
+    /// below it, and in default ACLs of directories, using `map`. See
+    /// [`remap_ids()`](Self::remap_ids).
+    ///
+    /// Only ACLs that change are written, and they are written exactly, keeping the `Mask` entry.
+    /// Symbolic links are not followed, except if `root` itself is one. Errors are collected in
+    /// the returned report instead of stopping the operation.
+    ///
+    /// ```no_run
+    /// use posix_acl::{PosixACL, Qualifier};
+    /// // Shift a container image layer into a user namespace starting at ID 100000
+    /// let report = PosixACL::remap_ids_recursive("/var/lib/containers/layer", |qual| match qual {
+    ///     Qualifier::User(uid) => Qualifier::User(uid + 100_000),
+    ///     Qualifier::Group(gid) => Qualifier::Group(gid + 100_000),
+    ///     other => other,
+    /// });
+    /// assert!(report.is_ok());
+    /// ```
+    ///
+    /// Mapping an entry to `Qualifier::Unknown` is reported as an error for that path.
+    pub fn remap_ids_recursive<P, F>(root: P, mut map: F) -> RecursiveReport
+    where
+        P: AsRef<Path>,
+        F: FnMut(Qualifier) -> Qualifier,
+    {
+        walk(root.as_ref(), true, |path, is_dir| {
+            remap_path(&mut map, path, is_dir)
+        })
+    }
+}
+
+/// Apply `changes` to the access ACL of `path`, for [`PosixACL::modify_recursive()`].
+pub(crate) fn modify_path(
+    changes: &[Modification],
+    path: &Path,
+    is_dir: bool,
+) -> Result<(), ACLError> {
+    let mut acl = PosixACL::read_acl(path)?;
+    acl.apply_modifications(changes, is_dir)?;
+    acl.write_acl(path)
+}
+
+/// Remap the ACLs of `path`, for [`PosixACL::remap_ids_recursive()`].
+pub(crate) fn remap_path<F>(map: &mut F, path: &Path, is_dir: bool) -> Result<(), ACLError>
+where
+    F: FnMut(Qualifier) -> Qualifier,
+{
+    let mut acl = PosixACL::read_acl(path)?;
+    if acl.try_remap_ids(&mut *map)? {
+        acl.write_acl_exact(path, ACL_TYPE_ACCESS)?;
+    }
+    if is_dir {
+        let mut acl = PosixACL::read_default_acl(path)?;
+        if acl.try_remap_ids(&mut *map)? {
+            acl.write_acl_exact(path, ACL_TYPE_DEFAULT)?;
+        }
+    }
+    Ok(())
+}
+
+/// Apply `op` to `root` and, if it is a directory, the directories below it, and files too if
+/// `files` is set. `op` is called with the path and whether it is a directory. Directories where
+/// `op` fails are not descended into.
+pub(crate) fn walk<F>(root: &Path, files: bool, mut op: F) -> RecursiveReport
+where
+    F: FnMut(&Path, bool) -> Result<(), ACLError>,
+{
+    let mut report = RecursiveReport::default();
+    let is_dir = match fs::metadata(root) {
+        Ok(meta) => meta.is_dir(),
+        Err(err) => {
+            report.errors.push((
+                root.to_path_buf(),
+                ACLError::io_error(err, 0).with_path(root),
+            ));
+            return report;
+        }
+    };
+    let mut stack = vec![(root.to_path_buf(), is_dir)];
+    while let Some((path, is_dir)) = stack.pop() {
+        if let Err(err) = op(&path, is_dir) {
+            report.errors.push((path, err));
+            continue;
+        }
+        report.processed += 1;
+        if is_dir {
+            if let Err(err) = push_children(&path, files, &mut stack) {
+                report.errors.push((path, err));
+            }
+        }
+    }
+    report
+}
+
+/// Push subdirectories, and other files if `files` is set, of `dir` onto `stack`. Symlinks are
+/// skipped.
+pub(crate) fn push_children(
+    dir: &Path,
+    files: bool,
+    stack: &mut Vec<(PathBuf, bool)>,
+) -> Result<(), ACLError> {
+    let to_error = |err| ACLError::io_error(err, 0).with_path(dir);
+    for entry in fs::read_dir(dir).map_err(to_error)? {
+        let entry = entry.map_err(to_error)?;
+        let file_type = entry.file_type().map_err(to_error)?;
+        if file_type.is_dir() {
+            stack.push((entry.path(), true));
+        } else if files && !file_type.is_symlink() {
+            stack.push((entry.path(), false));
+        }
+    }
+    Ok(())
+}
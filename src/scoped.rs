@@ -0,0 +1,111 @@
+//! Temporary ACL changes that are undone automatically.
+use crate::error::ACLError;
+use crate::PosixACL;
+use acl_sys::{ACL_TYPE_ACCESS, ACL_TYPE_DEFAULT};
+use std::path::{Path, PathBuf};
+
+/// Guard that temporarily changes the ACL of a path and restores the original ACL when dropped,
+/// including when unwinding from a panic.
+///
+/// The original access ACL and, for directories, default ACL are recorded before making the
+/// change. Both are restored as they were, including the `Mask` entry. Directories that had no
+/// default ACL have it removed again.
+///
+/// Errors during restoring are ignored on drop, use [`ScopedACL::restore()`] to handle them.
+///
+/// ```
+/// use posix_acl::{PosixACL, Qualifier, ScopedACL, ACL_READ};
+/// # std::fs::File::create("/tmp/posix-acl-scoped").unwrap();
+/// let mut acl = PosixACL::read_acl("/tmp/posix-acl-scoped").unwrap();
+/// acl.set(Qualifier::User(1234), ACL_READ);
+/// {
+///     let _guard = ScopedACL::set("/tmp/posix-acl-scoped", acl).unwrap();
+///     // ... user 1234 can read the file here ...
+/// }
+/// // Original ACL restored
+/// let acl = PosixACL::read_acl("/tmp/posix-acl-scoped").unwrap();
+/// assert_eq!(acl.get(Qualifier::User(1234)), None);
+/// ```
+#[derive(Debug)]
+#[must_use = "the original ACL is restored immediately if the guard is not stored"]
+pub struct ScopedACL {
+    path: PathBuf,
+    /// `None` once restored.
+    saved: Option<Saved>,
+}
+
+#[derive(Debug)]
+struct Saved {
+    access: PosixACL,
+    /// Only recorded for directories.
+    default: Option<PosixACL>,
+}
+
+impl ScopedACL {
+    /// Replace the access ACL of `path` with `acl` until the guard is dropped.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    /// * `ACLError::ValidationError`: The ACL failed validation.
+    pub fn set<P: AsRef<Path>>(path: P, mut acl: PosixACL) -> Result<ScopedACL, ACLError> {
+        let guard = ScopedACL::record(path.as_ref())?;
+        acl.write_acl(&guard.path)?;
+        Ok(guard)
+    }
+
+    /// Replace the default ACL of directory `path` with `acl` until the guard is dropped.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    /// * `ACLError::ValidationError`: The ACL failed validation.
+    pub fn set_default<P: AsRef<Path>>(path: P, mut acl: PosixACL) -> Result<ScopedACL, ACLError> {
+        let guard = ScopedACL::record(path.as_ref())?;
+        acl.write_default_acl(&guard.path)?;
+        Ok(guard)
+    }
+
+    fn record(path: &Path) -> Result<ScopedACL, ACLError> {
+        let is_dir = path
+            .metadata()
+            .map_err(|err| ACLError::io_error(err, 0).with_path(path))?
+            .is_dir();
+        let access = PosixACL::read_acl(path)?;
+        let default = if is_dir {
+            Some(PosixACL::read_default_acl(path)?)
+        } else {
+            None
+        };
+        Ok(ScopedACL {
+            path: path.to_path_buf(),
+            saved: Some(Saved { access, default }),
+        })
+    }
+
+    /// Restore the original ACL now, reporting errors.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    pub fn restore(mut self) -> Result<(), ACLError> {
+        self.restore_saved()
+    }
+
+    fn restore_saved(&mut self) -> Result<(), ACLError> {
+        let saved = match self.saved.take() {
+            Some(saved) => saved,
+            None => return Ok(()),
+        };
+        let access = saved.access.write_acl_exact(&self.path, ACL_TYPE_ACCESS);
+        let default = match saved.default {
+            Some(acl) if acl.is_empty() => PosixACL::delete_default_acl(&self.path),
+            Some(acl) => acl.write_acl_exact(&self.path, ACL_TYPE_DEFAULT),
+            None => Ok(()),
+        };
+        access.and(default)
+    }
+}
+
+impl Drop for ScopedACL {
+    fn drop(&mut self) {
+        let _ = self.restore_saved();
+    }
+}
@@ -0,0 +1,177 @@
+//! "Shadow" ACLs stored in `user.` namespace extended attributes, for filesystems that support
+//! extended attributes but not POSIX ACLs.
+use crate::error::{ACLError, FLAG_WRITE};
+use crate::util::path_to_cstring;
+use crate::xattr::{SHADOW_ACCESS, SHADOW_DEFAULT};
+use crate::PosixACL;
+use crate::Qualifier::{GroupObj, Mask, Other, UserObj};
+use acl_sys::{acl_type_t, ACL_TYPE_ACCESS, ACL_TYPE_DEFAULT};
+use std::ffi::CString;
+use std::os::raw::c_void;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::ptr::null_mut;
+use std::{fs, io};
+
+impl PosixACL {
+    /// Like [`read_acl()`](Self::read_acl), but if the file has a shadow ACL written by
+    /// [`write_acl_shadowed()`](Self::write_acl_shadowed), return that instead.
+    ///
+    /// `UserObj`, `Other` and `Mask` (or `GroupObj` if there is no mask) permissions of the shadow
+    /// ACL are taken from the current file mode, so `chmod` affects the shadow ACL as it would
+    /// affect a real one.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    /// * `ACLError::ParseError`: The shadow attribute contains invalid data.
+    pub fn read_acl_shadowed<P: AsRef<Path>>(path: P) -> Result<PosixACL, ACLError> {
+        let path = path.as_ref();
+        let mut acl = match read_shadow(path, ACL_TYPE_ACCESS)? {
+            Some(acl) => acl,
+            None => return PosixACL::read_acl(path),
+        };
+        let mode = fs::metadata(path)
+            .map_err(|err| ACLError::io_error(err, ACL_TYPE_ACCESS).with_path(path))?
+            .permissions()
+            .mode();
+        let group = if acl.try_contains(Mask)? {
+            Mask
+        } else {
+            GroupObj
+        };
+        acl.try_set(UserObj, (mode >> 6) & 0o7)?;
+        acl.try_set(group, (mode >> 3) & 0o7)?;
+        acl.try_set(Other, mode & 0o7)?;
+        Ok(acl)
+    }
+
+    /// Like [`read_default_acl()`](Self::read_default_acl), but if the directory has a shadow
+    /// default ACL written by [`write_default_acl_shadowed()`](Self::write_default_acl_shadowed),
+    /// return that instead.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    /// * `ACLError::ParseError`: The shadow attribute contains invalid data.
+    pub fn read_default_acl_shadowed<P: AsRef<Path>>(path: P) -> Result<PosixACL, ACLError> {
+        let path = path.as_ref();
+        match read_shadow(path, ACL_TYPE_DEFAULT)? {
+            Some(acl) => Ok(acl),
+            None => PosixACL::read_default_acl(path),
+        }
+    }
+
+    /// Like [`write_acl()`](Self::write_acl), but if the filesystem does not support ACLs, store
+    /// the ACL in the `user.posix_acl_access` extended attribute instead, in the same binary
+    /// format as the kernel (see [`to_xattr()`](Self::to_xattr)). The file mode is updated to
+    /// match the ACL like [`write_acl_or_chmod()`](Self::write_acl_or_chmod), keeping the
+    /// setuid, setgid and sticky bits.
+    ///
+    /// This does not make the kernel enforce the ACL. It allows application-level enforcement,
+    /// such as FUSE filesystems and sync tools, to carry ACLs across filesystems without ACL
+    /// support. Read it back with [`read_acl_shadowed()`](Self::read_acl_shadowed).
+    ///
+    /// When the real ACL is written successfully, any stale shadow ACL is removed.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    /// * `ACLError::ValidationError`: The ACL failed validation.
+    pub fn write_acl_shadowed<P: AsRef<Path>>(&mut self, path: P) -> Result<(), ACLError> {
+        let path = path.as_ref();
+        if self.write_acl_or_chmod(path)? {
+            write_shadow(path, ACL_TYPE_ACCESS, &self.to_xattr())
+        } else {
+            remove_shadow(path, ACL_TYPE_ACCESS)
+        }
+    }
+
+    /// Like [`write_default_acl()`](Self::write_default_acl), but if the filesystem does not
+    /// support ACLs, store the ACL in the `user.posix_acl_default` extended attribute instead.
+    /// See [`write_acl_shadowed()`](Self::write_acl_shadowed).
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors (file not found, permission denied, etc).
+    /// * `ACLError::ValidationError`: The ACL failed validation.
+    pub fn write_default_acl_shadowed<P: AsRef<Path>>(&mut self, path: P) -> Result<(), ACLError> {
+        let path = path.as_ref();
+        match self.write_default_acl(path) {
+            Err(ACLError::Unsupported(_)) => write_shadow(path, ACL_TYPE_DEFAULT, &self.to_xattr()),
+            Err(err) => Err(err),
+            Ok(()) => remove_shadow(path, ACL_TYPE_DEFAULT),
+        }
+    }
+}
+
+fn shadow_name(flags: acl_type_t) -> CString {
+    let name = if flags == ACL_TYPE_DEFAULT {
+        SHADOW_DEFAULT
+    } else {
+        SHADOW_ACCESS
+    };
+    CString::new(name).unwrap()
+}
+
+/// Read and decode the shadow ACL. `None` if there is none, or user xattrs are not supported.
+fn read_shadow(path: &Path, flags: acl_type_t) -> Result<Option<PosixACL>, ACLError> {
+    let c_path = path_to_cstring(path);
+    let name = shadow_name(flags);
+    loop {
+        let size = unsafe { libc::getxattr(c_path.as_ptr(), name.as_ptr(), null_mut(), 0) };
+        if size < 0 {
+            return match io::Error::last_os_error().raw_os_error() {
+                Some(libc::ENODATA | libc::EOPNOTSUPP) => Ok(None),
+                _ => Err(ACLError::last_os_error(flags).with_path(path)),
+            };
+        }
+        #[allow(clippy::cast_sign_loss)]
+        let mut buf = vec![0_u8; size as usize];
+        let size = unsafe {
+            libc::getxattr(
+                c_path.as_ptr(),
+                name.as_ptr(),
+                buf.as_mut_ptr().cast::<c_void>(),
+                buf.len(),
+            )
+        };
+        if size >= 0 {
+            #[allow(clippy::cast_sign_loss)]
+            buf.truncate(size as usize);
+            return PosixACL::from_xattr(&buf).map(Some);
+        }
+        // Attribute grew in the meantime, retry.
+        if io::Error::last_os_error().raw_os_error() != Some(libc::ERANGE) {
+            return Err(ACLError::last_os_error(flags).with_path(path));
+        }
+    }
+}
+
+fn write_shadow(path: &Path, flags: acl_type_t, data: &[u8]) -> Result<(), ACLError> {
+    let c_path = path_to_cstring(path);
+    let name = shadow_name(flags);
+    let ret = unsafe {
+        libc::setxattr(
+            c_path.as_ptr(),
+            name.as_ptr(),
+            data.as_ptr().cast::<c_void>(),
+            data.len(),
+            0,
+        )
+    };
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(ACLError::last_os_error(FLAG_WRITE | flags).with_path(path))
+    }
+}
+
+fn remove_shadow(path: &Path, flags: acl_type_t) -> Result<(), ACLError> {
+    let c_path = path_to_cstring(path);
+    let name = shadow_name(flags);
+    if unsafe { libc::removexattr(c_path.as_ptr(), name.as_ptr()) } == 0 {
+        return Ok(());
+    }
+    // User xattrs are not permitted on special files (EPERM), so there is no shadow ACL either.
+    match io::Error::last_os_error().raw_os_error() {
+        Some(libc::ENODATA | libc::EOPNOTSUPP | libc::EPERM) => Ok(()),
+        _ => Err(ACLError::last_os_error(FLAG_WRITE | flags).with_path(path)),
+    }
+}
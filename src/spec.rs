@@ -0,0 +1,185 @@
+//! Modifying ACLs with `setfacl`-style entry specifications, e.g. `u:alice:rw,g:devs:rX`.
+use crate::names::{resolve_group, resolve_user};
+use crate::Qualifier::{Group, GroupObj, Mask, Other, User, UserObj};
+use crate::{ACLError, PosixACL, Qualifier, ValidationReason, ACL_EXECUTE, ACL_READ, ACL_WRITE};
+
+impl PosixACL {
+    /// Merge entries in the syntax of `setfacl -m` into the ACL: existing entries are updated,
+    /// missing ones are added, and other entries are left unchanged.
+    ///
+    /// Entries are separated by commas or whitespace. Each entry consists of a tag (`u[ser]`,
+    /// `g[roup]`, `m[ask]` or `o[ther]`), a qualifier and permissions, separated by `:`. The
+    /// qualifier is a user/group name or numeric ID, or empty for the file owner/owning group;
+    /// for `mask` and `other` it may be omitted. Permissions are a combination of `r`, `w`, `x`
+    /// and `-`, or an octal digit.
+    ///
+    /// `X` grants execute permission if some entry already has it, like `setfacl` does for
+    /// non-directories; [`modify_recursive()`](Self::modify_recursive) also grants it for
+    /// directories. Like `setfacl`, the `Mask` entry is recalculated when writing with
+    /// [`write_acl()`](Self::write_acl). Default ACL entries (`d:u:alice:rw`) are not accepted,
+    /// modify the ACL from [`read_default_acl()`](Self::read_default_acl) instead.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ, ACL_WRITE};
+    /// let mut acl = PosixACL::new(0o640);
+    /// acl.modify_from_text("u:root:rw,u::r,o:4").unwrap();
+    /// assert_eq!(acl.get(Qualifier::User(0)), Some(ACL_READ | ACL_WRITE));
+    /// assert_eq!(acl.reported_mode(), 0o444);
+    /// ```
+    ///
+    /// # Errors
+    /// * `ACLError::ParseError`: The spec is not valid, or contains an unknown user/group name.
+    ///   The ACL is not modified in this case.
+    pub fn modify_from_text(&mut self, spec: &str) -> Result<(), ACLError> {
+        let changes = parse_modify_spec(spec)?;
+        self.apply_modifications(&changes, false)
+    }
+
+    /// Apply parsed `setfacl -m` entries. `X` grants execute permission if `is_dir` is set or
+    /// some entry already has execute permission.
+    pub(crate) fn apply_modifications(
+        &mut self,
+        changes: &[Modification],
+        is_dir: bool,
+    ) -> Result<(), ACLError> {
+        let execute = is_dir
+            || self
+                .try_entries()?
+                .iter()
+                .any(|entry| entry.qual != Mask && entry.perm & ACL_EXECUTE != 0);
+        for change in changes {
+            let perm = if change.cond_execute && execute {
+                change.perm | ACL_EXECUTE
+            } else {
+                change.perm
+            };
+            self.try_set(change.qual, perm)?;
+        }
+        Ok(())
+    }
+
+    /// Remove entries in the syntax of `setfacl -x`, e.g. `u:alice,g:devs`. Permissions, if
+    /// given, are ignored. Entries that do not exist in the ACL are skipped.
+    ///
+    /// The syntax is the same as for [`modify_from_text()`](Self::modify_from_text), except
+    /// that the permissions field is optional.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+    /// let mut acl = PosixACL::new(0o640);
+    /// acl.set(Qualifier::User(0), ACL_READ);
+    /// acl.set(Qualifier::Group(0), ACL_READ);
+    /// acl.remove_from_text("u:root,g:0:r").unwrap();
+    /// assert_eq!(acl, PosixACL::new(0o640));
+    /// ```
+    ///
+    /// # Errors
+    /// * `ACLError::ParseError`: The spec is not valid, or contains an unknown user/group name.
+    /// * `ACLError::ValidationError`: The spec refers to a required entry (`UserObj`, `GroupObj`
+    ///   or `Other`), like with [`remove_checked()`](Self::remove_checked).
+    ///
+    /// The ACL is not modified in case of an error.
+    pub fn remove_from_text(&mut self, spec: &str) -> Result<(), ACLError> {
+        let mut quals = Vec::new();
+        for entry in split_spec(spec) {
+            let fields: Vec<&str> = entry.split(':').collect();
+            let qual = match fields.as_slice() {
+                [tag] if is_mask_or_other(tag) => parse_qualifier(tag, "", entry)?,
+                [tag, name] | [tag, name, _] => parse_qualifier(tag, name, entry)?,
+                _ => return Err(invalid_entry(entry)),
+            };
+            if matches!(qual, UserObj | GroupObj | Other) {
+                return Err(ACLError::validation_error(ValidationReason::MissingEntry));
+            }
+            quals.push(qual);
+        }
+
+        for qual in quals {
+            self.try_remove(qual)?;
+        }
+        Ok(())
+    }
+}
+
+/// An entry of a `setfacl -m` spec.
+pub(crate) struct Modification {
+    qual: Qualifier,
+    perm: u32,
+    /// `X` was given
+    cond_execute: bool,
+}
+
+pub(crate) fn parse_modify_spec(spec: &str) -> Result<Vec<Modification>, ACLError> {
+    let mut changes = Vec::new();
+    for entry in split_spec(spec) {
+        let fields: Vec<&str> = entry.split(':').collect();
+        let (qual, perm) = match fields.as_slice() {
+            [tag, name, perm] => (parse_qualifier(tag, name, entry)?, *perm),
+            [tag, perm] if is_mask_or_other(tag) => (parse_qualifier(tag, "", entry)?, *perm),
+            _ => return Err(invalid_entry(entry)),
+        };
+        let (perm, cond_execute) = parse_perm(perm).ok_or_else(|| invalid_entry(entry))?;
+        changes.push(Modification {
+            qual,
+            perm,
+            cond_execute,
+        });
+    }
+    Ok(changes)
+}
+
+fn split_spec(spec: &str) -> impl Iterator<Item = &str> {
+    spec.split(|ch: char| ch == ',' || ch.is_whitespace())
+        .filter(|entry| !entry.is_empty())
+}
+
+pub(crate) fn invalid_entry(entry: &str) -> ACLError {
+    ACLError::parse_error(format!("Invalid ACL entry {entry:?}"))
+}
+
+fn is_mask_or_other(tag: &str) -> bool {
+    matches!(tag, "m" | "mask" | "o" | "other")
+}
+
+pub(crate) fn parse_qualifier(tag: &str, name: &str, entry: &str) -> Result<Qualifier, ACLError> {
+    Ok(match (tag, name) {
+        ("u" | "user", "") => UserObj,
+        ("u" | "user", name) => User(match name.parse() {
+            Ok(uid) => uid,
+            Err(_) => resolve_user(name)?,
+        }),
+        ("g" | "group", "") => GroupObj,
+        ("g" | "group", name) => Group(match name.parse() {
+            Ok(gid) => gid,
+            Err(_) => resolve_group(name)?,
+        }),
+        ("m" | "mask", "") => Mask,
+        ("o" | "other", "") => Other,
+        _ => return Err(invalid_entry(entry)),
+    })
+}
+
+/// Parse permissions, returning the permission bits and whether `X` was given.
+fn parse_perm(text: &str) -> Option<(u32, bool)> {
+    if text.is_empty() {
+        return None;
+    }
+    if text.len() == 1 {
+        if let Some(digit) = text.chars().next().and_then(|ch| ch.to_digit(8)) {
+            return Some((digit, false));
+        }
+    }
+    let mut perm = 0;
+    let mut cond_execute = false;
+    for ch in text.chars() {
+        match ch {
+            'r' => perm |= ACL_READ,
+            'w' => perm |= ACL_WRITE,
+            'x' => perm |= ACL_EXECUTE,
+            'X' => cond_execute = true,
+            '-' => {}
+            _ => return None,
+        }
+    }
+    Some((perm, cond_execute))
+}
@@ -0,0 +1,155 @@
+//! Temporary files and directories with ACLs, using the [`tempfile`](https://docs.rs/tempfile)
+//! crate. Enabled by the `tempfile` feature.
+use crate::error::{ACLError, FLAG_WRITE};
+use crate::PosixACL;
+use std::path::Path;
+use tempfile::{Builder, NamedTempFile, TempDir};
+
+/// Builder for a [`TempDir`] with access and/or default ACL applied.
+///
+/// The directory is removed again if applying an ACL fails.
+///
+/// ```
+/// use posix_acl::{PosixACL, Qualifier, TempDirWithACL, ACL_READ, ACL_EXECUTE};
+/// let mut shared = PosixACL::new(0o750);
+/// shared.set(Qualifier::Group(1234), ACL_READ | ACL_EXECUTE);
+///
+/// let dir = TempDirWithACL::new()
+///     .prefix("scratch")
+///     .default_acl(shared)
+///     .create()
+///     .unwrap();
+/// ```
+#[derive(Debug, Default)]
+pub struct TempDirWithACL {
+    prefix: Option<String>,
+    access: Option<PosixACL>,
+    default: Option<PosixACL>,
+}
+
+impl TempDirWithACL {
+    /// Create a builder that applies no ACLs.
+    #[must_use]
+    pub fn new() -> TempDirWithACL {
+        TempDirWithACL::default()
+    }
+
+    /// Prefix of the generated directory name.
+    #[must_use]
+    pub fn prefix(mut self, prefix: &str) -> TempDirWithACL {
+        self.prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// Access ACL to apply to the directory.
+    #[must_use]
+    pub fn access_acl(mut self, acl: PosixACL) -> TempDirWithACL {
+        self.access = Some(acl);
+        self
+    }
+
+    /// Default ACL to apply to the directory, inherited by files created in it.
+    #[must_use]
+    pub fn default_acl(mut self, acl: PosixACL) -> TempDirWithACL {
+        self.default = Some(acl);
+        self
+    }
+
+    /// Create the directory in the system temporary directory, see [`std::env::temp_dir()`].
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors when creating the directory or writing ACLs.
+    /// * `ACLError::ValidationError`: An ACL failed validation.
+    pub fn create(self) -> Result<TempDir, ACLError> {
+        self.create_in(std::env::temp_dir())
+    }
+
+    /// Create the directory in `dir`.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors when creating the directory or writing ACLs.
+    /// * `ACLError::ValidationError`: An ACL failed validation.
+    pub fn create_in<P: AsRef<Path>>(self, dir: P) -> Result<TempDir, ACLError> {
+        let temp = builder(self.prefix.as_deref())
+            .tempdir_in(dir)
+            .map_err(|err| ACLError::io_error(err, FLAG_WRITE))?;
+        if let Some(mut acl) = self.access {
+            acl.write_acl(temp.path())?;
+        }
+        if let Some(mut acl) = self.default {
+            acl.write_default_acl(temp.path())?;
+        }
+        Ok(temp)
+    }
+}
+
+/// Builder for a [`NamedTempFile`] with an access ACL applied.
+///
+/// The file is removed again if applying the ACL fails.
+///
+/// ```
+/// use posix_acl::{NamedTempFileWithACL, PosixACL};
+/// let file = NamedTempFileWithACL::new()
+///     .access_acl(PosixACL::new(0o600))
+///     .create()
+///     .unwrap();
+/// ```
+#[derive(Debug, Default)]
+pub struct NamedTempFileWithACL {
+    prefix: Option<String>,
+    access: Option<PosixACL>,
+}
+
+impl NamedTempFileWithACL {
+    /// Create a builder that applies no ACL.
+    #[must_use]
+    pub fn new() -> NamedTempFileWithACL {
+        NamedTempFileWithACL::default()
+    }
+
+    /// Prefix of the generated file name.
+    #[must_use]
+    pub fn prefix(mut self, prefix: &str) -> NamedTempFileWithACL {
+        self.prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// Access ACL to apply to the file.
+    #[must_use]
+    pub fn access_acl(mut self, acl: PosixACL) -> NamedTempFileWithACL {
+        self.access = Some(acl);
+        self
+    }
+
+    /// Create the file in the system temporary directory, see [`std::env::temp_dir()`].
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors when creating the file or writing the ACL.
+    /// * `ACLError::ValidationError`: The ACL failed validation.
+    pub fn create(self) -> Result<NamedTempFile, ACLError> {
+        self.create_in(std::env::temp_dir())
+    }
+
+    /// Create the file in `dir`.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: Filesystem errors when creating the file or writing the ACL.
+    /// * `ACLError::ValidationError`: The ACL failed validation.
+    pub fn create_in<P: AsRef<Path>>(self, dir: P) -> Result<NamedTempFile, ACLError> {
+        let temp = builder(self.prefix.as_deref())
+            .tempfile_in(dir)
+            .map_err(|err| ACLError::io_error(err, FLAG_WRITE))?;
+        if let Some(mut acl) = self.access {
+            acl.write_acl(temp.path())?;
+        }
+        Ok(temp)
+    }
+}
+
+fn builder(prefix: Option<&str>) -> Builder<'_, 'static> {
+    let mut builder = Builder::new();
+    if let Some(prefix) = prefix {
+        builder.prefix(prefix);
+    }
+    builder
+}
@@ -0,0 +1,131 @@
+//! Helpers for empirically verifying ACL behavior against the kernel and libacl. Enabled by the
+//! `testing` feature.
+use crate::capabilities::DISABLED;
+use crate::util::path_to_cstring;
+use crate::{ACL_EXECUTE, ACL_READ, ACL_WRITE};
+use std::io;
+use std::os::raw::c_int;
+use std::path::Path;
+
+/// Check whether the kernel grants access `want` to `path` for a given user, by forking a child
+/// process that drops privileges and attempts to actually open the file.
+///
+/// The child switches to user `uid`, primary group `gid` and supplementary `groups`, then
+/// attempts `open()` for [`ACL_READ`] and [`ACL_WRITE`] and `access()` for [`ACL_EXECUTE`] (and
+/// for writing directories, which cannot be opened for writing). Returns `true` if all requested
+/// accesses succeed.
+///
+/// Keep in mind that the user also needs search (execute) permission on all parent directories.
+///
+/// This can only be called by root. Intended for tests, it forks the current process.
+///
+/// ```no_run
+/// use posix_acl::testing::kernel_allows;
+/// use posix_acl::ACL_READ;
+/// assert!(kernel_allows("/etc/hostname", 1000, 1000, &[], ACL_READ).unwrap());
+/// ```
+///
+/// # Errors
+/// * `PermissionDenied` when not called by root.
+/// * Errors from `fork()`, `waitpid()` and stat of `path`.
+/// * `Other` when the child process fails to drop privileges.
+pub fn kernel_allows<P: AsRef<Path>>(
+    path: P,
+    uid: u32,
+    gid: u32,
+    groups: &[u32],
+    want: u32,
+) -> io::Result<bool> {
+    if unsafe { libc::geteuid() } != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "kernel_allows() requires root",
+        ));
+    }
+    let is_dir = path.as_ref().metadata()?.is_dir();
+    // Prepare everything before forking, the child must not allocate.
+    let c_path = path_to_cstring(path.as_ref());
+    let groups: Vec<libc::gid_t> = groups.to_vec();
+
+    match unsafe { libc::fork() } {
+        -1 => Err(io::Error::last_os_error()),
+        0 => unsafe {
+            if libc::setgroups(groups.len(), groups.as_ptr()) != 0
+                || libc::setgid(gid) != 0
+                || libc::setuid(uid) != 0
+            {
+                libc::_exit(2);
+            }
+            let mut allowed = true;
+            if want & ACL_READ != 0 {
+                allowed &= try_open(&c_path, libc::O_RDONLY);
+            }
+            if want & ACL_WRITE != 0 {
+                allowed &= if is_dir {
+                    libc::access(c_path.as_ptr(), libc::W_OK) == 0
+                } else {
+                    try_open(&c_path, libc::O_WRONLY)
+                };
+            }
+            if want & ACL_EXECUTE != 0 {
+                allowed &= libc::access(c_path.as_ptr(), libc::X_OK) == 0;
+            }
+            // Exit code 0: allowed, 1: denied
+            libc::_exit(i32::from(!allowed));
+        },
+        pid => {
+            let mut status: c_int = 0;
+            if unsafe { libc::waitpid(pid, &mut status, 0) } == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            match libc::WIFEXITED(status).then(|| libc::WEXITSTATUS(status)) {
+                Some(0) => Ok(true),
+                Some(1) => Ok(false),
+                _ => Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "Child process failed to drop privileges",
+                )),
+            }
+        }
+    }
+}
+
+/// Attempt to open and close a file. Must be async-signal-safe.
+unsafe fn try_open(c_path: &std::ffi::CStr, flags: c_int) -> bool {
+    let fd = libc::open(
+        c_path.as_ptr(),
+        flags | libc::O_NOCTTY | libc::O_NONBLOCK | libc::O_CLOEXEC,
+    );
+    if fd < 0 {
+        false
+    } else {
+        libc::close(fd);
+        true
+    }
+}
+
+/// Run `f` with all optional libacl extension functions treated as missing on the current
+/// thread, so that the crate uses its own fallback implementations. See [`Capabilities`] for the
+/// affected functions. [`Capabilities::detect()`] still reports the loaded library.
+///
+/// ```
+/// use posix_acl::testing::without_extensions;
+/// use posix_acl::PosixACL;
+/// let acl = PosixACL::new(0o640);
+/// assert_eq!(without_extensions(|| acl.len()), acl.len());
+/// ```
+///
+/// [`Capabilities`]: crate::Capabilities
+/// [`Capabilities::detect()`]: crate::Capabilities::detect
+pub fn without_extensions<T, F: FnOnce() -> T>(f: F) -> T {
+    /// Restores the previous state, also when `f` panics.
+    struct Restore(bool);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            DISABLED.with(|disabled| disabled.set(self.0));
+        }
+    }
+
+    let _restore = Restore(DISABLED.with(|disabled| disabled.replace(true)));
+    f()
+}
@@ -0,0 +1,267 @@
+//! Alternative text representations of ACLs, in addition to [`PosixACL::as_text()`].
+use crate::capabilities;
+use crate::util::{gid_to_name, or_panic, try_pointer, uid_to_name, AutoPtr};
+use crate::Qualifier::{Group, GroupObj, Mask, Other, Undefined, Unknown, User, UserObj};
+use crate::{ACLEntry, ACLError, PosixACL, Qualifier, ACL_EXECUTE, ACL_READ, ACL_WRITE};
+use acl_sys::{ACL_TYPE_ACCESS, TEXT_NUMERIC_IDS};
+use std::ffi::CStr;
+use std::fmt::Write;
+use std::io;
+use std::os::raw::c_char;
+use std::ptr::null;
+
+impl PosixACL {
+    /// Return the ACL in the tabular format of `getfacl -t`, one entry per line.
+    ///
+    /// Columns are the tag, qualifier name and permissions. As in `getfacl`, the tags of `UserObj`
+    /// and `GroupObj` entries are capitalized (`USER`, `GROUP`), and permissions that are
+    /// ineffective due to the `Mask` entry are displayed in upper case.
+    ///
+    /// UID/GID of named entries are resolved to names, falling back to the numeric ID. The owner
+    /// of the file is not known to the ACL, so the qualifier column of `USER` and `GROUP` is empty.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+    /// let mut acl = PosixACL::new(0o640);
+    /// acl.set(Qualifier::User(0), ACL_READ);
+    /// acl.set(Qualifier::Mask, 0);
+    /// assert_eq!(
+    ///     acl.as_tabular_text(),
+    ///     "USER             rw-\n\
+    ///      user   root      R--\n\
+    ///      GROUP            R--\n\
+    ///      mask             ---\n\
+    ///      other            ---\n"
+    /// );
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// When the platform fails to read an entry, use
+    /// [`try_as_tabular_text()`](Self::try_as_tabular_text) to handle errors.
+    #[must_use]
+    pub fn as_tabular_text(&self) -> String {
+        or_panic(self.try_as_tabular_text())
+    }
+
+    /// Like [`as_tabular_text()`](Self::as_tabular_text), but return an error instead of
+    /// panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to read an entry, e.g. failed to allocate
+    ///   memory.
+    pub fn try_as_tabular_text(&self) -> Result<String, ACLError> {
+        let mask = self.try_get(Mask)?;
+        let mut out = String::new();
+
+        for ACLEntry { qual, perm } in self.try_entries()? {
+            let (tag, name) = match qual {
+                UserObj => ("USER", String::new()),
+                User(uid) => ("user", uid_to_name(uid).unwrap_or_else(|| uid.to_string())),
+                GroupObj => ("GROUP", String::new()),
+                Group(gid) => ("group", gid_to_name(gid).unwrap_or_else(|| gid.to_string())),
+                Mask => ("mask", String::new()),
+                Other => ("other", String::new()),
+                Undefined => ("invalid", String::new()),
+                Unknown(tag) => ("unknown", tag.to_string()),
+            };
+            let ineffective = match (qual, mask) {
+                (User(_) | GroupObj | Group(_), Some(mask)) => perm & !mask,
+                _ => 0,
+            };
+            // Writing to String cannot fail.
+            let _ = writeln!(
+                out,
+                "{:<6} {:<9} {}",
+                tag,
+                name,
+                perm_text(perm, ineffective)
+            );
+        }
+        Ok(out)
+    }
+
+    /// Return the textual representation of the ACL like [`as_text()`](Self::as_text), but with
+    /// named `User` and `Group` entries ordered alphabetically by user/group name.
+    ///
+    /// Entries whose UID/GID cannot be resolved to a name are displayed numerically and ordered
+    /// after named ones, by ID. Unlike `as_text()`, no `#effective:` comments are included.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+    /// let mut acl = PosixACL::new(0o640);
+    /// acl.set(Qualifier::User(55555), ACL_READ);
+    /// acl.set(Qualifier::User(0), ACL_READ);
+    /// acl.fix_mask();
+    /// assert_eq!(
+    ///     acl.as_text_sorted_by_name(),
+    ///     "user::rw-\n\
+    ///      user:root:r--\n\
+    ///      user:55555:r--\n\
+    ///      group::r--\n\
+    ///      mask::r--\n\
+    ///      other::---\n"
+    /// );
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// When the platform fails to read an entry, use
+    /// [`try_as_text_sorted_by_name()`](Self::try_as_text_sorted_by_name) to handle errors.
+    #[must_use]
+    pub fn as_text_sorted_by_name(&self) -> String {
+        or_panic(self.try_as_text_sorted_by_name())
+    }
+
+    /// Like [`as_text_sorted_by_name()`](Self::as_text_sorted_by_name), but return an error
+    /// instead of panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to read an entry, e.g. failed to allocate
+    ///   memory.
+    pub fn try_as_text_sorted_by_name(&self) -> Result<String, ACLError> {
+        // Sort key: (position in canonical order, resolved name, qualifier), and the formatted
+        // line.
+        let mut lines: Vec<(u8, Option<String>, Qualifier, String)> = self
+            .try_entries()?
+            .into_iter()
+            .map(|entry| {
+                let name = entry_name(entry.qual);
+                let line = format!("{}\n", named_line(entry, name.as_deref()));
+                (entry.qual.canonical_key().0, name, entry.qual, line)
+            })
+            .collect();
+        // Resolved names first, alphabetically, then unresolved IDs in canonical (numeric) order.
+        lines.sort_by(|a, b| (a.0, a.1.is_none(), &a.1, a.2).cmp(&(b.0, b.1.is_none(), &b.1, b.2)));
+        Ok(lines.into_iter().map(|(_, _, _, line)| line).collect())
+    }
+
+    /// Return the textual representation of the ACL like [`as_text()`](Self::as_text), but with
+    /// numeric UID/GID instead of names, and without `#effective:` comments.
+    ///
+    /// No user or group database lookups are made, so this is suitable for machine-readable
+    /// output, and in chroots or containers where name lookups are slow or unavailable.
+    ///
+    /// ```
+    /// use posix_acl::{PosixACL, Qualifier, ACL_READ};
+    /// let mut acl = PosixACL::new(0o640);
+    /// acl.set(Qualifier::User(0), ACL_READ);
+    /// acl.set(Qualifier::Mask, 0);
+    /// assert_eq!(
+    ///     acl.as_numeric_text(),
+    ///     "user::rw-\nuser:0:r--\ngroup::r--\nmask::---\nother::---\n"
+    /// );
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// When platform returns a string that is not valid UTF-8, or fails to allocate memory. Use
+    /// [`try_as_numeric_text()`](Self::try_as_numeric_text) to handle errors.
+    #[must_use]
+    pub fn as_numeric_text(&self) -> String {
+        or_panic(self.try_as_numeric_text())
+    }
+
+    /// Like [`as_numeric_text()`](Self::as_numeric_text), but return an error instead of
+    /// panicking.
+    ///
+    /// # Errors
+    /// * `ACLError::IoError`: The platform failed to allocate memory, or returned a string that
+    ///   is not valid UTF-8.
+    pub fn try_as_numeric_text(&self) -> Result<String, ACLError> {
+        #[allow(clippy::cast_possible_wrap)]
+        let separator = b'\n' as c_char;
+        let ptr = match unsafe {
+            capabilities::acl_to_any_text(self.acl, null(), separator, TEXT_NUMERIC_IDS)
+        } {
+            Some(ptr) => ptr,
+            None => return self.format_numeric_text(),
+        };
+        try_pointer(ptr, ACL_TYPE_ACCESS)?;
+        let owned = AutoPtr(ptr);
+        let mut text = match unsafe { CStr::from_ptr(owned.0) }.to_str() {
+            Ok(text) => text.to_string(),
+            Err(err) => {
+                let err = io::Error::new(io::ErrorKind::InvalidData, err);
+                return Err(ACLError::io_error(err, ACL_TYPE_ACCESS));
+            }
+        };
+        // Unlike acl_to_text(), there is no separator after the last entry.
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        Ok(text)
+    }
+
+    /// Fallback for [`as_numeric_text()`](Self::as_numeric_text) without `acl_to_any_text()`.
+    fn format_numeric_text(&self) -> Result<String, ACLError> {
+        let mut out = String::new();
+        for entry in self.try_entries()? {
+            out.push_str(&numeric_entry_text(entry));
+            out.push('\n');
+        }
+        Ok(out)
+    }
+}
+
+/// Resolve the UID/GID of a `User` or `Group` entry to a name.
+fn entry_name(qual: Qualifier) -> Option<String> {
+    match qual {
+        User(uid) => uid_to_name(uid),
+        Group(gid) => gid_to_name(gid),
+        _ => None,
+    }
+}
+
+/// Format a single entry with the resolved `name` of its UID/GID, falling back to the number.
+fn named_line(ACLEntry { qual, perm }: ACLEntry, name: Option<&str>) -> String {
+    let (tag, qualifier) = match (qual, name) {
+        (User(_), Some(name)) => ("user", name.to_string()),
+        (User(uid), None) => ("user", uid.to_string()),
+        (Group(_), Some(name)) => ("group", name.to_string()),
+        (Group(gid), None) => ("group", gid.to_string()),
+        (UserObj, _) => ("user", String::new()),
+        (GroupObj, _) => ("group", String::new()),
+        (Mask, _) => ("mask", String::new()),
+        (Other, _) => ("other", String::new()),
+        (Undefined, _) => ("invalid", String::new()),
+        (Unknown(_), _) => ("unknown", String::new()),
+    };
+    format!("{tag}:{qualifier}:{}", perm_text(perm, 0))
+}
+
+/// Format a single entry like [`PosixACL::as_text()`], e.g. `user:alice:rw-`.
+pub(crate) fn named_entry_text(entry: ACLEntry) -> String {
+    named_line(entry, entry_name(entry.qual).as_deref())
+}
+
+/// Format a single entry like [`PosixACL::as_numeric_text()`], e.g. `user:1000:rw-`.
+pub(crate) fn numeric_entry_text(ACLEntry { qual, perm }: ACLEntry) -> String {
+    let (tag, id) = match qual {
+        UserObj => ("user", String::new()),
+        User(uid) => ("user", uid.to_string()),
+        GroupObj => ("group", String::new()),
+        Group(gid) => ("group", gid.to_string()),
+        Mask => ("mask", String::new()),
+        Other => ("other", String::new()),
+        Undefined => ("invalid", String::new()),
+        Unknown(tag) => ("unknown", tag.to_string()),
+    };
+    format!("{}:{}:{}", tag, id, perm_text(perm, 0))
+}
+
+/// Format permission bits like `rw-`. Bits set in `ineffective` are displayed in upper case.
+pub(crate) fn perm_text(perm: u32, ineffective: u32) -> String {
+    [(ACL_READ, 'r'), (ACL_WRITE, 'w'), (ACL_EXECUTE, 'x')]
+        .iter()
+        .map(|&(bit, ch)| {
+            if perm & bit == 0 {
+                '-'
+            } else if ineffective & bit != 0 {
+                ch.to_ascii_uppercase()
+            } else {
+                ch
+            }
+        })
+        .collect()
+}
@@ -1,10 +1,12 @@
 //! This file is for small helpers & utilities that aren't exported by the library.
+use crate::ACLError;
 use acl_sys::acl_free;
-use std::ffi::CString;
-use std::io;
-use std::os::raw::c_void;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_void};
 use std::os::unix::ffi::OsStrExt;
 use std::path::Path;
+use std::ptr::null_mut;
+use std::{io, mem};
 
 /// NB! Unix-only
 pub(crate) fn path_to_cstring(path: &Path) -> CString {
@@ -25,11 +27,122 @@ pub(crate) fn check_return(ret: i32, func: &str) {
     assert_eq!(ret, 0, "Error in {}: {}", func, io::Error::last_os_error());
 }
 
-pub(crate) fn check_pointer<T: ?Sized>(ret: *const T, func: &str) {
-    assert!(
-        !ret.is_null(),
-        "Error in {}: {}",
-        func,
-        io::Error::last_os_error()
-    );
+/// Like [`check_return()`], but return an error instead of panicking. `flags` describe the
+/// operation for the error message, see [`ACLError::last_os_error()`].
+pub(crate) fn try_return(ret: i32, flags: u32) -> Result<(), ACLError> {
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(ACLError::last_os_error(flags))
+    }
+}
+
+/// Like [`try_return()`], for functions returning a pointer that is null on error.
+pub(crate) fn try_pointer<T: ?Sized>(ret: *const T, flags: u32) -> Result<(), ACLError> {
+    if ret.is_null() {
+        Err(ACLError::last_os_error(flags))
+    } else {
+        Ok(())
+    }
+}
+
+/// Unwrap the result of a `try_` function in its panicking counterpart.
+pub(crate) fn or_panic<T>(result: Result<T, ACLError>) -> T {
+    result.unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Resolve `uid` to a user name using the platform's user database, if it has one.
+pub(crate) fn uid_to_name(uid: u32) -> Option<String> {
+    let mut pwd: libc::passwd = unsafe { mem::zeroed() };
+    let mut result: *mut libc::passwd = null_mut();
+    let mut buf: Vec<c_char> = vec![0; 1024];
+    loop {
+        let ret =
+            unsafe { libc::getpwuid_r(uid, &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result) };
+        if ret == libc::ERANGE {
+            buf.resize(buf.len() * 2, 0);
+        } else if ret != 0 || result.is_null() {
+            return None;
+        } else {
+            return Some(
+                unsafe { CStr::from_ptr(pwd.pw_name) }
+                    .to_string_lossy()
+                    .into_owned(),
+            );
+        }
+    }
+}
+
+/// Resolve `gid` to a group name using the platform's group database, if it has one.
+pub(crate) fn gid_to_name(gid: u32) -> Option<String> {
+    let mut grp: libc::group = unsafe { mem::zeroed() };
+    let mut result: *mut libc::group = null_mut();
+    let mut buf: Vec<c_char> = vec![0; 1024];
+    loop {
+        let ret =
+            unsafe { libc::getgrgid_r(gid, &mut grp, buf.as_mut_ptr(), buf.len(), &mut result) };
+        if ret == libc::ERANGE {
+            buf.resize(buf.len() * 2, 0);
+        } else if ret != 0 || result.is_null() {
+            return None;
+        } else {
+            return Some(
+                unsafe { CStr::from_ptr(grp.gr_name) }
+                    .to_string_lossy()
+                    .into_owned(),
+            );
+        }
+    }
+}
+
+/// Resolve user `name` to a UID using the platform's user database.
+pub(crate) fn name_to_uid(name: &str) -> Option<u32> {
+    let c_name = CString::new(name).ok()?;
+    let mut pwd: libc::passwd = unsafe { mem::zeroed() };
+    let mut result: *mut libc::passwd = null_mut();
+    let mut buf: Vec<c_char> = vec![0; 1024];
+    loop {
+        let ret = unsafe {
+            libc::getpwnam_r(
+                c_name.as_ptr(),
+                &mut pwd,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut result,
+            )
+        };
+        if ret == libc::ERANGE {
+            buf.resize(buf.len() * 2, 0);
+        } else if ret != 0 || result.is_null() {
+            return None;
+        } else {
+            return Some(pwd.pw_uid);
+        }
+    }
+}
+
+/// Resolve group `name` to a GID using the platform's group database.
+pub(crate) fn name_to_gid(name: &str) -> Option<u32> {
+    let c_name = CString::new(name).ok()?;
+    let mut grp: libc::group = unsafe { mem::zeroed() };
+    let mut result: *mut libc::group = null_mut();
+    let mut buf: Vec<c_char> = vec![0; 1024];
+    loop {
+        let ret = unsafe {
+            libc::getgrnam_r(
+                c_name.as_ptr(),
+                &mut grp,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut result,
+            )
+        };
+        if ret == libc::ERANGE {
+            buf.resize(buf.len() * 2, 0);
+        } else if ret != 0 || result.is_null() {
+            return None;
+        } else {
+            return Some(grp.gr_gid);
+        }
+    }
 }
@@ -0,0 +1,151 @@
+//! Interoperability with the extended attributes that store ACLs on Linux.
+//!
+//! The kernel stores ACLs in the `system.posix_acl_access` and `system.posix_acl_default` extended
+//! attributes, in a little-endian binary format. [`PosixACL::to_xattr()`] and
+//! [`PosixACL::from_xattr()`] convert between `PosixACL` and that format, so code that handles raw
+//! attributes (e.g. using the [`xattr`](https://docs.rs/xattr) crate) can work with ACLs.
+//!
+//! ```
+//! use posix_acl::{PosixACL, Qualifier, ACL_READ};
+//! let mut acl = PosixACL::new(0o640);
+//! acl.set(Qualifier::User(1234), ACL_READ);
+//! acl.fix_mask();
+//!
+//! // Value of the `xattr::ACCESS` attribute, e.g. for `xattr::set()`
+//! let blob = acl.to_xattr();
+//! assert_eq!(PosixACL::from_xattr(&blob).unwrap(), acl);
+//! ```
+use crate::Qualifier::{Group, GroupObj, Mask, Other, Undefined, Unknown, User, UserObj};
+use crate::{ACLError, PosixACL, ACL_RWX};
+use acl_sys::{
+    acl_tag_t, ACL_GROUP, ACL_GROUP_OBJ, ACL_MASK, ACL_OTHER, ACL_UNDEFINED_TAG, ACL_USER,
+    ACL_USER_OBJ,
+};
+use std::convert::TryFrom;
+
+/// Name of the extended attribute storing the access ACL.
+pub const ACCESS: &str = "system.posix_acl_access";
+/// Name of the extended attribute storing the default ACL of a directory.
+pub const DEFAULT: &str = "system.posix_acl_default";
+/// Name of the extended attribute storing a shadow access ACL, see
+/// [`PosixACL::write_acl_shadowed()`].
+pub const SHADOW_ACCESS: &str = "user.posix_acl_access";
+/// Name of the extended attribute storing a shadow default ACL, see
+/// [`PosixACL::write_default_acl_shadowed()`].
+pub const SHADOW_DEFAULT: &str = "user.posix_acl_default";
+
+const XATTR_VERSION: u32 = 2;
+const HEADER_SIZE: usize = 4;
+const ENTRY_SIZE: usize = 8;
+/// `e_id` of entries that have no qualifier
+const UNDEFINED_ID: u32 = u32::MAX;
+
+impl PosixACL {
+    /// Encode the ACL in the binary format of the `system.posix_acl_access` and
+    /// `system.posix_acl_default` extended attributes.
+    ///
+    /// Entries are written in the order required by the kernel. Note that the kernel also
+    /// requires a valid ACL, see [`validate()`](Self::validate).
+    ///
+    /// ```
+    /// use posix_acl::PosixACL;
+    /// let acl = PosixACL::new(0o640);
+    /// assert_eq!(
+    ///     acl.to_xattr(),
+    ///     [
+    ///         2, 0, 0, 0, // version
+    ///         0x01, 0, 6, 0, 0xff, 0xff, 0xff, 0xff, // user::rw-
+    ///         0x04, 0, 4, 0, 0xff, 0xff, 0xff, 0xff, // group::r--
+    ///         0x20, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, // other::---
+    ///     ]
+    /// );
+    /// ```
+    #[must_use]
+    pub fn to_xattr(&self) -> Vec<u8> {
+        let mut entries: Vec<(acl_tag_t, u16, u32)> = self
+            .entries()
+            .iter()
+            .map(|entry| {
+                let (tag, id) = match entry.qual {
+                    Undefined => (ACL_UNDEFINED_TAG, UNDEFINED_ID),
+                    UserObj => (ACL_USER_OBJ, UNDEFINED_ID),
+                    User(uid) => (ACL_USER, uid),
+                    GroupObj => (ACL_GROUP_OBJ, UNDEFINED_ID),
+                    Group(gid) => (ACL_GROUP, gid),
+                    Mask => (ACL_MASK, UNDEFINED_ID),
+                    Other => (ACL_OTHER, UNDEFINED_ID),
+                    Unknown(tag) => (tag, UNDEFINED_ID),
+                };
+                // Permission bits are at most ACL_RWX, truncation is not possible.
+                #[allow(clippy::cast_possible_truncation)]
+                (tag, (entry.perm & ACL_RWX) as u16, id)
+            })
+            .collect();
+        entries.sort_unstable_by_key(|&(tag, _, id)| (tag, id));
+
+        let mut buf = Vec::with_capacity(HEADER_SIZE + entries.len() * ENTRY_SIZE);
+        buf.extend_from_slice(&XATTR_VERSION.to_le_bytes());
+        for (tag, perm, id) in entries {
+            // The on-disk tag is 16 bits, other values cannot be represented.
+            let tag = u16::try_from(tag).unwrap_or(0);
+            buf.extend_from_slice(&tag.to_le_bytes());
+            buf.extend_from_slice(&perm.to_le_bytes());
+            buf.extend_from_slice(&id.to_le_bytes());
+        }
+        buf
+    }
+
+    /// Decode an ACL from the binary format of the `system.posix_acl_access` and
+    /// `system.posix_acl_default` extended attributes.
+    ///
+    /// # Errors
+    /// * `ACLError::ParseError`: The data is truncated, has an unsupported version, or contains
+    ///   unknown tags, invalid permissions or duplicate entries.
+    pub fn from_xattr(data: &[u8]) -> Result<PosixACL, ACLError> {
+        if data.len() < HEADER_SIZE || (data.len() - HEADER_SIZE) % ENTRY_SIZE != 0 {
+            return Err(ACLError::parse_error(format!(
+                "Invalid xattr data length {}",
+                data.len()
+            )));
+        }
+        let version = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        if version != XATTR_VERSION {
+            return Err(ACLError::parse_error(format!(
+                "Unsupported xattr version {version}"
+            )));
+        }
+
+        let entries = data[HEADER_SIZE..].chunks_exact(ENTRY_SIZE);
+        let mut acl = PosixACL::with_capacity(entries.len());
+        for chunk in entries {
+            let tag = u16::from_le_bytes([chunk[0], chunk[1]]);
+            let perm = u32::from(u16::from_le_bytes([chunk[2], chunk[3]]));
+            let id = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+
+            let qual = match acl_tag_t::from(tag) {
+                ACL_USER_OBJ => UserObj,
+                ACL_USER => User(id),
+                ACL_GROUP_OBJ => GroupObj,
+                ACL_GROUP => Group(id),
+                ACL_MASK => Mask,
+                ACL_OTHER => Other,
+                _ => {
+                    return Err(ACLError::parse_error(format!(
+                        "Invalid xattr entry tag {tag:#x}"
+                    )))
+                }
+            };
+            if perm & !ACL_RWX != 0 {
+                return Err(ACLError::parse_error(format!(
+                    "Invalid xattr entry permissions {perm:#o}"
+                )));
+            }
+            if acl.try_set(qual, perm)?.is_some() {
+                return Err(ACLError::parse_error(format!(
+                    "Duplicate xattr entry {qual:?}"
+                )));
+            }
+        }
+        Ok(acl)
+    }
+}
@@ -2,12 +2,23 @@
 //!
 //! For internal unit tests, write directly into `src/` modules.
 
-use acl_sys::{acl_free, ACL_EXECUTE, ACL_READ, ACL_WRITE};
+use acl_sys::{acl_entries, acl_free, acl_valid, ACL_EXECUTE, ACL_READ, ACL_WRITE};
+use camino::{Utf8Path, Utf8PathBuf};
+use posix_acl::audit::FindingKind;
+use posix_acl::dump::{DumpEntry, DumpReader, NameMap, UnmappedNames};
 use posix_acl::Qualifier::*;
-use posix_acl::{ACLEntry, ACLError, PosixACL, ACL_RWX};
-use std::fs::OpenOptions;
+use posix_acl::{
+    chown_with_acl_fixup, presets, setup_shared_dir, ACLBackend, ACLEntry, ACLError, AccessClass,
+    Capabilities, FileACLs, FsBackend, MemoryBackend, OwnerFixup, PosixACL, Principal, ScopedACL,
+    ValidationReason, ACL_RWX, CAP_DAC_OVERRIDE, CAP_DAC_READ_SEARCH,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::ffi::CString;
+use std::fs::{metadata, set_permissions, OpenOptions, Permissions};
 use std::io::ErrorKind;
-use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt, PermissionsExt};
 use std::path::{Path, PathBuf};
 use tempfile::{tempdir, TempDir};
 
@@ -40,6 +51,17 @@ fn new() {
     assert_eq!(acl.as_text(), "user::rwx\ngroup::r-x\nother::--x\n");
     assert!(acl.validate().is_ok());
 }
+/// Bits above the permission bits (SUID, SGID, sticky, file type) are ignored.
+#[test]
+fn new_high_bits() {
+    assert_eq!(PosixACL::new(0o4755), PosixACL::new(0o755));
+    assert_eq!(PosixACL::new(0o7777), PosixACL::new(0o777));
+    assert_eq!(PosixACL::new(0o100_640), PosixACL::new(0o640));
+    assert_eq!(
+        PosixACL::new(0o2750).as_text(),
+        "user::rwx\ngroup::r-x\nother::---\n"
+    );
+}
 #[test]
 fn empty() {
     let acl = PosixACL::empty();
@@ -95,6 +117,40 @@ fn validate_ok() {
     acl.fix_mask();
     assert!(acl.validate().is_ok());
 }
+#[test]
+fn validation_reason() {
+    let err = PosixACL::empty().validate().unwrap_err();
+    assert_eq!(
+        err.validation_reason(),
+        Some(ValidationReason::MissingEntry)
+    );
+
+    // Named entry without mask
+    let mut acl = PosixACL::new(0o640);
+    acl.set(User(0), ACL_READ);
+    let err = acl.validate().unwrap_err();
+    assert_eq!(
+        err.validation_reason(),
+        Some(ValidationReason::MissingEntry)
+    );
+
+    let acl = PosixACL::from_text("u::rw,u::r,g::r,o::r").unwrap();
+    let err = acl.validate().unwrap_err();
+    assert_eq!(
+        err.validation_reason(),
+        Some(ValidationReason::MultipleEntries)
+    );
+
+    let acl = PosixACL::from_text("u::rw,u:0:r,u:0:w,g::r,m::rw,o::r").unwrap();
+    let err = acl.validate().unwrap_err();
+    assert_eq!(
+        err.validation_reason(),
+        Some(ValidationReason::DuplicateEntry)
+    );
+
+    let err = PosixACL::from_text("bogus").unwrap_err();
+    assert_eq!(err.validation_reason(), None);
+}
 /// .set() method overwrites previous entry if one exists.
 #[test]
 fn set_overwrite() {
@@ -106,6 +162,15 @@ fn set_overwrite() {
     acl.set(UserObj, ACL_READ);
     assert_eq!(acl.as_text(), "user::r--\n");
 }
+/// .set() method returns the previous permissions, if any.
+#[test]
+fn set_returns_previous() {
+    let mut acl = PosixACL::new(0o640);
+    assert_eq!(acl.set(UserObj, ACL_RWX), Some(ACL_READ | ACL_WRITE));
+    assert_eq!(acl.set(UserObj, ACL_READ), Some(ACL_RWX));
+    assert_eq!(acl.set(User(1234), ACL_READ), None);
+    assert_eq!(acl.set(User(1234), 0), Some(ACL_READ));
+}
 /// Test .get() method
 #[test]
 fn get() {
@@ -132,11 +197,51 @@ fn remove() {
 
     assert_eq!(acl.entries(), [])
 }
+/// Test .try_remove() method
+#[test]
+fn try_remove() {
+    let mut acl = full_fixture();
+    assert_eq!(acl.try_remove(User(0)).unwrap(), Some(ACL_READ | ACL_WRITE));
+    assert_eq!(acl.try_remove(User(0)).unwrap(), None);
+    assert_eq!(acl.try_remove(GroupObj).unwrap(), Some(ACL_READ));
+    assert_eq!(acl.get(GroupObj), None);
+}
+/// Test .remove_checked() method
+#[test]
+fn remove_checked() {
+    let mut acl = full_fixture();
+    for qual in [UserObj, GroupObj, Other] {
+        let err = acl.remove_checked(qual).unwrap_err();
+        assert!(matches!(err, ACLError::ValidationError(_)));
+    }
+    assert_eq!(
+        acl.remove_checked(User(0)).unwrap(),
+        Some(ACL_READ | ACL_WRITE)
+    );
+    assert_eq!(acl.remove_checked(User(0)).unwrap(), None);
+    assert_eq!(
+        acl.remove_checked(Mask).unwrap(),
+        Some(ACL_READ | ACL_WRITE)
+    );
+    assert_eq!(acl.get(GroupObj), Some(ACL_READ));
+}
+/// Test .copy_entry_from() method
+#[test]
+fn copy_entry_from() {
+    let src = full_fixture();
+    let mut acl = PosixACL::new(0o600);
+    assert!(acl.copy_entry_from(&src, User(0)));
+    assert!(acl.copy_entry_from(&src, GroupObj));
+    assert!(!acl.copy_entry_from(&src, User(1234)));
+    assert_eq!(
+        acl.as_text(),
+        "user::rw-\nuser:root:rw-\ngroup::r--\nother::---\n"
+    );
+}
 #[test]
 fn equality() {
-    let acl = PosixACL::new(0o751);
+    let mut acl = PosixACL::new(0o751);
 
-    // Not using assert_eq! because the debug trait is not implemented by PosixACL.
     assert_eq!(acl, acl);
     assert_eq!(acl, PosixACL::new(0o751));
     assert_ne!(acl, PosixACL::new(0o741));
@@ -200,6 +305,118 @@ fn debug() {
         \")"
     );
 }
+#[test]
+fn tabular_text() {
+    let acl = full_fixture();
+    assert_eq!(
+        acl.as_tabular_text(),
+        "USER             rw-\n\
+         user   root      rw-\n\
+         user   55555     ---\n\
+         GROUP            r--\n\
+         group  root      r--\n\
+         group  55555     ---\n\
+         mask             rw-\n\
+         other            ---\n"
+    );
+}
+#[test]
+fn tabular_text_ineffective() {
+    let mut acl = full_fixture();
+    acl.set(Mask, ACL_READ);
+    assert_eq!(
+        acl.as_tabular_text(),
+        "USER             rw-\n\
+         user   root      rW-\n\
+         user   55555     ---\n\
+         GROUP            r--\n\
+         group  root      r--\n\
+         group  55555     ---\n\
+         mask             r--\n\
+         other            ---\n"
+    );
+}
+#[test]
+fn xattr_roundtrip() {
+    let acl = full_fixture();
+    let decoded = PosixACL::from_xattr(&acl.to_xattr()).unwrap();
+    assert_eq!(decoded, acl);
+
+    let acl = PosixACL::empty();
+    assert_eq!(acl.to_xattr(), [2, 0, 0, 0]);
+    assert_eq!(PosixACL::from_xattr(&[2, 0, 0, 0]).unwrap(), acl);
+}
+/// Read an extended attribute of `path`.
+fn get_xattr(path: &Path, name: &str) -> Vec<u8> {
+    let c_path = CString::new(path.as_os_str().as_bytes()).unwrap();
+    let c_name = CString::new(name).unwrap();
+    let mut buf = vec![0_u8; 4096];
+    let size = unsafe {
+        libc::getxattr(
+            c_path.as_ptr(),
+            c_name.as_ptr(),
+            buf.as_mut_ptr().cast(),
+            buf.len(),
+        )
+    };
+    assert!(size >= 0, "{}", std::io::Error::last_os_error());
+    buf.truncate(size as usize);
+    buf
+}
+
+/// The codec matches the format used by the kernel.
+#[test]
+fn xattr_kernel() {
+    let dir = tempdir().unwrap();
+    let mut acl = full_fixture();
+    acl.write_acl(dir.path()).unwrap();
+    let data = get_xattr(dir.path(), posix_acl::xattr::ACCESS);
+    assert_eq!(data, acl.to_xattr());
+    assert_eq!(PosixACL::from_xattr(&data).unwrap(), acl);
+
+    // Kernel accepts encoded ACL
+    set_xattr(dir.path(), posix_acl::xattr::DEFAULT, &acl.to_xattr());
+    assert_eq!(PosixACL::read_default_acl(dir.path()).unwrap(), acl);
+}
+#[test]
+fn xattr_invalid() {
+    let valid = PosixACL::new(0o640).to_xattr();
+    let err = PosixACL::from_xattr(&valid[..7]).unwrap_err();
+    assert!(matches!(err, ACLError::ParseError(_)));
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert_eq!(
+        err.to_string(),
+        "Error parsing ACL: Invalid xattr data length 7"
+    );
+
+    let mut data = valid.clone();
+    data[0] = 1;
+    assert_eq!(
+        PosixACL::from_xattr(&data).unwrap_err().to_string(),
+        "Error parsing ACL: Unsupported xattr version 1"
+    );
+
+    let mut data = valid.clone();
+    data[4] = 0x40;
+    assert_eq!(
+        PosixACL::from_xattr(&data).unwrap_err().to_string(),
+        "Error parsing ACL: Invalid xattr entry tag 0x40"
+    );
+
+    let mut data = valid.clone();
+    data[6] = 0o10;
+    assert_eq!(
+        PosixACL::from_xattr(&data).unwrap_err().to_string(),
+        "Error parsing ACL: Invalid xattr entry permissions 0o10"
+    );
+
+    let mut data = valid.clone();
+    data.extend_from_slice(&valid[4..12]);
+    assert_eq!(
+        PosixACL::from_xattr(&data).unwrap_err().to_string(),
+        "Error parsing ACL: Duplicate xattr entry UserObj"
+    );
+}
 /// Make sure that ACL survives the write+read round-trip
 #[test]
 fn writeread() {
@@ -223,6 +440,22 @@ fn writeread_default() {
     let acl2 = PosixACL::read_default_acl(dir.path()).unwrap();
     assert_eq!(acl1, acl2);
 }
+/// reported_mode() agrees with what the kernel reports after writing the ACL
+#[test]
+fn reported_mode() {
+    let dir = tempdir().unwrap();
+    let path = test_file(&dir, "test.file", 0o777);
+
+    let mut acl = PosixACL::new(0o751);
+    acl.write_acl(&path).unwrap();
+    assert_eq!(acl.reported_mode(), 0o751);
+    assert_eq!(metadata(&path).unwrap().permissions().mode() & 0o777, 0o751);
+
+    let mut acl = full_fixture();
+    acl.write_acl(&path).unwrap();
+    assert_eq!(acl.reported_mode(), 0o660);
+    assert_eq!(metadata(&path).unwrap().permissions().mode() & 0o777, 0o660);
+}
 #[test]
 fn read_file_with_no_acl() {
     let dir = tempdir().unwrap();
@@ -246,6 +479,34 @@ fn read_acl_not_found() {
     );
 }
 #[test]
+fn error_report() {
+    let mut acl = PosixACL::new(0o644);
+    let report = acl.write_acl("file_not_found").unwrap_err().report();
+    assert_eq!(report.kind, "NotFound");
+    assert_eq!(report.errno, Some(libc::ENOENT));
+    assert_eq!(report.path.as_deref(), Some("file_not_found"));
+    assert_eq!(report.operation.as_deref(), Some("writing ACL"));
+    assert_eq!(
+        report.message,
+        "Error writing ACL: No such file or directory (os error 2)"
+    );
+
+    let report = PosixACL::empty().validate().unwrap_err().report();
+    assert_eq!(report.kind, "InvalidData");
+    assert_eq!(report.errno, None);
+    assert_eq!(report.path, None);
+    assert_eq!(report.operation, None);
+}
+#[cfg(feature = "serde")]
+#[test]
+fn error_serialize() {
+    let err = PosixACL::read_acl("file_not_found").unwrap_err();
+    assert_eq!(
+        serde_json::to_string(&err).unwrap(),
+        r#"{"kind":"NotFound","errno":2,"path":"file_not_found","operation":"reading ACL","message":"Error reading ACL: No such file or directory (os error 2)"}"#
+    );
+}
+#[test]
 fn write_acl_not_found() {
     let mut acl = PosixACL::new(0o644);
     let err = acl.write_acl("file_not_found").unwrap_err();
@@ -272,6 +533,9 @@ fn path_types() {
     PosixACL::read_acl(&Path::new("/tmp")).unwrap();
     PosixACL::read_acl(PathBuf::from("/tmp")).unwrap();
     PosixACL::read_acl(&PathBuf::from("/tmp")).unwrap();
+    // camino UTF-8 paths implement AsRef<Path> too
+    PosixACL::read_acl(Utf8Path::new("/tmp")).unwrap();
+    PosixACL::read_acl(Utf8PathBuf::from("/tmp")).unwrap();
 }
 /// read_default_acl() fails when called with non-directory
 #[test]
@@ -316,3 +580,2302 @@ fn acl_into_raw_free() {
     let ptr = acl1.into_raw();
     assert_eq!(unsafe { acl_free(ptr) }, 0);
 }
+
+#[test]
+fn acl_as_raw() {
+    let acl = full_fixture();
+    let ptr = acl.as_raw();
+    assert_eq!(unsafe { acl_valid(ptr) }, 0);
+    assert_eq!(unsafe { acl_entries(ptr) }, 8);
+    assert_eq!(acl, full_fixture());
+}
+
+/// Changing ownership requires root, these tests are skipped otherwise.
+fn is_root() -> bool {
+    unsafe { libc::geteuid() == 0 }
+}
+
+fn chown_fixture(dir: &TempDir, fixup: OwnerFixup) -> PosixACL {
+    let path = test_file(dir, "test.file", 0o640);
+    let mut acl = PosixACL::new(0o640);
+    acl.set(User(0), ACL_READ | ACL_WRITE);
+    acl.set(Group(0), ACL_READ);
+    acl.set(User(55556), ACL_EXECUTE);
+    acl.write_acl(&path).unwrap();
+
+    chown_with_acl_fixup(&path, Some(55556), Some(55557), fixup).unwrap();
+    let meta = metadata(&path).unwrap();
+    assert_eq!((meta.uid(), meta.gid()), (55556, 55557));
+    PosixACL::read_acl(&path).unwrap()
+}
+
+#[test]
+fn chown_fixup_rebind() {
+    if !is_root() {
+        return;
+    }
+    let dir = tempdir().unwrap();
+    let acl = chown_fixture(&dir, OwnerFixup::Rebind);
+    assert_eq!(acl.get(User(0)), None);
+    assert_eq!(acl.get(Group(0)), None);
+    assert_eq!(acl.get(User(55556)), Some(ACL_RWX));
+    assert_eq!(acl.get(Group(55557)), Some(ACL_READ));
+}
+
+#[test]
+fn chown_fixup_drop() {
+    if !is_root() {
+        return;
+    }
+    let dir = tempdir().unwrap();
+    let acl = chown_fixture(&dir, OwnerFixup::Drop);
+    assert_eq!(acl.get(User(0)), None);
+    assert_eq!(acl.get(Group(0)), None);
+    assert_eq!(acl.get(User(55556)), Some(ACL_EXECUTE));
+    assert_eq!(acl.get(Group(55557)), None);
+}
+
+#[test]
+fn chown_fixup_keep() {
+    if !is_root() {
+        return;
+    }
+    let dir = tempdir().unwrap();
+    let acl = chown_fixture(&dir, OwnerFixup::Keep);
+    assert_eq!(acl.get(User(0)), Some(ACL_READ | ACL_WRITE));
+    assert_eq!(acl.get(Group(0)), Some(ACL_READ));
+}
+
+#[test]
+fn chown_fixup_not_found() {
+    let err =
+        chown_with_acl_fixup("file_not_found", Some(0), None, OwnerFixup::Rebind).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    assert_eq!(
+        err.to_string(),
+        "Error reading file: No such file or directory (os error 2)"
+    );
+}
+
+#[test]
+fn shared_dir() {
+    if !is_root() {
+        return;
+    }
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("share");
+    std::fs::create_dir(&path).unwrap();
+    // Sticky bit is kept
+    set_permissions(&path, Permissions::from_mode(0o1700)).unwrap();
+    let mut acl = PosixACL::read_acl(&path).unwrap();
+    acl.set(User(55555), ACL_READ);
+    acl.write_acl(&path).unwrap();
+
+    setup_shared_dir(&path, 55555, ACL_RWX).unwrap();
+
+    let meta = metadata(&path).unwrap();
+    assert_eq!(meta.gid(), 55555);
+    assert_eq!(meta.mode() & 0o7777, 0o3770);
+    let acl = PosixACL::read_acl(&path).unwrap();
+    assert_eq!(acl.get(GroupObj), Some(ACL_RWX));
+    assert_eq!(acl.get(User(55555)), Some(ACL_READ));
+    assert_eq!(PosixACL::read_default_acl(&path).unwrap(), acl);
+
+    // New files inherit the group and ACL
+    let file = path.join("file");
+    std::fs::File::create(&file).unwrap();
+    assert_eq!(metadata(&file).unwrap().gid(), 55555);
+    assert_eq!(
+        PosixACL::read_acl(&file).unwrap().get(User(55555)),
+        Some(ACL_READ)
+    );
+}
+
+#[test]
+fn shared_dir_not_dir() {
+    let dir = tempdir().unwrap();
+    let path = test_file(&dir, "file", 0o640);
+    let gid = metadata(&path).unwrap().gid();
+    let err = setup_shared_dir(&path, 55555, ACL_RWX).unwrap_err();
+    assert_eq!(
+        err.as_io_error().unwrap().raw_os_error(),
+        Some(libc::ENOTDIR)
+    );
+    assert_eq!(metadata(&path).unwrap().gid(), gid);
+}
+
+#[cfg(feature = "testing")]
+#[test]
+fn kernel_allows() {
+    use posix_acl::testing::kernel_allows;
+
+    if !is_root() {
+        return;
+    }
+    let dir = tempdir().unwrap();
+    set_permissions(dir.path(), Permissions::from_mode(0o711)).unwrap();
+    let path = test_file(&dir, "test.file", 0o640);
+    let mut acl = PosixACL::new(0o640);
+    acl.set(User(55555), ACL_READ | ACL_WRITE);
+    acl.set(Group(55556), ACL_READ);
+    acl.write_acl(&path).unwrap();
+
+    assert!(kernel_allows(&path, 55555, 55555, &[], ACL_READ | ACL_WRITE).unwrap());
+    assert!(!kernel_allows(&path, 55555, 55555, &[], ACL_EXECUTE).unwrap());
+    assert!(kernel_allows(&path, 55557, 55557, &[55556], ACL_READ).unwrap());
+    assert!(!kernel_allows(&path, 55557, 55557, &[55556], ACL_WRITE).unwrap());
+    assert!(!kernel_allows(&path, 55557, 55557, &[], ACL_READ).unwrap());
+}
+
+/// allows_privileged() with root's capabilities agrees with the kernel.
+#[cfg(feature = "testing")]
+#[test]
+fn kernel_allows_privileged() {
+    use posix_acl::testing::kernel_allows;
+
+    if !is_root() {
+        return;
+    }
+    let dir = tempdir().unwrap();
+    let caps = CAP_DAC_OVERRIDE | CAP_DAC_READ_SEARCH;
+    for (i, &mode) in [0o000, 0o600, 0o700, 0o601, 0o610].iter().enumerate() {
+        let file = test_file(&dir, &format!("file{i}"), 0o600);
+        let sub = dir.path().join(format!("dir{i}"));
+        std::fs::create_dir(&sub).unwrap();
+        for path in [&file, &sub] {
+            std::os::unix::fs::chown(path, Some(55555), Some(55555)).unwrap();
+            let mut acl = PosixACL::new(mode);
+            acl.set(User(55556), ACL_READ | ACL_WRITE);
+            acl.write_acl(path).unwrap();
+
+            let is_dir = path == &sub;
+            for want in 1..=ACL_RWX {
+                assert_eq!(
+                    acl.allows_privileged(0, &[0], want, 55555, 55555, is_dir, caps),
+                    kernel_allows(path, 0, 0, &[], want).unwrap(),
+                    "{path:?} mode {mode:o} want {want}"
+                );
+            }
+        }
+    }
+}
+
+/// Fallbacks for missing libacl extension functions give the same results as libacl.
+#[cfg(feature = "testing")]
+#[test]
+fn extension_fallbacks() {
+    use posix_acl::testing::without_extensions;
+    let dir = tempdir().unwrap();
+    let minimal = test_file(&dir, "minimal", 0o640);
+    let extended = test_file(&dir, "extended", 0o640);
+    full_fixture().write_acl(&extended).unwrap();
+    let plain_dir = dir.path().join("plain_dir");
+    std::fs::create_dir(&plain_dir).unwrap();
+    let default_dir = dir.path().join("default_dir");
+    std::fs::create_dir(&default_dir).unwrap();
+    PosixACL::new(0o750)
+        .write_default_acl(&default_dir)
+        .unwrap();
+
+    let observe = || {
+        let mut out = Vec::new();
+        for &mode in &[0, 0o640, 0o751, 0o777, 0o7777] {
+            let acl = PosixACL::new(mode);
+            out.push(format!("new({mode:o}) = {acl:?}"));
+            out.push(format!("equivalent_mode = {:?}", acl.equivalent_mode()));
+            out.push(format!("len = {}", acl.len()));
+            out.push(format!("numeric = {}", acl.as_numeric_text()));
+        }
+        for acl in &[PosixACL::empty(), full_fixture()] {
+            out.push(format!("equivalent_mode = {:?}", acl.equivalent_mode()));
+            out.push(format!("len = {}", acl.len()));
+            out.push(format!("numeric = {}", acl.as_numeric_text()));
+            out.push(format!("validate = {:?}", acl.validate().is_ok()));
+        }
+        out.push(format!("eq = {}", full_fixture() == full_fixture()));
+        out.push(format!(
+            "eq = {}",
+            PosixACL::new(0o640) == PosixACL::new(0o640)
+        ));
+        out.push(format!(
+            "eq = {}",
+            PosixACL::new(0o640) == PosixACL::new(0o644)
+        ));
+        out.push(format!("eq = {}", full_fixture() == PosixACL::new(0o640)));
+        for path in &[&minimal, &extended, &plain_dir, &default_dir] {
+            let ext = PosixACL::has_extended_acl(path).unwrap();
+            out.push(format!("has_extended_acl({path:?}) = {ext}"));
+            let file = std::fs::File::open(path).unwrap();
+            let ext = PosixACL::has_extended_acl_fd(&file).unwrap();
+            out.push(format!("has_extended_acl_fd({path:?}) = {ext}"));
+        }
+        let missing = PosixACL::has_extended_acl(dir.path().join("missing")).is_err();
+        out.push(format!("has_extended_acl(missing) = {missing}"));
+        out
+    };
+    let native = observe();
+    assert_eq!(without_extensions(observe), native);
+    assert!(native.contains(&format!("has_extended_acl_fd({extended:?}) = true")));
+    assert!(native.contains(&format!("has_extended_acl({default_dir:?}) = true")));
+
+    // Without acl_check() the reason is unknown.
+    let err = without_extensions(|| PosixACL::empty().validate()).unwrap_err();
+    assert_eq!(err.validation_reason(), Some(ValidationReason::Unknown));
+}
+
+#[test]
+fn scoped_acl() {
+    let dir = tempdir().unwrap();
+    let path = test_file(&dir, "file", 0o640);
+    full_fixture().write_acl(&path).unwrap();
+    // chmod narrows the mask; it must survive the round-trip without being re-calculated.
+    set_permissions(&path, Permissions::from_mode(0o600)).unwrap();
+    let original = PosixACL::read_acl(&path).unwrap();
+    assert_eq!(original.get(Mask), Some(0));
+
+    {
+        let _guard = ScopedACL::set(&path, PosixACL::new(0o777)).unwrap();
+        assert_eq!(PosixACL::read_acl(&path).unwrap().reported_mode(), 0o777);
+    }
+    assert_eq!(PosixACL::read_acl(&path).unwrap(), original);
+
+    let result = std::panic::catch_unwind(|| {
+        let _guard = ScopedACL::set(&path, PosixACL::new(0o777)).unwrap();
+        panic!("oops");
+    });
+    assert!(result.is_err());
+    assert_eq!(PosixACL::read_acl(&path).unwrap(), original);
+
+    let guard = ScopedACL::set(&path, PosixACL::new(0o600)).unwrap();
+    guard.restore().unwrap();
+    assert_eq!(PosixACL::read_acl(&path).unwrap(), original);
+}
+
+#[test]
+fn scoped_acl_default() {
+    let dir = tempdir().unwrap();
+    {
+        let _guard = ScopedACL::set_default(dir.path(), PosixACL::new(0o750)).unwrap();
+        assert!(!PosixACL::read_default_acl(dir.path())
+            .unwrap()
+            .entries()
+            .is_empty());
+    }
+    assert_eq!(
+        PosixACL::read_default_acl(dir.path()).unwrap(),
+        PosixACL::empty()
+    );
+}
+
+#[test]
+fn text_sorted_by_name() {
+    let mut acl = PosixACL::new(0o640);
+    for uid in &[55555, 0, 55554, 1, 2] {
+        acl.set(User(*uid), ACL_READ);
+    }
+    acl.set(Group(55555), ACL_READ);
+    acl.set(Group(0), ACL_READ);
+    acl.fix_mask();
+    assert_eq!(
+        acl.as_text_sorted_by_name(),
+        "user::rw-\n\
+         user:bin:r--\n\
+         user:daemon:r--\n\
+         user:root:r--\n\
+         user:55554:r--\n\
+         user:55555:r--\n\
+         group::r--\n\
+         group:root:r--\n\
+         group:55555:r--\n\
+         mask::r--\n\
+         other::---\n"
+    );
+}
+
+#[test]
+fn read_write_acl_fd() {
+    let dir = tempdir().unwrap();
+    let path = test_file(&dir, "file", 0o640);
+    let file = std::fs::File::open(&path).unwrap();
+    assert_eq!(PosixACL::read_acl_fd(&file).unwrap(), PosixACL::new(0o640));
+
+    full_fixture().write_acl_fd(&file).unwrap();
+    assert_eq!(PosixACL::read_acl(&path).unwrap(), full_fixture());
+    assert_eq!(PosixACL::read_acl_fd(&file).unwrap(), full_fixture());
+
+    // Still refers to the same file after rename
+    let renamed = dir.path().join("renamed");
+    std::fs::rename(&path, &renamed).unwrap();
+    PosixACL::new(0o600).write_acl_fd(&file).unwrap();
+    assert_eq!(PosixACL::read_acl(&renamed).unwrap().reported_mode(), 0o600);
+
+    let err = PosixACL::empty().write_acl_fd(&file).unwrap_err();
+    assert_eq!(err.to_string(), "ACL failed validation");
+}
+
+#[test]
+fn from_text() {
+    let acl = full_fixture();
+    assert_eq!(PosixACL::from_text(&acl.as_text()).unwrap(), acl);
+    let acl = PosixACL::from_text(
+        "user::rw-\n\
+         user:root:rw-\n\
+         group::r--\n\
+         mask::rw-\n\
+         other::---\n",
+    )
+    .unwrap();
+    assert_eq!(acl.get(User(0)), Some(ACL_READ | ACL_WRITE));
+    assert_eq!(acl.get(Mask), Some(ACL_READ | ACL_WRITE));
+    assert_eq!(acl.len(), 5);
+
+    for text in &[
+        "user::rwz",
+        "bogus::rwx",
+        "user:nosuchuser-xyz:rwx",
+        "user::r\0",
+    ] {
+        let err = PosixACL::from_text(text).unwrap_err();
+        assert!(matches!(err, ACLError::ParseError(_)), "{}", text);
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+    assert_eq!(
+        PosixACL::from_text("user::rwz").unwrap_err().to_string(),
+        "Error parsing ACL: Invalid ACL text \"user::rwz\""
+    );
+}
+
+#[test]
+fn from_str() {
+    let acl: PosixACL = "u::rwx,g::r-x,o::---,u:root:rw,g:0:r,m::rwx"
+        .parse()
+        .unwrap();
+    let mut expected = PosixACL::new(0o750);
+    expected.set(User(0), ACL_READ | ACL_WRITE);
+    expected.set(Group(0), ACL_READ);
+    expected.set(Mask, ACL_RWX);
+    assert_eq!(acl, expected);
+
+    // Long form with effective rights comments, as produced by as_text()
+    let mut acl = full_fixture();
+    acl.set(Mask, ACL_READ);
+    assert_eq!(acl.as_text().parse::<PosixACL>().unwrap(), acl);
+
+    let err = "u::rwx,g::r-x,o::---,q::r".parse::<PosixACL>().unwrap_err();
+    assert!(matches!(err, ACLError::ParseError(_)));
+}
+
+#[test]
+fn clone() {
+    let acl = full_fixture();
+    let mut copy = acl.clone();
+    assert_eq!(copy, acl);
+    copy.set(User(1), ACL_READ);
+    assert_eq!(acl.get(User(1)), None);
+    assert_eq!(copy.get(User(1)), Some(ACL_READ));
+}
+
+#[test]
+fn delete_default_acl() {
+    let dir = tempdir().unwrap();
+    full_fixture().write_default_acl(dir.path()).unwrap();
+    PosixACL::delete_default_acl(dir.path()).unwrap();
+    assert_eq!(
+        PosixACL::read_default_acl(dir.path()).unwrap(),
+        PosixACL::empty()
+    );
+    // No default ACL is not an error
+    PosixACL::delete_default_acl(dir.path()).unwrap();
+
+    let err = PosixACL::delete_default_acl(dir.path().join("missing")).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    assert_eq!(
+        err.to_string(),
+        "Error deleting default ACL: No such file or directory (os error 2)"
+    );
+    assert_eq!(
+        err.report().operation.as_deref(),
+        Some("deleting default ACL")
+    );
+}
+
+#[test]
+fn has_extended_acl() {
+    let dir = tempdir().unwrap();
+    let path = test_file(&dir, "file", 0o640);
+    let file = std::fs::File::open(&path).unwrap();
+    assert!(!PosixACL::has_extended_acl(&path).unwrap());
+    assert!(!PosixACL::has_extended_acl_fd(&file).unwrap());
+
+    full_fixture().write_acl(&path).unwrap();
+    assert!(PosixACL::has_extended_acl(&path).unwrap());
+    assert!(PosixACL::has_extended_acl_fd(&file).unwrap());
+
+    // Default ACL counts as well
+    assert!(!PosixACL::has_extended_acl(dir.path()).unwrap());
+    full_fixture().write_default_acl(dir.path()).unwrap();
+    assert!(PosixACL::has_extended_acl(dir.path()).unwrap());
+
+    let err = PosixACL::has_extended_acl(dir.path().join("missing")).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+#[test]
+fn from_permissions() {
+    assert_eq!(
+        PosixACL::from(Permissions::from_mode(0o4751)),
+        PosixACL::new(0o751)
+    );
+    let dir = tempdir().unwrap();
+    let path = test_file(&dir, "file", 0o604);
+    let acl = PosixACL::from(metadata(&path).unwrap().permissions());
+    assert_eq!(acl, PosixACL::read_acl(&path).unwrap());
+}
+
+#[test]
+fn len() {
+    assert_eq!(PosixACL::empty().len(), 0);
+    assert!(PosixACL::empty().is_empty());
+    assert_eq!(PosixACL::new(0o640).len(), 3);
+    let acl = full_fixture();
+    assert_eq!(acl.len(), acl.entries().len());
+    assert!(!acl.is_empty());
+}
+
+#[test]
+fn equivalent_mode() {
+    assert_eq!(PosixACL::new(0o751).equivalent_mode(), Some(0o751));
+    assert_eq!(PosixACL::new(0).equivalent_mode(), Some(0));
+    let mut acl = PosixACL::new(0o640);
+    acl.set(Mask, ACL_READ);
+    assert_eq!(acl.equivalent_mode(), None);
+    assert_eq!(full_fixture().equivalent_mode(), None);
+    assert_eq!(PosixACL::empty().equivalent_mode(), Some(0));
+}
+
+#[test]
+fn add_remove_perm() {
+    let mut acl = PosixACL::new(0o640);
+    assert_eq!(
+        acl.add_perm(UserObj, ACL_EXECUTE),
+        Some(ACL_READ | ACL_WRITE)
+    );
+    assert_eq!(acl.get(UserObj), Some(ACL_RWX));
+    assert_eq!(acl.add_perm(User(0), ACL_READ), None);
+    assert_eq!(acl.get(User(0)), Some(ACL_READ));
+
+    assert_eq!(acl.remove_perm(UserObj, ACL_WRITE), Some(ACL_RWX));
+    assert_eq!(acl.get(UserObj), Some(ACL_READ | ACL_EXECUTE));
+    assert_eq!(acl.remove_perm(User(0), ACL_READ), Some(ACL_READ));
+    assert_eq!(acl.get(User(0)), Some(0));
+    assert_eq!(acl.remove_perm(Group(0), ACL_READ), None);
+    assert_eq!(acl.get(Group(0)), None);
+}
+
+#[test]
+fn effective() {
+    let mut acl = full_fixture();
+    acl.set(Mask, ACL_READ);
+    assert_eq!(acl.effective(UserObj), acl.get(UserObj));
+    assert_eq!(
+        acl.effective(User(0)),
+        Some(acl.get(User(0)).unwrap() & ACL_READ)
+    );
+    assert_eq!(
+        acl.effective(Group(0)),
+        Some(acl.get(Group(0)).unwrap() & ACL_READ)
+    );
+    assert_eq!(acl.effective(Other), acl.get(Other));
+    assert_eq!(acl.effective(User(9999)), None);
+
+    for entry in acl.effective_entries() {
+        assert_eq!(Some(entry.perm), acl.effective(entry.qual));
+    }
+
+    // No mask, no limits
+    acl.remove(Mask);
+    assert_eq!(acl.effective_entries(), acl.entries());
+}
+
+#[test]
+fn allows() {
+    // Owner 1, group 2
+    let mut acl = PosixACL::new(0o640);
+    acl.set(User(10), ACL_RWX);
+    acl.set(Group(20), ACL_WRITE);
+    acl.set(Group(21), ACL_READ);
+    acl.set(Mask, ACL_READ | ACL_WRITE);
+    let allows = |uid, gids: &[u32], want| acl.allows(uid, gids, want, 1, 2);
+
+    // Owner entry is not masked, and takes precedence over group
+    assert!(allows(1, &[20], ACL_READ | ACL_WRITE));
+    assert!(!allows(1, &[20], ACL_EXECUTE));
+    // Named user is masked
+    assert!(allows(10, &[], ACL_READ | ACL_WRITE));
+    assert!(!allows(10, &[], ACL_EXECUTE));
+    // Any matching group entry may grant access
+    assert!(allows(3, &[2], ACL_READ));
+    assert!(allows(3, &[20, 21], ACL_READ));
+    assert!(allows(3, &[20, 21], ACL_WRITE));
+    // ...but all permissions have to come from a single entry
+    assert!(!allows(3, &[20, 21], ACL_READ | ACL_WRITE));
+    // Matching group denies, even though Other would allow
+    acl.set(Other, ACL_WRITE);
+    assert!(!acl.allows(3, &[2], ACL_WRITE, 1, 2));
+    assert!(acl.allows(3, &[4], ACL_WRITE, 1, 2));
+}
+
+#[test]
+fn allows_privileged() {
+    let acl = PosixACL::new(0o640);
+    let allows = |want, is_dir, caps| acl.allows_privileged(3, &[3], want, 1, 2, is_dir, caps);
+
+    // Without capabilities, same as allows()
+    assert!(!allows(ACL_READ, false, 0));
+    assert!(!allows(ACL_READ, true, 0));
+    // CAP_DAC_READ_SEARCH: read on files, read and search on directories
+    assert!(allows(ACL_READ, false, CAP_DAC_READ_SEARCH));
+    assert!(!allows(ACL_READ | ACL_EXECUTE, false, CAP_DAC_READ_SEARCH));
+    assert!(allows(ACL_READ | ACL_EXECUTE, true, CAP_DAC_READ_SEARCH));
+    assert!(!allows(ACL_WRITE, true, CAP_DAC_READ_SEARCH));
+    // CAP_DAC_OVERRIDE: anything on directories, execute on files needs an execute bit
+    assert!(allows(ACL_RWX, true, CAP_DAC_OVERRIDE));
+    assert!(allows(ACL_READ | ACL_WRITE, false, CAP_DAC_OVERRIDE));
+    assert!(!allows(ACL_EXECUTE, false, CAP_DAC_OVERRIDE));
+    let mut acl = acl.clone();
+    acl.set(Other, ACL_EXECUTE);
+    assert!(acl.allows_privileged(3, &[3], ACL_RWX, 1, 2, false, CAP_DAC_OVERRIDE));
+    // Execute bit of a named entry only counts if the mask allows it
+    let mut acl = PosixACL::new(0o640);
+    acl.set(User(4), ACL_EXECUTE);
+    acl.set(Mask, ACL_READ);
+    assert!(!acl.allows_privileged(3, &[3], ACL_EXECUTE, 1, 2, false, CAP_DAC_OVERRIDE));
+}
+
+#[test]
+fn explain_access() {
+    // Owner 1, group 2
+    let mut acl = PosixACL::new(0o640);
+    acl.set(User(10), ACL_RWX);
+    acl.set(Group(20), ACL_WRITE);
+    acl.set(Group(21), ACL_READ);
+    acl.set(Mask, ACL_READ | ACL_WRITE);
+    let explain = |uid, gids: &[u32], want| acl.explain_access(uid, gids, want, 1, 2);
+
+    let owner = explain(1, &[20], ACL_READ | ACL_WRITE);
+    assert!(owner.allowed);
+    assert_eq!(owner.class, AccessClass::Owner);
+    assert_eq!(owner.mask, None);
+    assert_eq!(owner.to_string(), "access granted by user::rw-");
+
+    let user = explain(10, &[], ACL_EXECUTE);
+    assert!(!user.allowed);
+    assert_eq!(user.class, AccessClass::User);
+    assert!(user.masked());
+    assert_eq!(
+        user.to_string(),
+        "access denied by user:10:rwx limited by mask::rw-"
+    );
+
+    let group = explain(3, &[2, 20, 21], ACL_READ | ACL_WRITE);
+    assert!(!group.allowed);
+    assert_eq!(group.class, AccessClass::Group);
+    assert_eq!(group.matched.len(), 3);
+    assert!(!group.masked());
+    assert_eq!(
+        group.to_string(),
+        "access denied by group::r--, group:20:-w-, group:21:r-- limited by mask::rw-"
+    );
+
+    let other = explain(3, &[4], ACL_READ);
+    assert!(!other.allowed);
+    assert_eq!(other.class, AccessClass::Other);
+    assert_eq!(
+        other.to_string(),
+        "access denied by other::--- (no user or group entry matches)"
+    );
+
+    // Same decisions as allows()
+    for uid in [1, 3, 10] {
+        for gids in [&[][..], &[2], &[20, 21], &[4]] {
+            for want in 0..=ACL_RWX {
+                assert_eq!(
+                    explain(uid, gids, want).allowed,
+                    acl.allows(uid, gids, want, 1, 2)
+                );
+            }
+        }
+    }
+
+    let missing = PosixACL::empty().explain_access(1, &[], ACL_READ, 1, 2);
+    assert!(!missing.allowed);
+    assert_eq!(missing.to_string(), "access denied by missing user:: entry");
+}
+
+#[test]
+fn access_report() {
+    let mut acl = full_fixture();
+    acl.set(User(1000), ACL_RWX);
+    acl.set(Mask, ACL_READ);
+    assert_eq!(
+        acl.access_report(Some(1000), Some(100)),
+        vec![
+            (Principal::Owner(Some(1000)), ACL_READ | ACL_WRITE),
+            (Principal::User(0), ACL_READ),
+            (Principal::User(55555), 0),
+            (Principal::OwningGroup(Some(100)), ACL_READ),
+            (Principal::Group(0), ACL_READ),
+            (Principal::Group(55555), 0),
+            (Principal::Other, 0),
+        ]
+    );
+    let report = acl.access_report(None, None);
+    assert_eq!(report[0], (Principal::Owner(None), ACL_READ | ACL_WRITE));
+    assert!(report.contains(&(Principal::User(1000), ACL_READ)));
+}
+
+#[test]
+fn copy_acl() {
+    let src = tempdir().unwrap();
+    let dst = tempdir().unwrap();
+    let mut acl = full_fixture();
+    acl.write_acl(src.path()).unwrap();
+    acl.write_default_acl(src.path()).unwrap();
+
+    posix_acl::copy_acl(src.path(), dst.path()).unwrap();
+    assert_eq!(PosixACL::read_acl(dst.path()).unwrap(), acl);
+    assert_eq!(PosixACL::read_default_acl(dst.path()).unwrap(), acl);
+
+    // Default ACL of destination is removed, file gets only access ACL
+    PosixACL::delete_default_acl(src.path()).unwrap();
+    posix_acl::copy_acl(src.path(), dst.path()).unwrap();
+    assert!(PosixACL::read_default_acl(dst.path()).unwrap().is_empty());
+
+    let file = dst.path().join("file");
+    std::fs::File::create(&file).unwrap();
+    posix_acl::copy_acl(src.path(), &file).unwrap();
+    assert_eq!(PosixACL::read_acl(&file).unwrap(), acl);
+}
+
+#[test]
+fn diff() {
+    let old = full_fixture();
+    assert!(old.diff(&old.clone()).is_empty());
+
+    let mut new = old.clone();
+    new.remove(User(55555));
+    new.set(Group(55555), ACL_READ);
+    new.set(User(1), ACL_RWX);
+    let diff = old.diff(&new);
+    assert_eq!(
+        diff.added,
+        vec![ACLEntry {
+            qual: User(1),
+            perm: ACL_RWX
+        }]
+    );
+    assert_eq!(
+        diff.removed,
+        vec![ACLEntry {
+            qual: User(55555),
+            perm: 0
+        }]
+    );
+    assert_eq!(diff.changed, vec![(Group(55555), 0, ACL_READ)]);
+    assert!(!diff.is_empty());
+
+    // Reverse direction
+    let diff = new.diff(&old);
+    assert_eq!(diff.added[0].qual, User(55555));
+    assert_eq!(diff.removed[0].qual, User(1));
+    assert_eq!(diff.changed, vec![(Group(55555), ACL_READ, 0)]);
+}
+
+#[test]
+fn modify_from_text() {
+    let mut acl = PosixACL::new(0o640);
+    acl.modify_from_text("u:daemon:rw, g:root:r\nuser:55555:7,m::rwx,o:-")
+        .unwrap();
+    assert_eq!(acl.get(User(1)), Some(ACL_READ | ACL_WRITE));
+    assert_eq!(acl.get(Group(0)), Some(ACL_READ));
+    assert_eq!(acl.get(User(55555)), Some(ACL_RWX));
+    assert_eq!(acl.get(Mask), Some(ACL_RWX));
+    assert_eq!(acl.get(Other), Some(0));
+    // Unchanged
+    assert_eq!(acl.get(UserObj), Some(ACL_READ | ACL_WRITE));
+
+    // X only grants execute if some entry has it
+    acl.modify_from_text("g::rX").unwrap();
+    assert_eq!(acl.get(GroupObj), Some(ACL_READ | ACL_EXECUTE));
+    let mut acl = PosixACL::new(0o640);
+    acl.modify_from_text("g::rX").unwrap();
+    assert_eq!(acl.get(GroupObj), Some(ACL_READ));
+}
+
+#[test]
+fn modify_from_text_invalid() {
+    let mut acl = PosixACL::new(0o640);
+    for spec in [
+        "u:daemon",
+        "u:daemon:rwz",
+        "u:daemon:",
+        "x::r",
+        "d:u:daemon:r",
+        "m:root:r",
+        "u::r,o",
+    ] {
+        let err = acl.modify_from_text(spec).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("Error parsing ACL: Invalid ACL entry"),
+            "{}: {}",
+            spec,
+            err
+        );
+    }
+    let err = acl.modify_from_text("u:nonexistent-user:r").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Error parsing ACL: Unknown user \"nonexistent-user\""
+    );
+    // Not modified on error
+    assert_eq!(acl, PosixACL::new(0o640));
+}
+
+#[test]
+fn remove_from_text() {
+    let mut acl = full_fixture();
+    acl.remove_from_text("u:root:rw user:55555,g:0,m").unwrap();
+    assert_eq!(acl.get(User(0)), None);
+    assert_eq!(acl.get(User(55555)), None);
+    assert_eq!(acl.get(Group(0)), None);
+    assert_eq!(acl.get(Mask), None);
+    assert_eq!(acl.get(Group(55555)), Some(0));
+    // Missing entries are skipped
+    acl.remove_from_text("u:daemon").unwrap();
+
+    let expected = acl.clone();
+    let err = acl.remove_from_text("g:55555,g::").unwrap_err();
+    assert_eq!(
+        err.validation_reason(),
+        Some(ValidationReason::MissingEntry)
+    );
+    assert!(matches!(
+        acl.remove_from_text("g:55555,u").unwrap_err(),
+        ACLError::ParseError(_)
+    ));
+    assert_eq!(acl, expected);
+}
+
+#[test]
+fn dump_parse() {
+    let text = "\
+# file: srv/my\\040dir\\\\x
+# owner: root
+# group: daemon
+# flags: -s-
+user::rwx
+user:bin:rwx\t\t#effective:r-x
+group::r-x
+mask::r-x
+other::---
+default:user::rwx
+default:group::r-x
+default:other::---
+
+# file: file
+user::rw-
+group::r--
+other::r--
+";
+    let entries: Vec<DumpEntry> = DumpReader::new(text.as_bytes())
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].path, Path::new("srv/my dir\\x"));
+    assert_eq!(entries[0].owner.as_deref(), Some("root"));
+    assert_eq!(entries[0].group.as_deref(), Some("daemon"));
+    assert_eq!(entries[0].flags.as_deref(), Some("-s-"));
+    assert_eq!(entries[0].access.get(User(2)), Some(ACL_RWX));
+    assert_eq!(entries[0].access.get(Mask), Some(ACL_READ | ACL_EXECUTE));
+    let default = entries[0].default.as_ref().unwrap();
+    assert_eq!(default.reported_mode(), 0o750);
+    assert_eq!(entries[1].path, Path::new("file"));
+    assert_eq!(entries[1].owner, None);
+    assert_eq!(entries[1].access, PosixACL::new(0o644));
+    assert!(entries[1].default.is_none());
+
+    // Round-trip through write_to()
+    let mut out = Vec::new();
+    for entry in &entries {
+        entry.write_to(&mut out).unwrap();
+    }
+    let reparsed: Vec<DumpEntry> = DumpReader::new(&out[..]).collect::<Result<_, _>>().unwrap();
+    assert_eq!(reparsed, entries);
+}
+
+#[test]
+fn dump_annotations() {
+    let text = "\
+# file: srv/data
+user::rwx
+user:bin:rwx\t#effective:r-x
+user:55555:rw-  # granted for ticket OPS-1234
+group::r-x
+mask::r-x
+other::---#nobody else
+default:user::rwx
+default:user:55555:r-x # inherited by new files
+default:group::r-x
+default:other::---
+";
+    let entry = DumpReader::new(text.as_bytes()).next().unwrap().unwrap();
+    assert_eq!(entry.annotations.len(), 2);
+    assert_eq!(
+        entry.annotations[&User(55555)],
+        "granted for ticket OPS-1234"
+    );
+    assert_eq!(entry.annotations[&Other], "nobody else");
+    assert_eq!(entry.default_annotations.len(), 1);
+    assert_eq!(
+        entry.default_annotations[&User(55555)],
+        "inherited by new files"
+    );
+
+    let text = entry.to_string();
+    assert!(text.contains("\nuser:55555:rw-  # granted for ticket OPS-1234\n"));
+    assert!(text.contains("\ndefault:user:55555:r-x  # inherited by new files\n"));
+    let reparsed = DumpReader::new(text.as_bytes()).next().unwrap().unwrap();
+    assert_eq!(reparsed, entry);
+
+    // Line breaks would end the entry
+    let mut entry = entry;
+    entry.annotations.insert(Mask, "two\nlines".to_string());
+    assert!(entry.to_string().contains("\nmask::r-x  # two lines\n"));
+
+    // Not stored in the filesystem
+    let dir = tempdir().unwrap();
+    assert!(DumpEntry::read(dir.path()).unwrap().annotations.is_empty());
+}
+
+#[test]
+fn dump_invalid() {
+    let err = |text: &str| {
+        let mut reader = DumpReader::new(text.as_bytes());
+        let err = reader.next().unwrap().unwrap_err().to_string();
+        assert!(reader.next().is_none());
+        err
+    };
+    assert_eq!(
+        err("\n\nuser::rw-\n"),
+        "Error parsing ACL: Missing file name in dump at line 3"
+    );
+    assert_eq!(
+        err("# file: a\n"),
+        "Error parsing ACL: Missing ACL entries in dump at line 1"
+    );
+    assert_eq!(
+        err("# file: a\nuser::rw-\nbogus\n"),
+        "Error parsing ACL: Invalid ACL in dump at line 1"
+    );
+    assert_eq!(
+        err("# file: a\\0\nuser::rw-\n"),
+        "Error parsing ACL: Invalid file name in dump at line 1"
+    );
+}
+
+#[test]
+fn dump_name_map() {
+    let text = "# file: a\nuser::rw-\nuser:alice:r--\nuser:bin:r--\nuser:7:r--\n\
+                group::r--\ngroup:staff:rw-\nmask::rw-\nother::---\n\
+                default:user::rwx\ndefault:u:alice:rwx\ndefault:group::r-x\n\
+                default:mask::rwx\ndefault:other::---\n";
+    let names = NameMap::new().user("alice", 1000).group("staff", 50);
+    let read = |names: NameMap| {
+        DumpReader::new(text.as_bytes())
+            .name_map(names)
+            .next()
+            .unwrap()
+    };
+
+    // Unmapped names are resolved locally by default
+    let entry = read(names.clone()).unwrap();
+    assert_eq!(entry.access.get(User(1000)), Some(ACL_READ));
+    assert_eq!(entry.access.get(User(2)), Some(ACL_READ));
+    assert_eq!(entry.access.get(User(7)), Some(ACL_READ));
+    assert_eq!(entry.access.get(Group(50)), Some(ACL_READ | ACL_WRITE));
+    let default = entry.default.unwrap();
+    assert_eq!(default.get(User(1000)), Some(ACL_RWX));
+
+    let entry = read(names.clone().unmapped(UnmappedNames::Skip)).unwrap();
+    assert_eq!(entry.access.get(User(2)), None);
+    assert_eq!(entry.access.get(User(7)), Some(ACL_READ));
+
+    let err = read(names.unmapped(UnmappedNames::Error)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Error parsing ACL: Unmapped user name \"bin\" in dump at line 1"
+    );
+    // Without a map, alice does not exist locally
+    assert!(DumpReader::new(text.as_bytes()).next().unwrap().is_err());
+}
+
+#[test]
+fn dump_restore() {
+    let dir = tempdir().unwrap();
+    let mut acl = full_fixture();
+    acl.write_acl(dir.path()).unwrap();
+    acl.write_default_acl(dir.path()).unwrap();
+
+    let entry = DumpEntry::read(dir.path()).unwrap();
+    assert_eq!(entry.access, acl);
+    assert_eq!(entry.default, Some(acl));
+    assert_eq!(entry.flags, None);
+
+    PosixACL::new(0o700).write_acl(dir.path()).unwrap();
+    PosixACL::delete_default_acl(dir.path()).unwrap();
+    entry.restore().unwrap();
+    assert_eq!(DumpEntry::read(dir.path()).unwrap(), entry);
+
+    // Default ACL is removed
+    let entry = DumpEntry {
+        default: None,
+        ..entry
+    };
+    entry.restore().unwrap();
+    assert!(PosixACL::read_default_acl(dir.path()).unwrap().is_empty());
+}
+
+#[test]
+fn memory_backend() {
+    let backend = MemoryBackend::new();
+    let (file, dir) = (Path::new("/srv/file"), Path::new("/srv/dir"));
+    backend.add_file(file, 0o640);
+    backend.add_dir(dir, 0o750);
+
+    let err = backend.read_acl(Path::new("/nonexistent")).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    assert_eq!(
+        err.to_string(),
+        "Error reading ACL: No such file or directory (os error 2)"
+    );
+
+    let mut acl = full_fixture();
+    acl.remove(Mask);
+    backend.write_acl(file, &mut acl).unwrap();
+    assert_eq!(backend.read_acl(file).unwrap(), full_fixture());
+    assert!(backend.read_default_acl(file).unwrap().is_empty());
+    let err = backend.write_default_acl(file, &mut acl).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    let mut invalid = PosixACL::empty();
+    assert!(matches!(
+        backend.write_acl(file, &mut invalid).unwrap_err(),
+        ACLError::ValidationError(_)
+    ));
+
+    backend.write_default_acl(dir, &mut acl).unwrap();
+    assert_eq!(backend.read_default_acl(dir).unwrap(), full_fixture());
+    backend.delete_default_acl(dir).unwrap();
+    assert!(backend.read_default_acl(dir).unwrap().is_empty());
+    assert_eq!(backend.read_acl(dir).unwrap(), PosixACL::new(0o750));
+}
+
+#[test]
+fn fs_backend() {
+    let dir = tempdir().unwrap();
+    let backend: &dyn ACLBackend = &FsBackend;
+    let mut acl = full_fixture();
+    backend.write_default_acl(dir.path(), &mut acl).unwrap();
+    assert_eq!(backend.read_default_acl(dir.path()).unwrap(), acl);
+    backend.delete_default_acl(dir.path()).unwrap();
+    assert!(backend.read_default_acl(dir.path()).unwrap().is_empty());
+}
+
+#[test]
+fn pax() {
+    let acl = full_fixture();
+    let text = "user::rw-,user:root:rw-:0,user:55555:---:55555,group::r--,group:root:r--:0,\
+                group:55555:---:55555,mask::rw-,other::---";
+    assert_eq!(acl.to_pax(), text);
+    assert_eq!(PosixACL::from_pax(text).unwrap(), acl);
+    assert_eq!(PosixACL::from_pax(text).unwrap().to_pax(), text);
+
+    // Numeric ID takes precedence over name, GNU tar format without IDs
+    let acl = PosixACL::from_pax("user::rwx,user:root:r--:1,user:bin:r--,group::---,other::---\n")
+        .unwrap();
+    assert_eq!(acl.get(User(1)), Some(ACL_READ));
+    assert_eq!(acl.get(User(2)), Some(ACL_READ));
+    assert_eq!(acl.get(User(0)), None);
+
+    assert!(PosixACL::from_pax("").unwrap().is_empty());
+    for text in [
+        "user::rw",
+        "user::rwz",
+        "user::r-x:1",
+        "user:root:r--:x",
+        "user::r--,user::rw-",
+        "foo::r--",
+    ] {
+        assert!(matches!(
+            PosixACL::from_pax(text).unwrap_err(),
+            ACLError::ParseError(_)
+        ));
+    }
+}
+
+#[test]
+fn nofollow() {
+    let dir = tempdir().unwrap();
+    let path = test_file(&dir, "file", 0o640);
+    let link = dir.path().join("link");
+    std::os::unix::fs::symlink(&path, &link).unwrap();
+
+    let mut acl = full_fixture();
+    acl.write_acl_nofollow(&path).unwrap();
+    assert_eq!(PosixACL::read_acl_nofollow(&path).unwrap(), acl);
+    acl.write_default_acl_nofollow(dir.path()).unwrap();
+    assert_eq!(
+        PosixACL::read_default_acl_nofollow(dir.path()).unwrap(),
+        acl
+    );
+
+    for err in [
+        PosixACL::read_acl_nofollow(&link).unwrap_err(),
+        PosixACL::read_default_acl_nofollow(&link).unwrap_err(),
+        PosixACL::new(0o600).write_acl_nofollow(&link).unwrap_err(),
+    ] {
+        assert_eq!(err.as_io_error().unwrap().raw_os_error(), Some(libc::ELOOP));
+    }
+    assert_eq!(
+        PosixACL::new(0o600)
+            .write_acl_nofollow(&link)
+            .unwrap_err()
+            .to_string(),
+        "Error writing ACL: Too many levels of symbolic links (os error 40)"
+    );
+    // Target is unchanged
+    assert_eq!(PosixACL::read_acl(&path).unwrap(), acl);
+    assert!(PosixACL::read_acl_nofollow(dir.path().join("missing")).is_err());
+}
+
+#[test]
+fn acl_at() {
+    let dir = tempdir().unwrap();
+    test_file(&dir, "file", 0o640);
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    std::os::unix::fs::symlink("file", dir.path().join("link")).unwrap();
+    let handle = std::fs::File::open(dir.path()).unwrap();
+
+    let mut acl = full_fixture();
+    acl.write_acl_at(&handle, "file").unwrap();
+    assert_eq!(PosixACL::read_acl_at(&handle, "file").unwrap(), acl);
+    assert_eq!(PosixACL::read_acl(dir.path().join("file")).unwrap(), acl);
+    acl.write_default_acl_at(&handle, "sub").unwrap();
+    assert_eq!(PosixACL::read_default_acl_at(&handle, "sub").unwrap(), acl);
+    // Absolute path ignores the directory
+    let abs = dir.path().join("sub");
+    assert_eq!(PosixACL::read_default_acl_at(&handle, abs).unwrap(), acl);
+
+    let err = PosixACL::read_acl_at(&handle, "link").unwrap_err();
+    assert_eq!(err.as_io_error().unwrap().raw_os_error(), Some(libc::ELOOP));
+    let err = PosixACL::read_acl_at(&handle, "missing").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+#[test]
+fn file_acls() {
+    let dir = tempdir().unwrap();
+    let file = test_file(&dir, "file", 0o640);
+
+    let mut acls = FileACLs::read_all(&file).unwrap();
+    assert_eq!(acls.access, PosixACL::new(0o640));
+    assert_eq!(acls.default, None);
+    acls.access = full_fixture();
+    acls.write_all(&file).unwrap();
+    assert_eq!(PosixACL::read_acl(&file).unwrap(), full_fixture());
+
+    let mut acls = FileACLs::read_all(dir.path()).unwrap();
+    assert!(acls.default.as_ref().unwrap().is_empty());
+    acls.default = Some(full_fixture());
+    acls.write_all(dir.path()).unwrap();
+    assert_eq!(FileACLs::read_all(dir.path()).unwrap(), acls);
+
+    // Empty default ACL removes it
+    acls.default = Some(PosixACL::empty());
+    acls.write_all(dir.path()).unwrap();
+    assert!(PosixACL::read_default_acl(dir.path()).unwrap().is_empty());
+}
+
+#[test]
+fn strip_extended() {
+    let mut acl = full_fixture();
+    let removed = acl.strip_extended();
+    let quals: Vec<_> = removed.iter().map(|entry| entry.qual).collect();
+    assert_eq!(
+        quals,
+        vec![User(0), User(55555), Group(0), Group(55555), Mask]
+    );
+    assert_eq!(removed[0].perm, ACL_READ | ACL_WRITE);
+    assert_eq!(acl, PosixACL::new(0o640));
+    assert!(acl.validate().is_ok());
+
+    assert!(acl.strip_extended().is_empty());
+}
+
+#[test]
+fn delete_default_acl_recursive() {
+    let dir = tempdir().unwrap();
+    let mut acl = full_fixture();
+    let subdirs = ["a", "a/b", "c"].map(|name| dir.path().join(name));
+    for path in &subdirs {
+        std::fs::create_dir(path).unwrap();
+    }
+    acl.write_default_acl(dir.path()).unwrap();
+    for path in &subdirs {
+        acl.write_default_acl(path).unwrap();
+    }
+    test_file(&dir, "a/file", 0o640);
+
+    // Symlinked directory outside the tree is left alone
+    let outside = tempdir().unwrap();
+    acl.write_default_acl(outside.path()).unwrap();
+    std::os::unix::fs::symlink(outside.path(), dir.path().join("link")).unwrap();
+
+    let report = PosixACL::delete_default_acl_recursive(dir.path());
+    assert!(report.is_ok(), "{:?}", report.errors);
+    assert_eq!(report.processed, 4);
+    assert!(PosixACL::read_default_acl(dir.path()).unwrap().is_empty());
+    for path in &subdirs {
+        assert!(PosixACL::read_default_acl(path).unwrap().is_empty());
+    }
+    assert_eq!(PosixACL::read_default_acl(outside.path()).unwrap(), acl);
+
+    let report = PosixACL::delete_default_acl_recursive(dir.path().join("missing"));
+    assert_eq!(report.processed, 0);
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].1.kind(), ErrorKind::NotFound);
+}
+
+#[test]
+fn symbolic_mode_cond_execute() {
+    let mut acl = PosixACL::new(0o640);
+    acl.apply_symbolic_mode("a+X").unwrap();
+    assert_eq!(acl.reported_mode(), 0o640);
+    acl.apply_symbolic_mode("u+x,go+X").unwrap();
+    assert_eq!(acl.reported_mode(), 0o751);
+    acl.apply_symbolic_mode("a-X").unwrap();
+    assert_eq!(acl.reported_mode(), 0o640);
+}
+
+#[test]
+fn modify_recursive() {
+    let dir = tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    let data = test_file(&dir, "sub/data", 0o640);
+    let script = test_file(&dir, "sub/script", 0o750);
+    std::os::unix::fs::symlink(&data, dir.path().join("link")).unwrap();
+
+    let report = PosixACL::modify_recursive(dir.path(), "u:55555:rX").unwrap();
+    assert!(report.is_ok(), "{:?}", report.errors);
+    assert_eq!(report.processed, 4);
+    let perm = |path: &Path| PosixACL::read_acl(path).unwrap().get(User(55555));
+    assert_eq!(perm(dir.path()), Some(ACL_READ | ACL_EXECUTE));
+    assert_eq!(perm(&dir.path().join("sub")), Some(ACL_READ | ACL_EXECUTE));
+    assert_eq!(perm(&data), Some(ACL_READ));
+    assert_eq!(perm(&script), Some(ACL_READ | ACL_EXECUTE));
+
+    assert!(matches!(
+        PosixACL::modify_recursive(dir.path(), "u:55555:z").unwrap_err(),
+        ACLError::ParseError(_)
+    ));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel_walk() {
+    use posix_acl::ParallelWalk;
+
+    let dir = tempdir().unwrap();
+    let mut files = Vec::new();
+    for i in 0..4 {
+        let sub = dir.path().join(format!("dir{i}"));
+        std::fs::create_dir(&sub).unwrap();
+        full_fixture().write_default_acl(&sub).unwrap();
+        for j in 0..8 {
+            files.push(test_file(&dir, &format!("dir{i}/file{j}"), 0o640));
+        }
+    }
+    let walk = ParallelWalk::new(4).unwrap();
+
+    let report = walk.modify(dir.path(), "u:55555:rX").unwrap();
+    assert!(report.is_ok(), "{:?}", report.errors);
+    assert_eq!(report.processed, 37);
+    for path in &files {
+        assert_eq!(
+            PosixACL::read_acl(path).unwrap().get(User(55555)),
+            Some(ACL_READ)
+        );
+    }
+
+    let report = walk.remap_ids(dir.path(), |qual| match qual {
+        User(55555) => User(55556),
+        other => other,
+    });
+    assert!(report.is_ok(), "{:?}", report.errors);
+    assert_eq!(report.processed, 37);
+    let acl = PosixACL::read_acl(&files[31]).unwrap();
+    assert_eq!(acl.get(User(55555)), None);
+    assert_eq!(acl.get(User(55556)), Some(ACL_READ));
+    let default = PosixACL::read_default_acl(dir.path().join("dir3")).unwrap();
+    assert_eq!(default.get(User(55556)), Some(0));
+
+    let report = walk.delete_default_acl(dir.path());
+    assert!(report.is_ok(), "{:?}", report.errors);
+    assert_eq!(report.processed, 5);
+    assert!(PosixACL::read_default_acl(dir.path().join("dir0"))
+        .unwrap()
+        .is_empty());
+
+    let report = walk.delete_default_acl(dir.path().join("missing"));
+    assert_eq!(report.processed, 0);
+    assert_eq!(report.errors[0].1.kind(), ErrorKind::NotFound);
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn async_ops() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let dir = tempdir().unwrap();
+    let path = test_file(&dir, "file", 0o640);
+
+    runtime.block_on(async {
+        let mut acl = PosixACL::new(0o640);
+        acl.set(User(55555), ACL_READ | ACL_WRITE);
+        acl.write_acl_async(&path).await.unwrap();
+        // Mask is recalculated like with write_acl()
+        assert_eq!(acl.get(Mask), Some(ACL_READ | ACL_WRITE));
+        assert_eq!(PosixACL::read_acl_async(&path).await.unwrap(), acl);
+
+        acl.write_default_acl_async(dir.path()).await.unwrap();
+        let default = PosixACL::read_default_acl_async(dir.path()).await.unwrap();
+        assert_eq!(default, acl);
+
+        let err = PosixACL::read_acl_async(dir.path().join("missing"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+
+        let report = PosixACL::modify_recursive_async(dir.path(), "u:55556:r")
+            .await
+            .unwrap();
+        assert_eq!(report.processed, 2);
+        let report = PosixACL::remap_ids_recursive_async(dir.path(), |qual| match qual {
+            User(55556) => User(55557),
+            other => other,
+        })
+        .await;
+        assert!(report.is_ok(), "{:?}", report.errors);
+        assert_eq!(
+            PosixACL::read_acl(&path).unwrap().get(User(55557)),
+            Some(ACL_READ)
+        );
+        let report = PosixACL::delete_default_acl_recursive_async(dir.path()).await;
+        assert_eq!(report.processed, 1);
+    });
+    assert!(PosixACL::read_default_acl(dir.path()).unwrap().is_empty());
+}
+
+#[test]
+fn canonicalize() {
+    let mut acl = PosixACL::empty();
+    acl.set(Other, 0);
+    acl.set(Mask, ACL_READ);
+    acl.set(Group(2), ACL_READ);
+    acl.set(User(2), ACL_READ);
+    acl.set(GroupObj, ACL_READ);
+    acl.set(User(1), ACL_WRITE);
+    acl.set(UserObj, ACL_RWX);
+    acl.canonicalize();
+
+    let order: Vec<_> = acl.entries().iter().map(|entry| entry.qual).collect();
+    assert_eq!(
+        order,
+        [UserObj, User(1), User(2), GroupObj, Group(2), Mask, Other]
+    );
+    assert_eq!(acl.get(User(1)), Some(ACL_WRITE));
+    acl.validate().unwrap();
+
+    let mut full = full_fixture();
+    let expected = full.entries();
+    full.canonicalize();
+    assert_eq!(full.entries(), expected);
+}
+
+#[test]
+fn entries_sorted() {
+    let mut acl = PosixACL::empty();
+    acl.set(Other, 0);
+    acl.set(Group(7), ACL_READ);
+    acl.set(Group(3), ACL_WRITE);
+    acl.set(GroupObj, ACL_READ);
+    acl.set(User(9), ACL_READ);
+    acl.set(UserObj, ACL_RWX);
+
+    let order: Vec<_> = acl
+        .entries_sorted()
+        .iter()
+        .map(|entry| entry.qual)
+        .collect();
+    assert_eq!(
+        order,
+        [UserObj, User(9), GroupObj, Group(3), Group(7), Other]
+    );
+    assert_eq!(acl.entries_sorted()[3].perm, ACL_WRITE);
+    assert_eq!(full_fixture().entries_sorted(), full_fixture().entries());
+}
+
+#[test]
+fn iter() {
+    let acl = full_fixture();
+    assert_eq!(acl.iter().collect::<Vec<_>>(), acl.entries());
+    assert_eq!(acl.iter().count(), acl.len());
+
+    // Parallel iterators don't disturb each other
+    let mut count = 0;
+    for (entry1, entry2) in acl.iter().zip(&acl) {
+        assert_eq!(entry1, entry2);
+        assert_eq!(acl.get(entry1.qual), Some(entry1.perm));
+        count += 1;
+    }
+    assert_eq!(count, acl.len());
+}
+
+#[test]
+fn from_iter_extend() {
+    let full = full_fixture();
+    let copy: PosixACL = full.iter().collect();
+    assert_eq!(copy, full);
+
+    let named: PosixACL = full
+        .iter()
+        .filter(|entry| matches!(entry.qual, User(_) | Group(_)))
+        .map(|entry| (entry.qual, entry.perm))
+        .collect();
+    assert_eq!(named.len(), 4);
+
+    let mut acl = PosixACL::new(0o640);
+    acl.extend(named.entries());
+    acl.extend(vec![(User(0), ACL_READ), (Other, ACL_READ)]);
+    assert_eq!(acl.get(User(0)), Some(ACL_READ));
+    assert_eq!(acl.get(Group(55555)), Some(0));
+    assert_eq!(acl.get(Other), Some(ACL_READ));
+    assert_eq!(acl.len(), 7);
+}
+
+#[test]
+fn map_conversions() {
+    let full = full_fixture();
+    let map = BTreeMap::from(full.clone());
+    assert_eq!(map.len(), full.len());
+    assert_eq!(map.keys().next(), Some(&UserObj));
+    assert_eq!(map[&User(55555)], 0);
+    assert_eq!(PosixACL::try_from(map).unwrap(), full);
+
+    let mut map = HashMap::from(full.clone());
+    assert_eq!(map.get(&Group(0)), Some(&ACL_READ));
+    map.remove(&Mask);
+    let err = PosixACL::try_from(map.clone()).unwrap_err();
+    assert_eq!(
+        err.validation_reason(),
+        Some(ValidationReason::MissingEntry)
+    );
+
+    map.insert(Mask, ACL_RWX);
+    map.insert(Unknown(0x100), ACL_READ);
+    let err = PosixACL::try_from(map).unwrap_err();
+    assert_eq!(
+        err.validation_reason(),
+        Some(ValidationReason::InvalidEntry)
+    );
+}
+
+#[test]
+fn qualifier_order() {
+    let mut quals = vec![
+        Other,
+        Unknown(5),
+        Mask,
+        Group(1),
+        Undefined,
+        GroupObj,
+        User(2),
+        User(1),
+        UserObj,
+    ];
+    quals.sort();
+    assert_eq!(
+        quals,
+        [
+            UserObj,
+            User(1),
+            User(2),
+            GroupObj,
+            Group(1),
+            Mask,
+            Other,
+            Undefined,
+            Unknown(5)
+        ]
+    );
+}
+
+#[test]
+fn index() {
+    let acl = full_fixture();
+    assert_eq!(acl[UserObj], ACL_READ | ACL_WRITE);
+    assert_eq!(acl[Group(0)], ACL_READ);
+    assert_eq!(acl[User(55555)], 0);
+    for entry in &acl {
+        assert_eq!(acl[entry.qual], entry.perm);
+    }
+}
+
+#[test]
+#[should_panic(expected = "ACL has no User(1234) entry")]
+fn index_missing() {
+    let acl = PosixACL::new(0o640);
+    let _ = acl[User(1234)];
+}
+
+#[test]
+fn retain() {
+    let mut acl = full_fixture();
+    acl.retain(|qual, perm| perm != 0 || qual == Other);
+    assert_eq!(acl.get(User(55555)), None);
+    assert_eq!(acl.get(Group(55555)), None);
+    assert_eq!(acl.len(), 6);
+
+    let mut seen = Vec::new();
+    acl.retain(|qual, _| {
+        seen.push(qual);
+        !matches!(qual, User(_))
+    });
+    assert_eq!(seen.len(), 6);
+    assert_eq!(acl.get(User(0)), None);
+    assert_eq!(acl.get(Group(0)), Some(ACL_READ));
+}
+
+#[test]
+fn clear_reset() {
+    let mut acl = full_fixture();
+    acl.clear();
+    assert!(acl.is_empty());
+    assert_eq!(acl.entries(), []);
+
+    let mut acl = full_fixture();
+    for mode in [0o751, 0o640, 0o000, 0o777, 0o4755] {
+        acl.reset(mode);
+        assert_eq!(acl.as_text(), PosixACL::new(mode).as_text());
+    }
+    acl.validate().unwrap();
+}
+
+#[test]
+fn contains() {
+    let acl = full_fixture();
+    for entry in &acl {
+        assert!(acl.contains(entry.qual));
+    }
+    assert!(!acl.contains(User(1)));
+    assert!(!acl.contains(Group(1)));
+    assert!(!acl.contains(Undefined));
+    assert!(!PosixACL::new(0o640).contains(Mask));
+}
+
+#[test]
+fn equality_insertion_order() {
+    let mut acl1 = PosixACL::empty();
+    acl1.set(User(1), ACL_READ);
+    acl1.set(UserObj, ACL_RWX);
+    acl1.set(Group(2), ACL_WRITE);
+    let mut acl2 = PosixACL::empty();
+    acl2.set(Group(2), ACL_WRITE);
+    acl2.set(UserObj, ACL_RWX);
+    acl2.set(User(1), ACL_READ);
+    assert_eq!(acl1, acl2);
+
+    acl2.set(User(1), ACL_WRITE);
+    assert_ne!(acl1, acl2);
+    assert_eq!(PosixACL::empty(), PosixACL::empty());
+    assert_ne!(PosixACL::empty(), PosixACL::new(0o640));
+    assert_eq!(acl1, acl1.clone());
+}
+
+#[test]
+fn remap_ids() {
+    let mut acl = full_fixture();
+    acl.set(User(1), ACL_EXECUTE);
+    // Swap users 0 and 1
+    let changed = acl.remap_ids(|qual| match qual {
+        User(0) => User(1),
+        User(1) => User(0),
+        other => other,
+    });
+    assert!(changed);
+    assert_eq!(acl.get(User(0)), Some(ACL_EXECUTE));
+    assert_eq!(acl.get(User(1)), Some(ACL_READ | ACL_WRITE));
+    assert_eq!(acl.get(Group(0)), Some(ACL_READ));
+
+    assert!(!acl.remap_ids(|qual| qual));
+}
+
+/// A mapped entry replaces an unchanged entry with the same qualifier.
+#[test]
+fn remap_ids_collision() {
+    let mut acl = full_fixture();
+    acl.set(User(1), ACL_EXECUTE);
+    acl.set(User(2), ACL_READ);
+    let changed = acl.remap_ids(|qual| match qual {
+        User(1) => User(2),
+        other => other,
+    });
+    assert!(changed);
+    assert_eq!(acl.get(User(1)), None);
+    assert_eq!(acl.get(User(2)), Some(ACL_EXECUTE));
+
+    // Entries mapped to the same qualifier: the last in entries() order wins.
+    let changed = acl.remap_ids(|qual| match qual {
+        User(0) | User(2) => User(3),
+        other => other,
+    });
+    assert!(changed);
+    assert_eq!(acl.get(User(0)), None);
+    assert_eq!(acl.get(User(2)), None);
+    assert_eq!(acl.get(User(3)), Some(ACL_EXECUTE));
+}
+
+#[test]
+fn remap_ids_recursive() {
+    let dir = tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    let file = test_file(&dir, "sub/file", 0o640);
+    let mut acl = full_fixture();
+    acl.write_acl(&file).unwrap();
+    acl.write_default_acl(dir.path()).unwrap();
+
+    let shift = |qual| match qual {
+        User(55555) => User(55556),
+        Group(0) => Group(55557),
+        other => other,
+    };
+    let report = PosixACL::remap_ids_recursive(dir.path(), shift);
+    assert!(report.is_ok(), "{:?}", report.errors);
+    assert_eq!(report.processed, 3);
+
+    let acl = PosixACL::read_acl(&file).unwrap();
+    assert_eq!(acl.get(User(55555)), None);
+    assert_eq!(acl.get(User(55556)), Some(0));
+    assert_eq!(acl.get(Group(55557)), Some(ACL_READ));
+    assert_eq!(acl.get(Mask), Some(ACL_READ | ACL_WRITE));
+    let default = PosixACL::read_default_acl(dir.path()).unwrap();
+    assert_eq!(default.get(User(55556)), Some(0));
+}
+
+#[test]
+fn by_name() {
+    let mut acl = PosixACL::new(0o640);
+    assert_eq!(acl.set_user_by_name("daemon", ACL_READ).unwrap(), None);
+    assert_eq!(acl.set_group_by_name("root", ACL_WRITE).unwrap(), None);
+    assert_eq!(acl.get(User(1)), Some(ACL_READ));
+    assert_eq!(acl.get(Group(0)), Some(ACL_WRITE));
+    assert_eq!(acl.get_user_by_name("daemon").unwrap(), Some(ACL_READ));
+    assert_eq!(acl.get_group_by_name("root").unwrap(), Some(ACL_WRITE));
+
+    assert_eq!(acl.remove_user_by_name("daemon").unwrap(), Some(ACL_READ));
+    assert_eq!(acl.remove_group_by_name("root").unwrap(), Some(ACL_WRITE));
+    assert_eq!(acl, PosixACL::new(0o640));
+
+    let err = acl.set_user_by_name("no-such-user", ACL_READ).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Error parsing ACL: Unknown user \"no-such-user\""
+    );
+    let err = acl.get_group_by_name("no-such-group").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Error parsing ACL: Unknown group \"no-such-group\""
+    );
+    assert_eq!(acl, PosixACL::new(0o640));
+}
+
+#[test]
+fn numeric_text() {
+    let acl = full_fixture();
+    assert_eq!(
+        acl.as_numeric_text(),
+        "user::rw-\n\
+         user:0:rw-\n\
+         user:55555:---\n\
+         group::r--\n\
+         group:0:r--\n\
+         group:55555:---\n\
+         mask::rw-\n\
+         other::---\n"
+    );
+    assert_eq!(PosixACL::from_text(&acl.as_numeric_text()).unwrap(), acl);
+    assert_eq!(PosixACL::empty().as_numeric_text(), "");
+}
+
+#[test]
+fn dump_display() {
+    let dir = tempdir().unwrap();
+    set_permissions(dir.path(), Permissions::from_mode(0o2750)).unwrap();
+    let entry = DumpEntry::read(dir.path()).unwrap();
+    let text = entry.to_string();
+    let relative = dir.path().strip_prefix("/").unwrap();
+    assert!(text.starts_with(&format!("# file: {}\n", relative.display())));
+    assert!(text.contains("# flags: -s-\nuser::rwx\n"));
+    assert!(text.ends_with("other::---\n\n"));
+
+    // write_to() keeps the absolute path
+    let mut out = Vec::new();
+    entry.write_to(&mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out)
+            .unwrap()
+            .replacen("# file: /", "# file: ", 1),
+        text
+    );
+}
+
+#[test]
+fn error_path() {
+    let dir = tempdir().unwrap();
+    let missing = dir.path().join("missing");
+    let file = test_file(&dir, "file", 0o640);
+
+    let err = PosixACL::read_acl(&missing).unwrap_err();
+    assert_eq!(err.path(), Some(missing.as_path()));
+    let err = full_fixture().write_default_acl(&file).unwrap_err();
+    assert_eq!(err.path(), Some(file.as_path()));
+    let err = PosixACL::delete_default_acl(&missing).unwrap_err();
+    assert_eq!(err.path(), Some(missing.as_path()));
+    let err = FileACLs::read_all(&missing).unwrap_err();
+    assert_eq!(err.path(), Some(missing.as_path()));
+
+    // The path given by the caller is reported, not the /proc path used internally
+    let err = PosixACL::read_default_acl_at(&std::fs::File::open(dir.path()).unwrap(), "file")
+        .unwrap_err();
+    assert_eq!(err.path(), Some(Path::new("file")));
+
+    // Validation errors have no path
+    assert_eq!(PosixACL::empty().write_acl(&file).unwrap_err().path(), None);
+}
+
+#[test]
+fn try_variants() {
+    let mut acl = PosixACL::new(0o640);
+    assert_eq!(acl.try_set(User(0), ACL_READ).unwrap(), None);
+    assert_eq!(acl.try_set(User(0), ACL_WRITE).unwrap(), Some(ACL_READ));
+    acl.try_fix_mask().unwrap();
+    assert_eq!(acl.try_entries().unwrap(), acl.entries());
+    assert_eq!(acl.try_as_text().unwrap(), acl.as_text());
+
+    // Failed set leaves the ACL unchanged
+    let before = acl.try_clone().unwrap();
+    assert_eq!(before, acl.clone());
+    let err = acl.try_set(Unknown(0x100), ACL_READ).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert_eq!(
+        err.to_string(),
+        "Error writing ACL: Invalid argument (os error 22)"
+    );
+    assert!(acl.try_set(Undefined, ACL_READ).is_err());
+    assert_eq!(acl, before);
+    assert_eq!(acl.len(), before.len());
+
+    assert_eq!(PosixACL::try_new(0o640).unwrap(), PosixACL::new(0o640));
+    assert!(PosixACL::try_with_capacity(10).unwrap().is_empty());
+    assert_eq!(acl.try_len().unwrap(), 5);
+    assert_eq!(acl.try_get(User(0)).unwrap(), Some(ACL_WRITE));
+    assert!(acl.try_contains(Mask).unwrap());
+    assert_eq!(acl.try_as_numeric_text().unwrap(), acl.as_numeric_text());
+
+    let mut copy = PosixACL::new(0o600);
+    assert!(copy.try_copy_entry_from(&acl, User(0)).unwrap());
+    assert!(!copy.try_copy_entry_from(&acl, User(1)).unwrap());
+    assert_eq!(copy.get(User(0)), Some(ACL_WRITE));
+    copy.try_canonicalize().unwrap();
+    copy.try_clear().unwrap();
+    assert!(copy.is_empty());
+
+    let mut acl = full_fixture();
+    assert_eq!(acl.try_compute_mask().unwrap(), acl.compute_mask());
+    assert!(!acl.try_mask_is_stale().unwrap());
+    assert_eq!(acl.try_effective(User(0)).unwrap(), acl.effective(User(0)));
+    assert_eq!(
+        acl.try_effective_entries().unwrap(),
+        acl.effective_entries()
+    );
+    assert_eq!(acl.try_reported_mode().unwrap(), 0o660);
+    assert_eq!(acl.try_as_tabular_text().unwrap(), acl.as_tabular_text());
+    assert_eq!(
+        acl.try_as_text_sorted_by_name().unwrap(),
+        acl.as_text_sorted_by_name()
+    );
+    assert_eq!(acl.try_add_perm(Other, ACL_READ).unwrap(), Some(0));
+    assert!(!acl.try_ensure(Other, ACL_READ).unwrap());
+    assert_eq!(
+        acl.try_remove_perm(Other, ACL_READ).unwrap(),
+        Some(ACL_READ)
+    );
+    for mut entry in acl.entries_mut() {
+        if entry.try_qualifier().unwrap() == User(55555) {
+            assert_eq!(entry.try_perm().unwrap(), 0);
+            entry.try_set_perm(ACL_READ).unwrap();
+        }
+    }
+    assert_eq!(acl.get(User(55555)), Some(ACL_READ));
+    assert!(acl
+        .try_remap_ids(|qual| if qual == User(55555) { User(1) } else { qual })
+        .unwrap());
+    acl.try_retain(|qual, _| qual != Group(55555)).unwrap();
+    assert_eq!(acl.try_strip_extended().unwrap().len(), 4);
+    acl.try_reset(0o751).unwrap();
+    assert_eq!(acl, PosixACL::new(0o751));
+}
+
+#[test]
+#[should_panic(expected = "Error writing ACL: Invalid argument")]
+fn set_unknown() {
+    PosixACL::new(0o640).set(Unknown(0x100), ACL_READ);
+}
+
+#[test]
+fn unsupported_error() {
+    // procfs does not support ACLs
+    let mut acl = PosixACL::new(0o644);
+    let err = acl.write_acl("/proc/self/comm").unwrap_err();
+    assert!(matches!(err, ACLError::Unsupported(_)), "{:?}", err);
+    assert_eq!(err.kind(), ErrorKind::Unsupported);
+    assert_eq!(
+        err.as_io_error().unwrap().raw_os_error(),
+        Some(libc::EOPNOTSUPP)
+    );
+    assert_eq!(err.path(), Some(Path::new("/proc/self/comm")));
+    assert_eq!(
+        err.to_string(),
+        "Error writing ACL: Operation not supported (os error 95)"
+    );
+}
+
+#[test]
+fn write_acl_if_changed() {
+    let dir = tempdir().unwrap();
+    let path = test_file(&dir, "file", 0o640);
+
+    // Same mode bits, nothing to write
+    let mut acl = PosixACL::new(0o640);
+    assert!(!acl.write_acl_if_changed(&path).unwrap());
+
+    acl.set(User(55555), ACL_READ);
+    assert!(acl.write_acl_if_changed(&path).unwrap());
+    assert!(!acl.write_acl_if_changed(&path).unwrap());
+    assert_eq!(PosixACL::read_acl(&path).unwrap(), acl);
+
+    acl.set(User(55555), ACL_RWX);
+    assert!(acl.write_acl_if_changed(&path).unwrap());
+    assert_eq!(PosixACL::read_acl(&path).unwrap().get(Mask), Some(ACL_RWX));
+
+    let mut acl = PosixACL::new(0o600);
+    assert!(acl.write_acl_if_changed(&path).unwrap());
+    assert_eq!(PosixACL::read_acl(&path).unwrap().reported_mode(), 0o600);
+    assert!(!acl.write_acl_if_changed(&path).unwrap());
+}
+
+#[test]
+fn write_acl_if_unchanged() {
+    let dir = tempdir().unwrap();
+    let path = test_file(&dir, "file", 0o640);
+    let original = PosixACL::read_acl(&path).unwrap();
+
+    let mut acl = original.clone();
+    acl.set(User(55555), ACL_READ);
+    acl.write_acl_if_unchanged(&path, &original).unwrap();
+
+    // Someone else's change is not overwritten
+    let mut other = original.clone();
+    other.set(Group(55555), ACL_READ);
+    let err = other.write_acl_if_unchanged(&path, &original).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Other);
+    assert_eq!(err.path(), Some(path.as_path()));
+    assert!(err.as_io_error().is_none());
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "Error writing ACL: {} was changed concurrently",
+            path.display()
+        )
+    );
+    assert_eq!(PosixACL::read_acl(&path).unwrap(), acl);
+}
+
+#[test]
+fn write_acl_or_chmod() {
+    let dir = tempdir().unwrap();
+    let path = test_file(&dir, "file", 0o600);
+    let mut acl = PosixACL::new(0o640);
+    acl.set(User(55555), ACL_READ);
+    assert!(!acl.write_acl_or_chmod(&path).unwrap());
+    assert_eq!(PosixACL::read_acl(&path).unwrap(), acl);
+
+    let err = acl
+        .write_acl_or_chmod(dir.path().join("missing"))
+        .unwrap_err();
+    assert_eq!(err.as_io_error().unwrap().kind(), ErrorKind::NotFound);
+}
+
+#[test]
+fn ensure() {
+    let mut acl = full_fixture();
+    assert!(!acl.ensure(User(0), ACL_READ));
+    assert!(!acl.ensure(User(55555), 0));
+    assert!(!acl.ensure(Group(1), 0));
+    assert!(!acl.contains(Group(1)));
+    assert_eq!(acl, full_fixture());
+
+    assert!(acl.ensure(User(0), ACL_EXECUTE));
+    assert_eq!(acl.get(User(0)), Some(ACL_RWX));
+    assert!(acl.ensure(Group(1), ACL_READ));
+    assert_eq!(acl.get(Group(1)), Some(ACL_READ));
+}
+
+#[test]
+fn builder() {
+    let acl = PosixACL::builder()
+        .mode(0o640)
+        .user(0, ACL_READ | ACL_WRITE)
+        .user(55555, 0)
+        .group(0, ACL_READ)
+        .group(55555, 0)
+        .mask(ACL_READ | ACL_WRITE)
+        .build()
+        .unwrap();
+    assert_eq!(acl, full_fixture());
+
+    // Mask is required with named entries
+    let err = PosixACL::builder().mode(0o640).user(0, ACL_READ).build();
+    assert_eq!(
+        err.unwrap_err().validation_reason(),
+        Some(ValidationReason::MissingEntry)
+    );
+    let acl = PosixACL::builder()
+        .mode(0o640)
+        .user_name("root", ACL_RWX)
+        .mask(0)
+        .mask_auto()
+        .build()
+        .unwrap();
+    assert_eq!(acl.get(User(0)), Some(ACL_RWX));
+    assert_eq!(acl.get(Mask), Some(ACL_RWX));
+
+    let err = PosixACL::builder()
+        .mode(0o640)
+        .group_name("no-such-group-posix-acl", ACL_READ)
+        .user_name("no-such-user-posix-acl", ACL_READ)
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Error parsing ACL: Unknown group \"no-such-group-posix-acl\""
+    );
+    let err = PosixACL::builder().mode(0o640).entry(Undefined, 0).build();
+    assert_eq!(
+        err.unwrap_err().validation_reason(),
+        Some(ValidationReason::InvalidEntry)
+    );
+}
+
+#[test]
+fn presets() {
+    let acl = presets::private(55555, ACL_READ | ACL_WRITE);
+    assert_eq!(
+        acl.as_text(),
+        "user::rw-\nuser:55555:rw-\ngroup::---\nmask::rw-\nother::---\n"
+    );
+    let acl = presets::group_shared(55555, ACL_RWX);
+    assert_eq!(
+        acl.as_text(),
+        "user::rwx\ngroup::---\ngroup:55555:rwx\nmask::rwx\nother::---\n"
+    );
+    let acl = presets::group_readable(55555, ACL_READ | ACL_WRITE);
+    assert_eq!(
+        acl.as_text(),
+        "user::rw-\ngroup::---\ngroup:55555:r--\nmask::r--\nother::---\n"
+    );
+    assert_eq!(presets::world_readable(ACL_RWX), PosixACL::new(0o755));
+    assert_eq!(
+        presets::world_readable(ACL_READ | ACL_WRITE),
+        PosixACL::new(0o644)
+    );
+    for acl in [
+        presets::private(0, ACL_RWX),
+        presets::group_shared(0, ACL_RWX),
+        presets::group_readable(0, ACL_RWX),
+        presets::world_readable(ACL_RWX),
+    ] {
+        acl.validate().unwrap();
+    }
+}
+
+#[test]
+fn audit() {
+    let found = full_fixture().audit();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].kind, FindingKind::RootEntry);
+
+    let mut acl = PosixACL::new(0o642);
+    acl.set(User(0), ACL_READ);
+    acl.set(User(55555), ACL_RWX);
+    acl.set(Group(55555), ACL_READ);
+    acl.set(Group(0), 0);
+    acl.set(Mask, ACL_RWX);
+    let found: Vec<_> = acl
+        .audit()
+        .into_iter()
+        .map(|finding| (finding.kind, finding.entry.qual, finding.entry.perm))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (FindingKind::RootEntry, User(0), ACL_READ),
+            (FindingKind::UnknownId, User(55555), ACL_RWX),
+            (FindingKind::UnknownId, Group(55555), ACL_READ),
+            (FindingKind::OtherWritable, Other, ACL_WRITE),
+        ]
+    );
+
+    // Findings use effective permissions
+    acl.set(Mask, ACL_READ);
+    let kinds: Vec<_> = acl
+        .audit()
+        .into_iter()
+        .map(|finding| finding.kind)
+        .collect();
+    assert!(!kinds.contains(&FindingKind::WideMask));
+    acl.remove(User(55555));
+    assert_eq!(acl.compute_mask(), ACL_READ);
+    acl.set(Mask, ACL_READ | ACL_EXECUTE);
+    let wide = acl.audit().pop().unwrap();
+    assert_eq!(wide.kind, FindingKind::WideMask);
+    assert_eq!(wide.entry.perm, ACL_EXECUTE);
+}
+
+#[test]
+fn audit_tree() {
+    let dir = tempdir().unwrap();
+    let path = test_file(&dir, "file", 0o644);
+    set_permissions(&path, Permissions::from_mode(0o646)).unwrap();
+    let mut acl = PosixACL::new(0o750);
+    acl.set(User(55555), ACL_READ);
+    acl.write_default_acl(dir.path()).unwrap();
+
+    let (findings, report) = posix_acl::audit::audit_tree(dir.path());
+    assert!(report.is_ok());
+    assert_eq!(report.processed, 2);
+    let found: Vec<_> = findings
+        .iter()
+        .map(|found| (found.path.as_path(), found.default, found.finding.kind))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (dir.path(), true, FindingKind::UnknownId),
+            (path.as_path(), false, FindingKind::OtherWritable),
+        ]
+    );
+}
+
+#[test]
+fn entries_mut() {
+    let mut acl = full_fixture();
+    let mut entries: Vec<_> = acl.entries_mut().collect();
+    assert_eq!(entries.len(), 8);
+    for entry in &mut entries {
+        if let User(_) | Group(_) = entry.qualifier() {
+            entry.set_perm(entry.perm() | ACL_EXECUTE);
+        }
+    }
+    assert_eq!(acl.get(User(0)), Some(ACL_RWX));
+    assert_eq!(acl.get(Group(55555)), Some(ACL_EXECUTE));
+    assert_eq!(acl.get(UserObj), Some(ACL_READ | ACL_WRITE));
+
+    let expected: Vec<_> = acl.iter().collect();
+    let actual: Vec<_> = acl
+        .entries_mut()
+        .map(|entry| ACLEntry {
+            qual: entry.qualifier(),
+            perm: entry.perm(),
+        })
+        .collect();
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn send_to_thread() {
+    let acl = full_fixture();
+    let text = std::thread::spawn(move || acl.as_text()).join().unwrap();
+    assert_eq!(text, full_fixture().as_text());
+}
+
+#[test]
+#[allow(clippy::mutable_key_type)]
+fn hash() {
+    let mut reversed = PosixACL::empty();
+    let mut entries = full_fixture().entries();
+    entries.reverse();
+    reversed.extend(entries);
+
+    let mut groups: HashMap<PosixACL, usize> = HashMap::new();
+    for acl in [full_fixture(), reversed, PosixACL::new(0o640)] {
+        *groups.entry(acl).or_default() += 1;
+    }
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[&full_fixture()], 2);
+    assert_eq!(groups[&PosixACL::new(0o640)], 1);
+
+    let entries: HashSet<ACLEntry> = full_fixture().iter().chain(&full_fixture()).collect();
+    assert_eq!(entries.len(), 8);
+}
+
+#[test]
+fn entry_ord() {
+    let mut entries = full_fixture().entries();
+    entries.reverse();
+    entries.sort();
+    assert_eq!(entries, full_fixture().entries_sorted());
+    let quals: Vec<_> = entries.iter().map(|entry| entry.qual).collect();
+    assert_eq!(
+        quals,
+        vec![
+            UserObj,
+            User(0),
+            User(55555),
+            GroupObj,
+            Group(0),
+            Group(55555),
+            Mask,
+            Other
+        ]
+    );
+
+    let entry = |qual, perm| ACLEntry { qual, perm };
+    assert!(entry(User(0), ACL_RWX) < entry(User(1), 0));
+    assert!(entry(User(0), ACL_EXECUTE) < entry(User(0), ACL_READ));
+    assert!(entry(Other, 0) < entry(Undefined, 0));
+}
+
+#[test]
+fn compute_mask() {
+    assert_eq!(PosixACL::empty().compute_mask(), 0);
+    let mut acl = PosixACL::new(0o751);
+    assert_eq!(acl.compute_mask(), ACL_READ | ACL_EXECUTE);
+    acl.set(Group(0), ACL_WRITE);
+    acl.set(Mask, 0);
+    assert_eq!(acl.compute_mask(), ACL_RWX);
+    // Not modified
+    assert_eq!(acl.get(Mask), Some(0));
+    assert!(acl.mask_is_stale());
+
+    acl.fix_mask();
+    assert_eq!(acl.get(Mask), Some(acl.compute_mask()));
+    assert!(!acl.mask_is_stale());
+
+    // Missing mask is only stale with named entries
+    let mut acl = PosixACL::new(0o640);
+    assert!(!acl.mask_is_stale());
+    acl.set(User(0), ACL_READ);
+    assert!(acl.mask_is_stale());
+}
+
+#[test]
+fn symbolic_mode() {
+    let mut acl = PosixACL::new(0o640);
+    acl.apply_symbolic_mode("u+x,g+w,o=r").unwrap();
+    assert_eq!(acl.reported_mode(), 0o764);
+    acl.apply_symbolic_mode("a-w").unwrap();
+    assert_eq!(acl.reported_mode(), 0o544);
+    acl.apply_symbolic_mode("=rw").unwrap();
+    assert_eq!(acl.reported_mode(), 0o666);
+    acl.apply_symbolic_mode("ug=rwx-w,o=").unwrap();
+    assert_eq!(acl.reported_mode(), 0o550);
+    assert_eq!(acl.get(Mask), None);
+
+    // Group class changes affect the mask, named entries are preserved.
+    let mut acl = full_fixture();
+    acl.apply_symbolic_mode("g-w").unwrap();
+    assert_eq!(acl.get(Mask), Some(ACL_READ));
+    assert_eq!(acl.get(GroupObj), Some(ACL_READ));
+    assert_eq!(acl.get(User(0)), Some(ACL_READ | ACL_WRITE));
+    assert_eq!(acl.reported_mode(), 0o640);
+}
+
+#[test]
+fn symbolic_mode_invalid() {
+    let mut acl = PosixACL::new(0o640);
+    for mode in &["", "u", "u+rq", "u+r,", "z+r", "u+r g+r"] {
+        let err = acl.apply_symbolic_mode(mode).unwrap_err();
+        assert!(matches!(err, ACLError::ParseError(_)), "{}", mode);
+    }
+    assert_eq!(
+        acl.apply_symbolic_mode("u+r,g*w").unwrap_err().to_string(),
+        "Error parsing ACL: Invalid symbolic mode \"g*w\""
+    );
+    // Not modified
+    assert_eq!(acl, PosixACL::new(0o640));
+}
+
+/// Set an extended attribute of `path`. Filesystems used for tests support ACLs, so shadow
+/// attributes are stored directly.
+fn set_xattr(path: &Path, name: &str, data: &[u8]) {
+    let c_path = CString::new(path.as_os_str().as_bytes()).unwrap();
+    let c_name = CString::new(name).unwrap();
+    let ret = unsafe {
+        libc::setxattr(
+            c_path.as_ptr(),
+            c_name.as_ptr(),
+            data.as_ptr().cast(),
+            data.len(),
+            0,
+        )
+    };
+    assert_eq!(ret, 0, "{}", std::io::Error::last_os_error());
+}
+
+#[test]
+fn shadow_acl() {
+    let dir = tempdir().unwrap();
+    let path = test_file(&dir, "file", 0o640);
+    assert_eq!(
+        PosixACL::read_acl_shadowed(&path).unwrap(),
+        PosixACL::new(0o640)
+    );
+
+    set_xattr(
+        &path,
+        posix_acl::xattr::SHADOW_ACCESS,
+        &full_fixture().to_xattr(),
+    );
+    set_permissions(&path, Permissions::from_mode(0o660)).unwrap();
+    assert_eq!(PosixACL::read_acl_shadowed(&path).unwrap(), full_fixture());
+    // The real ACL is unaffected
+    assert_eq!(PosixACL::read_acl(&path).unwrap(), PosixACL::new(0o660));
+
+    // Base entries follow the file mode
+    set_permissions(&path, Permissions::from_mode(0o604)).unwrap();
+    let acl = PosixACL::read_acl_shadowed(&path).unwrap();
+    assert_eq!(acl.reported_mode(), 0o604);
+    assert_eq!(acl.get(GroupObj), Some(ACL_READ));
+    assert_eq!(acl.get(User(0)), Some(ACL_READ | ACL_WRITE));
+
+    // Writing the real ACL removes the stale shadow ACL
+    let mut acl = full_fixture();
+    acl.set(User(55555), ACL_EXECUTE);
+    acl.write_acl_shadowed(&path).unwrap();
+    assert_eq!(PosixACL::read_acl(&path).unwrap(), acl);
+    assert_eq!(PosixACL::read_acl_shadowed(&path).unwrap(), acl);
+
+    set_xattr(&path, posix_acl::xattr::SHADOW_ACCESS, b"junk");
+    let err = PosixACL::read_acl_shadowed(&path).unwrap_err();
+    assert!(matches!(err, ACLError::ParseError(_)));
+}
+
+#[test]
+fn shadow_default_acl() {
+    let dir = tempdir().unwrap();
+    assert_eq!(
+        PosixACL::read_default_acl_shadowed(dir.path()).unwrap(),
+        PosixACL::empty()
+    );
+    set_xattr(
+        dir.path(),
+        posix_acl::xattr::SHADOW_DEFAULT,
+        &full_fixture().to_xattr(),
+    );
+    assert_eq!(
+        PosixACL::read_default_acl_shadowed(dir.path()).unwrap(),
+        full_fixture()
+    );
+    full_fixture()
+        .write_default_acl_shadowed(dir.path())
+        .unwrap();
+    assert_eq!(
+        PosixACL::read_default_acl(dir.path()).unwrap(),
+        full_fixture()
+    );
+}
+
+#[test]
+fn capabilities() {
+    let caps = Capabilities::detect();
+    // Linux libacl provides all extension functions.
+    assert!(caps.acl_check);
+    assert!(caps.acl_cmp);
+    assert!(caps.acl_entries);
+    assert!(caps.acl_equiv_mode);
+    assert!(caps.acl_extended_fd);
+    assert!(caps.acl_extended_file);
+    assert!(caps.acl_extended_file_nofollow);
+    assert!(caps.acl_from_mode);
+    assert!(caps.acl_get_perm);
+    assert!(caps.acl_to_any_text);
+    let version = caps.version.expect("libacl version");
+    assert!(version.starts_with("1."), "{}", version);
+}
+
+#[cfg(feature = "tempfile")]
+#[test]
+fn temp_dir_with_acl() {
+    use posix_acl::TempDirWithACL;
+    let parent = tempdir().unwrap();
+    let dir = TempDirWithACL::new()
+        .prefix("acl-")
+        .access_acl(full_fixture())
+        .default_acl(PosixACL::new(0o750))
+        .create_in(parent.path())
+        .unwrap();
+
+    assert!(dir
+        .path()
+        .file_name()
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .starts_with("acl-"));
+    assert_eq!(PosixACL::read_acl(dir.path()).unwrap(), full_fixture());
+    let mut expected = PosixACL::new(0o750);
+    expected.fix_mask();
+    assert_eq!(PosixACL::read_default_acl(dir.path()).unwrap(), expected);
+}
+
+#[cfg(feature = "tempfile")]
+#[test]
+fn temp_file_with_acl() {
+    use posix_acl::NamedTempFileWithACL;
+    let parent = tempdir().unwrap();
+    let file = NamedTempFileWithACL::new()
+        .access_acl(full_fixture())
+        .create_in(parent.path())
+        .unwrap();
+    assert_eq!(PosixACL::read_acl(file.path()).unwrap(), full_fixture());
+
+    // Invalid ACL: the file is cleaned up.
+    let err = NamedTempFileWithACL::new()
+        .access_acl(PosixACL::empty())
+        .create_in(parent.path())
+        .unwrap_err();
+    assert_eq!(err.to_string(), "ACL failed validation");
+    assert_eq!(parent.path().read_dir().unwrap().count(), 1);
+}